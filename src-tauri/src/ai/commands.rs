@@ -1,10 +1,16 @@
 // src-tauri/src/ai/commands.rs
 
 use crate::ai::{
+    context_pack::{self, ProjectContextPack},
+    credential_profiles::{self, CredentialProfileSummary, ProjectProfileAssignment},
     error::{AiError, AiErrorPayload},
-    providers, secret_store,
+    key_rotation::{self, KeyRotation},
+    providers,
+    secret_bundle::{self, BundleSummary},
+    secret_store::{self, KeyPersistence},
     types::{AiRequest, AiResponse},
 };
+use crate::secret_vault::{self, VaultStatus};
 
 use serde::Deserialize;
 
@@ -21,19 +27,166 @@ pub fn ai_clear_api_key(providerId: String) -> Result<(), AiErrorPayload> {
     secret_store::clear_api_key(&providerId).map_err(AiErrorPayload::from)
 }
 
+/// `projectPath` is optional; when given, a key from the project's credential
+/// profile also counts.
 #[tauri::command]
-pub fn ai_has_api_key(providerId: String) -> Result<bool, AiErrorPayload> {
-    let has = secret_store::get_api_key(&providerId)
+pub fn ai_has_api_key(
+    providerId: String,
+    project_path: Option<String>,
+) -> Result<bool, AiErrorPayload> {
+    let has = secret_store::get_api_key(&providerId, project_path.as_deref())
         .map_err(AiErrorPayload::from)?
         .is_some();
     Ok(has)
 }
 
-/// Returns true if the key exists in the OS keyring (persisted),
-/// false if it is missing from keyring.
+/// Reports whether the key survives a restart and which backend holds it:
+/// `os_keyring`, `encrypted_file` (unlocked vault) or `session` (not persisted).
 #[tauri::command]
-pub fn ai_is_key_persisted(providerId: String) -> Result<bool, AiErrorPayload> {
-    secret_store::is_persisted_in_keyring(&providerId).map_err(AiErrorPayload::from)
+pub fn ai_is_key_persisted(providerId: String) -> Result<KeyPersistence, AiErrorPayload> {
+    secret_store::key_persistence(&providerId).map_err(AiErrorPayload::from)
+}
+
+/// Status of the encrypted key vault used when no OS keyring is reachable.
+#[tauri::command]
+pub fn ai_key_vault_status(app: tauri::AppHandle) -> Result<VaultStatus, AiErrorPayload> {
+    let path = secret_vault::default_vault_path(&app).map_err(vault_payload)?;
+    secret_vault::status(&path).map_err(vault_payload)
+}
+
+/// Unlock the encrypted key vault for this session, creating it on first use.
+#[tauri::command]
+pub fn ai_unlock_key_vault(
+    app: tauri::AppHandle,
+    passphrase: String,
+) -> Result<VaultStatus, AiErrorPayload> {
+    let path = secret_vault::default_vault_path(&app).map_err(vault_payload)?;
+    secret_vault::unlock(&path, &passphrase)
+        .map_err(|e| AiErrorPayload::from(AiError::auth(format!("Encrypted key vault: {e}"))))?;
+    secret_vault::status(&path).map_err(vault_payload)
+}
+
+#[tauri::command]
+pub fn ai_lock_key_vault(app: tauri::AppHandle) -> Result<VaultStatus, AiErrorPayload> {
+    let path = secret_vault::default_vault_path(&app).map_err(vault_payload)?;
+    secret_vault::lock().map_err(vault_payload)?;
+    secret_vault::status(&path).map_err(vault_payload)
+}
+
+// -------------------- Project credential profiles --------------------
+// None of these return key values; listings only name providers that have a key.
+// Tauri maps the snake_case args to camelCase for JS ({ profileName, providerId, ... }).
+
+#[tauri::command]
+pub fn ai_set_profile_api_key(
+    profile_name: String,
+    provider_id: String,
+    api_key: String,
+) -> Result<(), AiErrorPayload> {
+    secret_store::set_profile_api_key(&profile_name, &provider_id, &api_key)
+        .map_err(AiErrorPayload::from)
+}
+
+#[tauri::command]
+pub fn ai_clear_profile_api_key(
+    profile_name: String,
+    provider_id: String,
+) -> Result<(), AiErrorPayload> {
+    secret_store::clear_profile_api_key(&profile_name, &provider_id).map_err(AiErrorPayload::from)
+}
+
+#[tauri::command]
+pub fn ai_list_credential_profiles() -> Result<Vec<CredentialProfileSummary>, AiErrorPayload> {
+    credential_profiles::list_profiles().map_err(profile_payload)
+}
+
+#[tauri::command]
+pub fn ai_list_project_profiles() -> Result<Vec<ProjectProfileAssignment>, AiErrorPayload> {
+    credential_profiles::list_assignments().map_err(profile_payload)
+}
+
+#[tauri::command]
+pub fn ai_assign_project_profile(
+    project_path: String,
+    profile_name: String,
+) -> Result<ProjectProfileAssignment, AiErrorPayload> {
+    credential_profiles::assign_project(&project_path, &profile_name)
+        .map_err(|e| AiErrorPayload::from(AiError::invalid(e)))
+}
+
+/// Returns false when the project had no profile assigned.
+#[tauri::command]
+pub fn ai_remove_project_profile(project_path: String) -> Result<bool, AiErrorPayload> {
+    credential_profiles::unassign_project(&project_path).map_err(profile_payload)
+}
+
+// -------------------- Key export / import / rotation --------------------
+
+/// Write every stored key (global and per profile) to a passphrase-encrypted file.
+#[tauri::command]
+pub fn ai_export_secrets(
+    path: String,
+    passphrase: String,
+) -> Result<BundleSummary, AiErrorPayload> {
+    secret_bundle::export_to_file(&path, &passphrase).map_err(AiErrorPayload::from)
+}
+
+/// Import keys from an exported file. Existing keys are kept unless `overwrite`.
+#[tauri::command]
+pub fn ai_import_secrets(
+    path: String,
+    passphrase: String,
+    overwrite: Option<bool>,
+) -> Result<BundleSummary, AiErrorPayload> {
+    secret_bundle::import_from_file(&path, &passphrase, overwrite.unwrap_or(false))
+        .map_err(AiErrorPayload::from)
+}
+
+/// Replace a key only after the provider accepts it. `endpoint` overrides the
+/// provider's default API root; `profile_name` targets a credential profile.
+#[tauri::command]
+pub fn ai_rotate_api_key(
+    provider_id: String,
+    new_api_key: String,
+    endpoint: Option<String>,
+    profile_name: Option<String>,
+) -> Result<KeyRotation, AiErrorPayload> {
+    key_rotation::rotate_api_key(
+        &provider_id,
+        &new_api_key,
+        endpoint.as_deref(),
+        profile_name.as_deref(),
+    )
+    .map_err(AiErrorPayload::from)
+}
+
+// -------------------- Project file context --------------------
+
+/// Pack relevant project files into a prompt context block. `focus_paths`
+/// (project-relative, e.g. the open file) go first; `query` terms boost
+/// matching paths. The result lists exactly which files were included.
+#[tauri::command]
+pub fn ai_pack_project_context(
+    project_path: String,
+    token_budget: Option<usize>,
+    focus_paths: Option<Vec<String>>,
+    query: Option<String>,
+) -> Result<ProjectContextPack, AiErrorPayload> {
+    context_pack::pack_project_context(
+        &project_path,
+        token_budget,
+        focus_paths.as_deref().unwrap_or_default(),
+        query.as_deref(),
+    )
+    .map_err(AiErrorPayload::from)
+}
+
+fn profile_payload(e: String) -> AiErrorPayload {
+    AiErrorPayload::from(AiError::unknown(format!("Credential profiles: {e}")))
+}
+
+fn vault_payload(e: String) -> AiErrorPayload {
+    AiErrorPayload::from(AiError::unknown(format!("Encrypted key vault: {e}")))
 }
 
 #[tauri::command]
@@ -0,0 +1,515 @@
+// src-tauri/src/ai/context_pack.rs
+//
+// Packs project file contents into a prompt context block under a token
+// budget. Files are walked with .gitignore rules applied, secrets and binaries
+// are never read into the block, and the returned manifest lists exactly which
+// files went in (and why others did not).
+
+use std::path::{Path, PathBuf};
+
+use ignore::WalkBuilder;
+use serde::Serialize;
+
+use crate::ai::error::AiError;
+use crate::project_files::is_env_file;
+use crate::supabase_autopilot::inspection::read_bounded_text;
+
+pub const DEFAULT_TOKEN_BUDGET: usize = 8_000;
+const MIN_TOKEN_BUDGET: usize = 256;
+const MAX_TOKEN_BUDGET: usize = 200_000;
+
+const MAX_FILE_BYTES: u64 = 160_000;
+const MAX_WALK_ENTRIES: usize = 5_000;
+const MAX_DEPTH: usize = 12;
+const MAX_SKIPPED_LISTED: usize = 200;
+/// A focused file that does not fit is truncated rather than dropped, as long
+/// as at least this many tokens remain.
+const MIN_TRUNCATED_TOKENS: usize = 200;
+
+const SKIPPED_DIRS: &[&str] = &[
+    "node_modules",
+    ".git",
+    "dist",
+    "build",
+    "out",
+    "target",
+    ".next",
+    ".nuxt",
+    ".svelte-kit",
+    ".turbo",
+    "coverage",
+    "vendor",
+];
+
+const LOCK_FILES: &[&str] = &[
+    "pnpm-lock.yaml",
+    "package-lock.json",
+    "yarn.lock",
+    "bun.lock",
+    "bun.lockb",
+    "Cargo.lock",
+    "composer.lock",
+    "poetry.lock",
+];
+
+const SECRET_FILE_NAMES: &[&str] = &[
+    ".npmrc",
+    ".pypirc",
+    ".netrc",
+    "id_rsa",
+    "id_ed25519",
+    "credentials.json",
+    "service-account.json",
+    "secrets.vault",
+];
+
+const SECRET_EXTENSIONS: &[&str] = &["pem", "key", "p12", "pfx", "keystore", "jks"];
+
+const BINARY_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "webp", "ico", "bmp", "avif", "tiff", "psd", "mp3", "mp4", "wav",
+    "ogg", "webm", "mov", "avi", "pdf", "zip", "gz", "tgz", "bz2", "xz", "7z", "rar", "tar", "exe",
+    "dll", "so", "dylib", "wasm", "class", "jar", "woff", "woff2", "ttf", "otf", "eot", "sqlite",
+    "db", "bin",
+];
+
+/// Files a reader usually needs first to understand a project.
+const ORIENTATION_FILES: &[&str] = &[
+    "package.json",
+    "README.md",
+    "index.html",
+    "vite.config.js",
+    "vite.config.ts",
+    "next.config.js",
+    "next.config.mjs",
+    "tsconfig.json",
+    "Cargo.toml",
+    "pyproject.toml",
+    "app.json",
+];
+
+const SOURCE_EXTENSIONS: &[&str] = &[
+    "js", "jsx", "ts", "tsx", "mjs", "cjs", "vue", "svelte", "astro", "css", "scss", "html", "rs",
+    "py", "go", "php", "rb", "java", "kt", "swift", "sql", "json", "toml", "yaml", "yml", "md",
+];
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PackedFile {
+    pub path: String,
+    pub bytes: usize,
+    pub estimated_tokens: usize,
+    pub truncated: bool,
+    /// Why the file was picked: "focus", "query", "orientation" or "source".
+    pub reason: &'static str,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SkippedFile {
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ProjectContextPack {
+    /// Ready-to-send prompt block.
+    pub context: String,
+    pub token_budget: usize,
+    pub estimated_tokens: usize,
+    /// Manifest of exactly what is in `context`, in order.
+    pub files: Vec<PackedFile>,
+    /// Files left out and why; capped, see `skipped_total`.
+    pub skipped: Vec<SkippedFile>,
+    pub skipped_total: usize,
+}
+
+struct Candidate {
+    rel: String,
+    abs: PathBuf,
+    score: i64,
+    reason: &'static str,
+}
+
+/// Rough prompt size; about four characters per token for code and English.
+fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+pub fn pack_project_context(
+    project_path: &str,
+    token_budget: Option<usize>,
+    focus_paths: &[String],
+    query: Option<&str>,
+) -> Result<ProjectContextPack, AiError> {
+    let root = validate_project_path(project_path)?;
+    let budget = token_budget
+        .unwrap_or(DEFAULT_TOKEN_BUDGET)
+        .clamp(MIN_TOKEN_BUDGET, MAX_TOKEN_BUDGET);
+
+    let focus: Vec<String> = focus_paths.iter().map(|p| normalize_rel(p)).collect();
+    let terms = query_terms(query.unwrap_or(""));
+
+    let mut skipped = Vec::new();
+    let mut candidates = collect_candidates(&root, &focus, &terms, &mut skipped);
+    candidates.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.rel.cmp(&b.rel)));
+
+    let root_name = root
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "project".to_string());
+    let header = format!("Project files from {root_name} (paths relative to the project root):\n");
+    let mut context = header.clone();
+    let mut used = estimate_tokens(&header);
+    let mut files = Vec::new();
+
+    for candidate in candidates {
+        // One token for the blank line between blocks.
+        let remaining = budget.saturating_sub(used + 1);
+        if remaining == 0 {
+            skipped.push(skip(&candidate.rel, "over the token budget"));
+            continue;
+        }
+
+        let content = match read_bounded_text(&candidate.abs, MAX_FILE_BYTES) {
+            Ok(content) => content,
+            Err(e) => {
+                skipped.push(skip(&candidate.rel, &e));
+                continue;
+            }
+        };
+        if content.contains('\0') {
+            skipped.push(skip(&candidate.rel, "binary content"));
+            continue;
+        }
+
+        let block = file_block(&candidate.rel, &content, false);
+        let tokens = estimate_tokens(&block);
+        let (block, tokens, truncated) = if tokens <= remaining {
+            (block, tokens, false)
+        } else if candidate.reason == "focus" && remaining >= MIN_TRUNCATED_TOKENS {
+            let block = truncated_block(&candidate.rel, &content, remaining);
+            let tokens = estimate_tokens(&block);
+            (block, tokens, true)
+        } else {
+            skipped.push(skip(&candidate.rel, "over the token budget"));
+            continue;
+        };
+
+        context.push('\n');
+        context.push_str(&block);
+        used += tokens + 1;
+        files.push(PackedFile {
+            path: candidate.rel,
+            bytes: content.len(),
+            estimated_tokens: tokens,
+            truncated,
+            reason: candidate.reason,
+        });
+    }
+
+    let skipped_total = skipped.len();
+    skipped.truncate(MAX_SKIPPED_LISTED);
+
+    Ok(ProjectContextPack {
+        context,
+        token_budget: budget,
+        estimated_tokens: used,
+        files,
+        skipped,
+        skipped_total,
+    })
+}
+
+fn validate_project_path(path: &str) -> Result<PathBuf, AiError> {
+    let trimmed = path.trim();
+    if trimmed.is_empty() {
+        return Err(AiError::invalid("Project path is empty"));
+    }
+    let pb = PathBuf::from(trimmed);
+    if !pb.is_dir() {
+        return Err(AiError::invalid(format!(
+            "Project path is not a folder: {trimmed}"
+        )));
+    }
+    Ok(pb)
+}
+
+fn collect_candidates(
+    root: &Path,
+    focus: &[String],
+    terms: &[String],
+    skipped: &mut Vec<SkippedFile>,
+) -> Vec<Candidate> {
+    let walker = WalkBuilder::new(root)
+        .max_depth(Some(MAX_DEPTH))
+        // Apply .gitignore even when the folder is not (yet) a git repository.
+        .require_git(false)
+        .filter_entry(|entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            !(is_dir && SKIPPED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref()))
+        })
+        .build();
+
+    let mut candidates = Vec::new();
+    for entry in walker.flatten().take(MAX_WALK_ENTRIES) {
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let Ok(rel) = entry.path().strip_prefix(root) else {
+            continue;
+        };
+        let rel = rel.to_string_lossy().replace('\\', "/");
+
+        if let Some(reason) = exclusion_reason(&rel) {
+            skipped.push(skip(&rel, reason));
+            continue;
+        }
+
+        let (score, reason) = score_path(&rel, focus, terms);
+        candidates.push(Candidate {
+            abs: entry.path().to_path_buf(),
+            rel,
+            score,
+            reason,
+        });
+    }
+
+    // Focus files are included even when hidden or ignored, but never secrets
+    // and never anything a symlink resolves to outside the project.
+    let Ok(canonical_root) = root.canonicalize() else {
+        return candidates;
+    };
+    for rel in focus {
+        if candidates.iter().any(|c| &c.rel == rel) || exclusion_reason(rel).is_some() {
+            continue;
+        }
+        if rel.split('/').any(|part| part.is_empty() || part == "..") {
+            continue;
+        }
+        let Ok(abs) = root.join(rel).canonicalize() else {
+            continue;
+        };
+        let Ok(target) = abs.strip_prefix(&canonical_root) else {
+            continue;
+        };
+        if exclusion_reason(&target.to_string_lossy().replace('\\', "/")).is_some() {
+            continue;
+        }
+        if abs.is_file() {
+            candidates.push(Candidate {
+                rel: rel.clone(),
+                abs,
+                score: i64::MAX,
+                reason: "focus",
+            });
+        }
+    }
+
+    candidates
+}
+
+/// Secrets, binaries and lockfiles are never packed.
+fn exclusion_reason(rel: &str) -> Option<&'static str> {
+    let name = rel.rsplit('/').next().unwrap_or(rel);
+    let lower = name.to_ascii_lowercase();
+    let extension = lower.rsplit_once('.').map(|(_, ext)| ext).unwrap_or("");
+
+    if is_env_file(&lower) || SECRET_FILE_NAMES.contains(&lower.as_str()) {
+        return Some("may contain secrets");
+    }
+    if SECRET_EXTENSIONS.contains(&extension) {
+        return Some("may contain secrets");
+    }
+    if BINARY_EXTENSIONS.contains(&extension) {
+        return Some("binary file");
+    }
+    if LOCK_FILES.contains(&name) || lower.ends_with(".min.js") || lower.ends_with(".map") {
+        return Some("generated file");
+    }
+    None
+}
+
+fn score_path(rel: &str, focus: &[String], terms: &[String]) -> (i64, &'static str) {
+    if focus.iter().any(|f| f == rel) {
+        return (i64::MAX, "focus");
+    }
+
+    let lower = rel.to_ascii_lowercase();
+    let name = lower.rsplit('/').next().unwrap_or(&lower);
+    let depth = rel.matches('/').count() as i64;
+    let extension = name.rsplit_once('.').map(|(_, ext)| ext).unwrap_or("");
+
+    let term_hits = terms.iter().filter(|t| lower.contains(t.as_str())).count() as i64;
+    if term_hits > 0 {
+        return (10_000 + term_hits * 100 - depth, "query");
+    }
+    if ORIENTATION_FILES
+        .iter()
+        .any(|f| f.eq_ignore_ascii_case(rel))
+    {
+        return (5_000, "orientation");
+    }
+
+    let mut score = 1_000 - depth * 10;
+    if !SOURCE_EXTENSIONS.contains(&extension) {
+        score -= 500;
+    }
+    if lower.starts_with("src/") || lower.starts_with("app/") {
+        score += 100;
+    }
+    if name.starts_with("main.") || name.starts_with("app.") || name.starts_with("index.") {
+        score += 50;
+    }
+    if lower.contains(".test.") || lower.contains(".spec.") || lower.contains("__tests__") {
+        score -= 200;
+    }
+    (score, "source")
+}
+
+fn query_terms(query: &str) -> Vec<String> {
+    query
+        .split(|c: char| !c.is_alphanumeric() && c != '_' && c != '-')
+        .filter(|t| t.len() >= 3)
+        .map(|t| t.to_ascii_lowercase())
+        .collect()
+}
+
+fn normalize_rel(path: &str) -> String {
+    let path = path.trim().replace('\\', "/");
+    path.strip_prefix("./").unwrap_or(&path).to_string()
+}
+
+fn fence_language(rel: &str) -> &str {
+    rel.rsplit_once('.').map(|(_, ext)| ext).unwrap_or("")
+}
+
+fn file_block(rel: &str, content: &str, truncated: bool) -> String {
+    let fence = if content.contains("```") {
+        "````"
+    } else {
+        "```"
+    };
+    let mut block = format!("### File: {rel}\n{fence}{}\n{content}", fence_language(rel));
+    if !content.ends_with('\n') {
+        block.push('\n');
+    }
+    if truncated {
+        block.push_str("... (truncated)\n");
+    }
+    block.push_str(fence);
+    block.push('\n');
+    block
+}
+
+fn truncated_block(rel: &str, content: &str, token_limit: usize) -> String {
+    let overhead = estimate_tokens(&file_block(rel, "", true));
+    // Leave a token of slack for rounding in `estimate_tokens`.
+    let max_chars = token_limit.saturating_sub(overhead + 1) * 4;
+    // Cut at a line boundary so the model does not see half a statement.
+    let head: String = content.chars().take(max_chars).collect();
+    let head = match head.rfind('\n') {
+        Some(end) => &head[..=end],
+        None => head.as_str(),
+    };
+    file_block(rel, head, true)
+}
+
+fn skip(rel: &str, reason: &str) -> SkippedFile {
+    SkippedFile {
+        path: rel.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::tempdir;
+
+    use super::pack_project_context;
+
+    #[test]
+    fn packs_source_and_skips_ignored_secret_and_binary_files() {
+        let project = tempdir().unwrap();
+        let root = project.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("generated")).unwrap();
+        fs::write(root.join(".gitignore"), "generated/\n").unwrap();
+        fs::write(root.join("package.json"), r#"{"name":"demo"}"#).unwrap();
+        fs::write(
+            root.join("src/App.jsx"),
+            "export default function App() {}\n",
+        )
+        .unwrap();
+        fs::write(root.join("generated/big.js"), "ignored").unwrap();
+        fs::write(root.join(".env"), "SUPABASE_KEY=secret-value").unwrap();
+        fs::write(root.join("config.env"), "TOKEN=secret-value").unwrap();
+        fs::write(root.join("logo.png"), [0x89, b'P', b'N', b'G']).unwrap();
+        fs::write(root.join("src/data.txt"), "a\0b").unwrap();
+
+        let pack = pack_project_context(&root.to_string_lossy(), None, &[], None).unwrap();
+        let included: Vec<&str> = pack.files.iter().map(|f| f.path.as_str()).collect();
+
+        assert_eq!(included, vec!["package.json", "src/App.jsx"]);
+        assert!(!pack.context.contains("secret-value"));
+        assert!(pack.context.contains("### File: src/App.jsx"));
+        let skipped: Vec<(&str, &str)> = pack
+            .skipped
+            .iter()
+            .map(|s| (s.path.as_str(), s.reason.as_str()))
+            .collect();
+        assert!(skipped.contains(&("config.env", "may contain secrets")));
+        assert!(skipped.contains(&("logo.png", "binary file")));
+        assert!(skipped.contains(&("src/data.txt", "binary content")));
+        assert!(!skipped
+            .iter()
+            .any(|(path, _)| path.starts_with("generated/")));
+    }
+
+    #[test]
+    fn focus_files_come_first_and_the_budget_is_respected() {
+        let project = tempdir().unwrap();
+        let root = project.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/a.js"), "const a = 1;\n".repeat(40)).unwrap();
+        fs::write(root.join("src/focus.js"), "const focus = 1;\n".repeat(400)).unwrap();
+
+        let pack = pack_project_context(
+            &root.to_string_lossy(),
+            Some(500),
+            &["./src/focus.js".to_string()],
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(pack.files[0].path, "src/focus.js");
+        assert!(pack.files[0].truncated);
+        assert!(pack.estimated_tokens <= pack.token_budget);
+        assert!(pack
+            .skipped
+            .iter()
+            .any(|s| s.path == "src/a.js" && s.reason == "over the token budget"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn focus_paths_cannot_follow_symlinks_out_of_the_project() {
+        let outside = tempdir().unwrap();
+        fs::write(outside.path().join("id_rsa"), "outside-secret").unwrap();
+        let project = tempdir().unwrap();
+        let root = project.path();
+        std::os::unix::fs::symlink(outside.path(), root.join("linked")).unwrap();
+        std::os::unix::fs::symlink(root.join(".env"), root.join("notes.txt")).unwrap();
+        fs::write(root.join(".env"), "TOKEN=env-secret").unwrap();
+
+        let pack = pack_project_context(
+            &root.to_string_lossy(),
+            None,
+            &["linked/id_rsa".to_string(), "notes.txt".to_string()],
+            None,
+        )
+        .unwrap();
+
+        assert!(!pack.context.contains("outside-secret"));
+        assert!(!pack.context.contains("env-secret"));
+    }
+}
@@ -0,0 +1,361 @@
+// src-tauri/src/ai/credential_profiles.rs
+//
+// Project-scoped credential profiles.
+//
+// A profile is a named set of provider keys (e.g. "client-acme") stored by
+// `secret_store` under its own keyring accounts. This module only keeps the
+// non-secret registry: which project folder uses which profile, and which
+// providers each profile has a key for. Key values never pass through here.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::secret_vault::write_private_file;
+
+pub const REGISTRY_FILE_NAME: &str = "credential_profiles.json";
+
+const MAX_PROFILE_NAME_LEN: usize = 64;
+
+static REGISTRY: OnceLock<Mutex<LoadedRegistry>> = OnceLock::new();
+
+fn registry() -> &'static Mutex<LoadedRegistry> {
+    REGISTRY.get_or_init(|| Mutex::new(LoadedRegistry::default()))
+}
+
+#[derive(Default)]
+struct LoadedRegistry {
+    path: Option<PathBuf>,
+    data: ProfileRegistry,
+    /// Why the registry file could not be loaded. Project lookups, listing
+    /// and saving fail with this until the app restarts, so a broken file is
+    /// never clobbered and no project silently uses the global keys.
+    load_error: Option<String>,
+}
+
+impl LoadedRegistry {
+    fn load(path: PathBuf) -> Self {
+        match ProfileRegistry::load(&path) {
+            Ok(data) => Self {
+                path: Some(path),
+                data,
+                load_error: None,
+            },
+            Err(e) => Self {
+                path: Some(path),
+                data: ProfileRegistry::default(),
+                load_error: Some(e),
+            },
+        }
+    }
+
+    fn check(&self) -> Result<(), String> {
+        match &self.load_error {
+            Some(e) => Err(e.clone()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct ProfileRegistry {
+    /// Normalized project path -> profile name.
+    #[serde(default)]
+    projects: BTreeMap<String, String>,
+    /// Profile name -> provider ids that have a key stored for the profile.
+    #[serde(default)]
+    profiles: BTreeMap<String, BTreeSet<String>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ProjectProfileAssignment {
+    pub project_path: String,
+    pub profile: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CredentialProfileSummary {
+    pub name: String,
+    pub providers: Vec<String>,
+    pub projects: Vec<String>,
+}
+
+/// Load the registry from the app data folder. Called once during setup;
+/// a missing file starts an empty registry. A load failure does not stop
+/// startup: it is kept and reported by `list_profiles` instead.
+pub fn init(app: &AppHandle) {
+    let loaded = match app.path().app_local_data_dir() {
+        Ok(dir) => LoadedRegistry::load(dir.join(REGISTRY_FILE_NAME)),
+        Err(e) => LoadedRegistry {
+            load_error: Some(format!("Could not resolve the app data folder: {e}")),
+            ..LoadedRegistry::default()
+        },
+    };
+    if let Ok(mut guard) = registry().lock() {
+        *guard = loaded;
+    }
+}
+
+pub fn validate_profile_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Profile name is empty".into());
+    }
+    if name.len() > MAX_PROFILE_NAME_LEN {
+        return Err(format!(
+            "Profile name must be at most {MAX_PROFILE_NAME_LEN} characters"
+        ));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+    {
+        return Err("Profile name may only contain letters, digits, '-' and '.'".into());
+    }
+    Ok(name.to_string())
+}
+
+/// Profile assigned to `project_path`, if any. Fails while the registry
+/// could not be loaded, so a mapped project never falls back to the global
+/// keys unnoticed.
+pub fn profile_for_project(project_path: &str) -> Result<Option<String>, String> {
+    let guard = registry()
+        .lock()
+        .map_err(|_| "Credential profile registry lock poisoned".to_string())?;
+    guard.check()?;
+    let Ok(key) = normalize_project_path(project_path) else {
+        return Ok(None);
+    };
+    Ok(guard.data.projects.get(&key).cloned())
+}
+
+pub fn assign_project(
+    project_path: &str,
+    profile: &str,
+) -> Result<ProjectProfileAssignment, String> {
+    let profile = validate_profile_name(profile)?;
+    let key = normalize_project_path(project_path)?;
+    update(|data| {
+        data.projects.insert(key.clone(), profile.clone());
+        data.profiles.entry(profile.clone()).or_default();
+    })?;
+    Ok(ProjectProfileAssignment {
+        project_path: key,
+        profile,
+    })
+}
+
+/// Remove the mapping for `project_path`. Returns false when none existed.
+pub fn unassign_project(project_path: &str) -> Result<bool, String> {
+    let key = normalize_project_path(project_path)?;
+    let mut removed = false;
+    update(|data| removed = data.projects.remove(&key).is_some())?;
+    Ok(removed)
+}
+
+pub fn list_assignments() -> Result<Vec<ProjectProfileAssignment>, String> {
+    let guard = registry()
+        .lock()
+        .map_err(|_| "Credential profile registry lock poisoned".to_string())?;
+    guard.check()?;
+    Ok(guard.data.assignments())
+}
+
+pub fn list_profiles() -> Result<Vec<CredentialProfileSummary>, String> {
+    let guard = registry()
+        .lock()
+        .map_err(|_| "Credential profile registry lock poisoned".to_string())?;
+    guard.check()?;
+    Ok(guard.data.summaries())
+}
+
+/// Track whether `profile` holds a key for `provider_id`. Called by
+/// `secret_store` after writing or clearing a profile key.
+pub fn record_profile_provider(
+    profile: &str,
+    provider_id: &str,
+    present: bool,
+) -> Result<(), String> {
+    update(|data| {
+        let providers = data.profiles.entry(profile.to_string()).or_default();
+        if present {
+            providers.insert(provider_id.to_string());
+        } else {
+            providers.remove(provider_id);
+        }
+    })
+}
+
+fn update(change: impl FnOnce(&mut ProfileRegistry)) -> Result<(), String> {
+    let mut guard = registry()
+        .lock()
+        .map_err(|_| "Credential profile registry lock poisoned".to_string())?;
+    guard.check()?;
+
+    let mut next = guard.data.clone();
+    change(&mut next);
+
+    if let Some(path) = &guard.path {
+        next.save(path)?;
+    }
+    guard.data = next;
+    Ok(())
+}
+
+/// Canonical form used as the registry key, so "C:\\work\\app" and
+/// "C:/work/app/" resolve to the same project.
+fn normalize_project_path(project_path: &str) -> Result<String, String> {
+    let trimmed = project_path.trim();
+    if trimmed.is_empty() {
+        return Err("Project path is empty".into());
+    }
+
+    let path = fs::canonicalize(trimmed).unwrap_or_else(|_| PathBuf::from(trimmed));
+    let normalized = path.to_string_lossy().replace('\\', "/");
+    let normalized = normalized.trim_end_matches('/');
+    if normalized.is_empty() {
+        return Ok("/".into());
+    }
+    Ok(normalized.to_string())
+}
+
+impl ProfileRegistry {
+    fn load(path: &Path) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(raw) => serde_json::from_str(&raw)
+                .map_err(|e| format!("Credential profile registry is malformed: {e}")),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!(
+                "Credential profile registry could not be read: {e}"
+            )),
+        }
+    }
+
+    fn save(&self, path: &Path) -> Result<(), String> {
+        let encoded = serde_json::to_vec_pretty(self)
+            .map_err(|e| format!("Credential profile registry could not be encoded: {e}"))?;
+        write_private_file(path, &encoded)
+    }
+
+    fn assignments(&self) -> Vec<ProjectProfileAssignment> {
+        self.projects
+            .iter()
+            .map(|(project_path, profile)| ProjectProfileAssignment {
+                project_path: project_path.clone(),
+                profile: profile.clone(),
+            })
+            .collect()
+    }
+
+    fn summaries(&self) -> Vec<CredentialProfileSummary> {
+        let mut names: BTreeSet<&String> = self.profiles.keys().collect();
+        names.extend(self.projects.values());
+
+        names
+            .into_iter()
+            .map(|name| CredentialProfileSummary {
+                name: name.clone(),
+                providers: self
+                    .profiles
+                    .get(name)
+                    .map(|set| set.iter().cloned().collect())
+                    .unwrap_or_default(),
+                projects: self
+                    .projects
+                    .iter()
+                    .filter(|(_, profile)| *profile == name)
+                    .map(|(project, _)| project.clone())
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_project_path, validate_profile_name, LoadedRegistry, ProfileRegistry};
+
+    #[test]
+    fn profile_names_are_restricted_to_keyring_safe_characters() {
+        assert_eq!(
+            validate_profile_name(" client-acme ").unwrap(),
+            "client-acme"
+        );
+        assert!(validate_profile_name("").is_err());
+        assert!(validate_profile_name("client_acme").is_err());
+        assert!(validate_profile_name("client/acme").is_err());
+        assert!(validate_profile_name(&"a".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn project_paths_normalize_separators_and_trailing_slashes() {
+        let dir = tempfile::tempdir().unwrap();
+        let with_slash = format!("{}/", dir.path().display());
+
+        assert_eq!(
+            normalize_project_path(&with_slash).unwrap(),
+            normalize_project_path(&dir.path().to_string_lossy()).unwrap()
+        );
+        assert_eq!(
+            normalize_project_path("missing\\client\\app\\").unwrap(),
+            "missing/client/app"
+        );
+    }
+
+    #[test]
+    fn registry_round_trips_and_lists_without_key_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("credential_profiles.json");
+
+        let mut registry = ProfileRegistry::default();
+        registry
+            .projects
+            .insert("/work/acme".into(), "client-acme".into());
+        registry
+            .profiles
+            .entry("client-acme".into())
+            .or_default()
+            .insert("openai".into());
+        registry.profiles.entry("unused".into()).or_default();
+        registry.save(&path).unwrap();
+
+        let loaded = ProfileRegistry::load(&path).unwrap();
+        let summaries = loaded.summaries();
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].name, "client-acme");
+        assert_eq!(summaries[0].providers, vec!["openai"]);
+        assert_eq!(summaries[0].projects, vec!["/work/acme"]);
+        assert!(summaries[1].projects.is_empty());
+
+        let assignments = loaded.assignments();
+        assert_eq!(assignments.len(), 1);
+        assert_eq!(assignments[0].profile, "client-acme");
+    }
+
+    #[test]
+    fn missing_registry_file_starts_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let loaded = ProfileRegistry::load(&dir.path().join("absent.json")).unwrap();
+        assert!(loaded.projects.is_empty());
+        assert!(loaded.profiles.is_empty());
+    }
+
+    #[test]
+    fn malformed_registry_keeps_its_load_error_and_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("credential_profiles.json");
+        std::fs::write(&path, "{ not json").unwrap();
+
+        let loaded = LoadedRegistry::load(path.clone());
+        let error = loaded.check().unwrap_err();
+        assert!(error.contains("malformed"), "{error}");
+        assert_eq!(loaded.path.as_deref(), Some(path.as_path()));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{ not json");
+    }
+}
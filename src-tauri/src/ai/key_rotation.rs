@@ -0,0 +1,313 @@
+// src-tauri/src/ai/key_rotation.rs
+//
+// Validate a replacement API key with a cheap authenticated call before it
+// overwrites the stored one. The check is a list-models (or key-info) request,
+// which costs nothing and fails fast with 401/403 on a bad key.
+
+use std::time::Duration;
+
+use reqwest::blocking::Client;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::ai::{
+    error::AiError,
+    providers::{
+        ollama::{normalize_ollama_base_url, OLLAMA_CLOUD_DEFAULT_BASE_URL},
+        openai_compat::{OpenAICompatClient, OpenAICompatConfig, ProviderError},
+    },
+    secret_store,
+};
+
+const CHECK_TIMEOUT_SECS: u64 = 20;
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+#[derive(Clone, Debug, Serialize)]
+pub struct KeyCheck {
+    pub provider_id: String,
+    /// Number of models the key can see, when the check endpoint lists them.
+    pub models_seen: Option<usize>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct KeyRotation {
+    pub provider_id: String,
+    pub profile: Option<String>,
+    pub models_seen: Option<usize>,
+}
+
+/// How a provider's key is checked. `base` is the default API root; callers
+/// may override it with the same endpoint the provider would use.
+enum CheckKind {
+    /// OpenAI-compatible GET {base}/v1/{path} with Bearer auth.
+    Compat {
+        base: &'static str,
+        path: &'static str,
+    },
+    /// Anthropic GET {base}/models with x-api-key.
+    Claude { base: &'static str },
+    /// Gemini GET {base}/models with x-goog-api-key.
+    Gemini { base: &'static str },
+    /// Ollama Cloud GET {base}/api/tags with Bearer auth.
+    OllamaCloud,
+}
+
+fn check_kind(provider_id: &str) -> Option<CheckKind> {
+    let kind = match provider_id {
+        "openai" => CheckKind::Compat {
+            base: "https://api.openai.com",
+            path: "models",
+        },
+        "groq" => CheckKind::Compat {
+            base: "https://api.groq.com/openai",
+            path: "models",
+        },
+        "deepseek" => CheckKind::Compat {
+            base: "https://api.deepseek.com",
+            path: "models",
+        },
+        "mistral" => CheckKind::Compat {
+            base: "https://api.mistral.ai",
+            path: "models",
+        },
+        // The custom provider has no meaningful default; an endpoint is required.
+        "custom" => CheckKind::Compat {
+            base: "",
+            path: "models",
+        },
+        // OpenRouter's model list is public, so use the authenticated key-info route.
+        "openrouter" => CheckKind::Compat {
+            base: "https://openrouter.ai/api",
+            path: "key",
+        },
+        "claude" => CheckKind::Claude {
+            base: "https://api.anthropic.com/v1",
+        },
+        "gemini" => CheckKind::Gemini {
+            base: "https://generativelanguage.googleapis.com/v1beta",
+        },
+        "ollama_cloud" => CheckKind::OllamaCloud,
+        _ => return None,
+    };
+    Some(kind)
+}
+
+/// Make one cheap authenticated request with `api_key`. Nothing is stored.
+pub fn validate_api_key(
+    provider_id: &str,
+    api_key: &str,
+    endpoint: Option<&str>,
+) -> Result<KeyCheck, AiError> {
+    let provider_id = provider_id.trim();
+    let api_key = api_key.trim();
+    if api_key.is_empty() {
+        return Err(AiError::invalid("API key is empty"));
+    }
+
+    let kind = check_kind(provider_id).ok_or_else(|| {
+        AiError::invalid(format!(
+            "Provider {provider_id} does not use an API key that can be validated"
+        ))
+    })?;
+    let endpoint = endpoint.map(str::trim).filter(|e| !e.is_empty());
+
+    let body = match kind {
+        CheckKind::Compat { base, path } => {
+            let base = endpoint.unwrap_or(base);
+            if base.is_empty() {
+                return Err(AiError::invalid(
+                    "An endpoint is required to validate a Custom Endpoint key",
+                ));
+            }
+            compat_get(provider_id, base, api_key, path)?
+        }
+        CheckKind::Claude { base } => {
+            let url = format!("{}/models", endpoint.unwrap_or(base).trim_end_matches('/'));
+            http_get(provider_id, &url, |req| {
+                req.header("x-api-key", api_key)
+                    .header("anthropic-version", ANTHROPIC_VERSION)
+            })?
+        }
+        CheckKind::Gemini { base } => {
+            let url = format!("{}/models", endpoint.unwrap_or(base).trim_end_matches('/'));
+            http_get(provider_id, &url, |req| {
+                req.header("x-goog-api-key", api_key)
+            })?
+        }
+        CheckKind::OllamaCloud => {
+            let base = normalize_ollama_base_url(endpoint.unwrap_or(OLLAMA_CLOUD_DEFAULT_BASE_URL));
+            let url = format!("{base}/api/tags");
+            http_get(provider_id, &url, |req| req.bearer_auth(api_key))?
+        }
+    };
+
+    Ok(KeyCheck {
+        provider_id: provider_id.to_string(),
+        models_seen: count_models(&body),
+    })
+}
+
+/// Validate `new_api_key`, then replace the stored global key (or the key of
+/// `profile` when given). A key that fails validation is never stored.
+pub fn rotate_api_key(
+    provider_id: &str,
+    new_api_key: &str,
+    endpoint: Option<&str>,
+    profile: Option<&str>,
+) -> Result<KeyRotation, AiError> {
+    let check = validate_api_key(provider_id, new_api_key, endpoint)?;
+    let profile = profile.map(str::trim).filter(|p| !p.is_empty());
+
+    match profile {
+        Some(profile) => secret_store::set_profile_api_key(profile, provider_id, new_api_key)?,
+        None => secret_store::set_api_key(provider_id, new_api_key)?,
+    }
+
+    Ok(KeyRotation {
+        provider_id: check.provider_id,
+        profile: profile.map(str::to_string),
+        models_seen: check.models_seen,
+    })
+}
+
+fn compat_get(provider_id: &str, base: &str, api_key: &str, path: &str) -> Result<Value, AiError> {
+    // OpenAICompatClient appends /v1 itself.
+    let base = base.trim_end_matches('/');
+    let base = base.strip_suffix("/v1").unwrap_or(base);
+
+    let client = OpenAICompatClient::new(&OpenAICompatConfig {
+        base_url: base.to_string(),
+        api_key: api_key.to_string(),
+        default_model: None,
+        extra_headers: Vec::new(),
+        timeout_secs: CHECK_TIMEOUT_SECS,
+    })
+    .map_err(|e| AiError::invalid(format!("Key check setup failed: {e}")))?;
+
+    client.get_v1(path).map_err(|e| match e {
+        ProviderError::Upstream { status, body } => rejected(provider_id, status, &body),
+        ProviderError::Http(e) => network_error(provider_id, &e),
+        other => AiError::provider(format!("{provider_id} key check failed: {other}")),
+    })
+}
+
+fn http_get(
+    provider_id: &str,
+    url: &str,
+    auth: impl FnOnce(reqwest::blocking::RequestBuilder) -> reqwest::blocking::RequestBuilder,
+) -> Result<Value, AiError> {
+    let client = Client::builder()
+        .timeout(Duration::from_secs(CHECK_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| AiError::unknown(format!("HTTP client build failed: {e}")))?;
+
+    let resp = auth(client.get(url))
+        .send()
+        .map_err(|e| network_error(provider_id, &e))?;
+    let status = resp.status();
+    let text = resp
+        .text()
+        .map_err(|e| AiError::unknown(format!("Failed reading response: {e}")))?;
+
+    if !status.is_success() {
+        return Err(rejected(provider_id, status.as_u16(), &text));
+    }
+    serde_json::from_str(&text).map_err(|e| {
+        AiError::provider(format!(
+            "{provider_id} key check returned invalid JSON: {e}"
+        ))
+    })
+}
+
+fn rejected(provider_id: &str, status: u16, body: &str) -> AiError {
+    let detail: String = body.trim().chars().take(200).collect();
+    match status {
+        401 | 403 => AiError::auth(format!(
+            "{provider_id} rejected the new key (HTTP {status}). The stored key was not changed."
+        )),
+        _ => AiError::provider(format!(
+            "{provider_id} key check failed (HTTP {status}): {detail}. The stored key was not changed."
+        )),
+    }
+}
+
+fn network_error(provider_id: &str, e: &reqwest::Error) -> AiError {
+    AiError::provider(format!(
+        "Could not reach {provider_id} to validate the key: {e}. The stored key was not changed."
+    ))
+}
+
+fn count_models(body: &Value) -> Option<usize> {
+    body.get("data")
+        .or_else(|| body.get("models"))
+        .and_then(Value::as_array)
+        .map(Vec::len)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        sync::mpsc,
+        thread,
+    };
+
+    use super::validate_api_key;
+    use crate::ai::error::{AiErrorKind, AiErrorPayload};
+
+    /// One-shot local stand-in for a provider API. Returns the base URL and a
+    /// receiver yielding the raw request it saw.
+    fn stand_in(status: &'static str, body: &'static str) -> (String, mpsc::Receiver<String>) {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0_u8; 8192];
+            let read = stream.read(&mut buffer).unwrap();
+            let _ = tx.send(String::from_utf8_lossy(&buffer[..read]).to_string());
+            write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+        });
+
+        (format!("http://127.0.0.1:{port}"), rx)
+    }
+
+    #[test]
+    fn accepts_a_key_that_can_list_models() {
+        let (base, seen) = stand_in("200 OK", r#"{"data":[{"id":"a"},{"id":"b"}]}"#);
+
+        let check = validate_api_key("openai", "sk-new", Some(&format!("{base}/v1"))).unwrap();
+        assert_eq!(check.models_seen, Some(2));
+
+        let request = seen.recv().unwrap();
+        assert!(request.starts_with("GET /v1/models "));
+        assert!(request
+            .to_ascii_lowercase()
+            .contains("authorization: bearer sk-new"));
+    }
+
+    #[test]
+    fn rejected_keys_report_an_auth_error() {
+        let (base, _seen) = stand_in("401 Unauthorized", r#"{"error":"bad key"}"#);
+
+        let error =
+            AiErrorPayload::from(validate_api_key("claude", "sk-bad", Some(&base)).unwrap_err());
+        assert!(matches!(error.kind, AiErrorKind::Auth));
+        assert!(error.message.contains("not changed"));
+    }
+
+    #[test]
+    fn providers_without_keys_cannot_be_validated() {
+        assert!(validate_api_key("ollama", "anything", None).is_err());
+        assert!(validate_api_key("custom", "sk-new", None).is_err());
+        assert!(validate_api_key("openai", "  ", None).is_err());
+    }
+}
@@ -1,5 +1,9 @@
 pub mod commands;
+pub mod context_pack;
+pub mod credential_profiles;
 pub mod error;
+pub mod key_rotation;
 pub mod providers;
+pub mod secret_bundle;
 pub mod secret_store;
 pub mod types;
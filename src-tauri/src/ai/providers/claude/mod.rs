@@ -38,8 +38,8 @@ impl ClaudeProvider {
             .unwrap_or_else(|| self.base_url.clone())
     }
 
-    fn load_api_key(&self) -> Result<String, AiError> {
-        match secret_store::get_api_key(self.provider_id())? {
+    fn load_api_key(&self, req: &AiRequest) -> Result<String, AiError> {
+        match secret_store::get_api_key(self.provider_id(), req.project_path.as_deref())? {
             Some(k) if !k.trim().is_empty() => Ok(k),
             _ => Err(AiError::auth(
                 "No Claude (Anthropic) API key set. Use ai_set_api_key first.",
@@ -91,7 +91,7 @@ impl super::AiProvider for ClaudeProvider {
     }
 
     fn generate(&self, req: &AiRequest) -> Result<AiResponse, AiError> {
-        let api_key = self.load_api_key()?;
+        let api_key = self.load_api_key(req)?;
         let base_url = self.resolve_base_url(req);
         let url = format!("{}/messages", base_url.trim_end_matches('/'));
 
@@ -277,6 +277,7 @@ mod tests {
             temperature,
             max_output_tokens: Some(32),
             endpoint: None,
+            project_path: None,
         }
     }
 
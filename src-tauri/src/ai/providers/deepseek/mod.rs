@@ -51,8 +51,8 @@ impl DeepSeekProvider {
         Self::normalize_base_url_for_compat(&raw)
     }
 
-    fn load_api_key(&self) -> Result<String, AiError> {
-        match secret_store::get_api_key(self.provider_id())? {
+    fn load_api_key(&self, req: &AiRequest) -> Result<String, AiError> {
+        match secret_store::get_api_key(self.provider_id(), req.project_path.as_deref())? {
             Some(k) if !k.trim().is_empty() => Ok(k),
             _ => Err(AiError::auth(
                 "No DeepSeek API key set. Use ai_set_api_key first.",
@@ -200,7 +200,7 @@ impl super::AiProvider for DeepSeekProvider {
     }
 
     fn generate(&self, req: &AiRequest) -> Result<AiResponse, AiError> {
-        let api_key = self.load_api_key()?;
+        let api_key = self.load_api_key(req)?;
         let base_url = self.resolve_base_url(req);
 
         let cfg = OpenAICompatConfig {
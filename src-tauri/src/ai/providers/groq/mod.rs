@@ -33,8 +33,8 @@ impl GroqProvider {
         "groq"
     }
 
-    fn load_api_key(&self) -> Result<String, AiError> {
-        match secret_store::get_api_key(self.provider_id())? {
+    fn load_api_key(&self, req: &AiRequest) -> Result<String, AiError> {
+        match secret_store::get_api_key(self.provider_id(), req.project_path.as_deref())? {
             Some(k) if !k.trim().is_empty() => Ok(k),
             _ => Err(AiError::auth(
                 "No Groq API key set. Use ai_set_api_key first.",
@@ -128,7 +128,7 @@ impl super::AiProvider for GroqProvider {
     }
 
     fn generate(&self, req: &AiRequest) -> Result<AiResponse, AiError> {
-        let api_key = self.load_api_key()?;
+        let api_key = self.load_api_key(req)?;
         let base_url = self.resolve_base_url(req);
 
         let mut messages: Vec<Value> = Vec::new();
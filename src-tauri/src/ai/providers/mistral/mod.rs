@@ -30,8 +30,8 @@ impl MistralProvider {
         "mistral"
     }
 
-    fn load_api_key(&self) -> Result<String, AiError> {
-        match secret_store::get_api_key(self.provider_id())? {
+    fn load_api_key(&self, req: &AiRequest) -> Result<String, AiError> {
+        match secret_store::get_api_key(self.provider_id(), req.project_path.as_deref())? {
             Some(k) if !k.trim().is_empty() => Ok(k),
             _ => Err(AiError::auth(
                 "No Mistral API key set. Use ai_set_api_key with provider 'mistral' first.",
@@ -105,7 +105,7 @@ impl super::AiProvider for MistralProvider {
     }
 
     fn generate(&self, req: &AiRequest) -> Result<AiResponse, AiError> {
-        let api_key = self.load_api_key()?;
+        let api_key = self.load_api_key(req)?;
         let base_url = self.base_url.clone();
 
         let mut messages: Vec<Value> = Vec::new();
@@ -22,6 +22,21 @@ pub mod openai;
 pub mod openai_compat; // ✅ Phase 3.2.0 prep: shared OpenAI-compatible client
 pub mod openrouter; // ✅ Phase 3.2.4: RunPod / DataCrunch / Custom Endpoint
 
+/// Every provider id accepted by `get_provider` (keep both in sync).
+pub const PROVIDER_IDS: &[&str] = &[
+    "mock",
+    "openai",
+    "gemini",
+    "deepseek",
+    "claude",
+    "groq",
+    "openrouter",
+    "ollama",
+    "ollama_cloud",
+    "custom",
+    "mistral",
+];
+
 pub fn get_provider(provider_id: &str) -> Option<Box<dyn AiProvider>> {
     match provider_id {
         "mock" => Some(Box::new(mock::MockProvider)),
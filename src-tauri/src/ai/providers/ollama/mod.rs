@@ -103,12 +103,12 @@ impl OllamaProvider {
         normalize_ollama_base_url(&raw)
     }
 
-    fn resolve_api_key(&self) -> Result<Option<String>, AiError> {
+    fn resolve_api_key(&self, req: &AiRequest) -> Result<Option<String>, AiError> {
         if !self.needs_api_key {
             return Ok(None);
         }
 
-        match secret_store::get_api_key(self.provider_id())? {
+        match secret_store::get_api_key(self.provider_id(), req.project_path.as_deref())? {
             Some(key) if !key.trim().is_empty() => Ok(Some(key)),
             _ => Err(AiError::provider(
                 "No Ollama Cloud API key set. Add it in Settings first.",
@@ -149,7 +149,7 @@ impl super::AiProvider for OllamaProvider {
 
     fn generate(&self, req: &AiRequest) -> Result<AiResponse, AiError> {
         let base_url = self.resolve_base_url(req);
-        let api_key = self.resolve_api_key()?;
+        let api_key = self.resolve_api_key(req)?;
 
         let url = format!("{}/api/chat", base_url.trim_end_matches('/'));
 
@@ -67,7 +67,12 @@ impl OpenAICompatClient {
 
     /// GET {base}/v1/models (handy for debugging; optional)
     pub fn get_models(&self) -> Result<serde_json::Value, ProviderError> {
-        let url = self.v1("models");
+        self.get_v1("models")
+    }
+
+    /// GET {base}/v1/{path}
+    pub fn get_v1(&self, path: &str) -> Result<serde_json::Value, ProviderError> {
+        let url = self.v1(path);
         let resp = self.http.get(url).send()?;
         let status = resp.status();
         let text = resp.text()?;
@@ -0,0 +1,236 @@
+// src-tauri/src/ai/secret_bundle.rs
+//
+// Passphrase-encrypted export/import of provider keys for moving to a new
+// machine. The bundle holds the global key of every provider plus the keys
+// of each credential profile. Project-to-profile assignments are not
+// included because project paths differ between machines.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ai::{credential_profiles, error::AiError, providers::PROVIDER_IDS, secret_store};
+use crate::secret_vault::{self, SealedEnvelope};
+
+const BUNDLE_PURPOSE: &str = "kforge-secret-export";
+const BUNDLE_VERSION: u32 = 1;
+const MAX_BUNDLE_BYTES: u64 = 1_000_000;
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct SecretBundle {
+    version: u32,
+    created_at_unix: u64,
+    /// provider id -> key
+    #[serde(default)]
+    keys: BTreeMap<String, String>,
+    /// profile name -> provider id -> key
+    #[serde(default)]
+    profiles: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// What was exported or imported, by name only.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct BundleSummary {
+    pub path: String,
+    /// Entries such as "openai" or "client-acme/openai".
+    pub entries: Vec<String>,
+    /// Entries left untouched on import because a key already existed.
+    pub skipped: Vec<String>,
+}
+
+pub fn export_to_file(path: &str, passphrase: &str) -> Result<BundleSummary, AiError> {
+    let path = bundle_path(path)?;
+    let bundle = collect_bundle()?;
+    let entries = bundle_entries(&bundle);
+    if entries.is_empty() {
+        return Err(AiError::invalid("There are no stored API keys to export"));
+    }
+
+    let plaintext = serde_json::to_vec(&bundle)
+        .map_err(|e| AiError::unknown(format!("Key bundle could not be encoded: {e}")))?;
+    let envelope = secret_vault::seal(passphrase, BUNDLE_PURPOSE, &plaintext)
+        .map_err(|e| AiError::invalid(format!("Key bundle: {e}")))?;
+    let encoded = serde_json::to_vec_pretty(&envelope)
+        .map_err(|e| AiError::unknown(format!("Key bundle could not be encoded: {e}")))?;
+    secret_vault::write_private_file(path, &encoded).map_err(AiError::unknown)?;
+
+    Ok(BundleSummary {
+        path: path.to_string_lossy().to_string(),
+        entries,
+        skipped: Vec::new(),
+    })
+}
+
+/// Import every key in the bundle. Existing keys are kept unless `overwrite`.
+pub fn import_from_file(
+    path: &str,
+    passphrase: &str,
+    overwrite: bool,
+) -> Result<BundleSummary, AiError> {
+    let path = bundle_path(path)?;
+    let bundle = read_bundle(path, passphrase)?;
+    let mut summary = BundleSummary {
+        path: path.to_string_lossy().to_string(),
+        ..BundleSummary::default()
+    };
+
+    for (provider_id, key) in &bundle.keys {
+        if !overwrite && secret_store::get_api_key(provider_id, None)?.is_some() {
+            summary.skipped.push(provider_id.clone());
+            continue;
+        }
+        secret_store::set_api_key(provider_id, key)?;
+        summary.entries.push(provider_id.clone());
+    }
+
+    for (profile, keys) in &bundle.profiles {
+        for (provider_id, key) in keys {
+            let name = format!("{profile}/{provider_id}");
+            if !overwrite && secret_store::get_profile_api_key(profile, provider_id)?.is_some() {
+                summary.skipped.push(name);
+                continue;
+            }
+            secret_store::set_profile_api_key(profile, provider_id, key)?;
+            summary.entries.push(name);
+        }
+    }
+
+    Ok(summary)
+}
+
+fn bundle_path(path: &str) -> Result<&Path, AiError> {
+    let trimmed = path.trim();
+    if trimmed.is_empty() {
+        return Err(AiError::invalid("Bundle path is empty"));
+    }
+    Ok(Path::new(trimmed))
+}
+
+fn collect_bundle() -> Result<SecretBundle, AiError> {
+    let mut bundle = SecretBundle {
+        version: BUNDLE_VERSION,
+        created_at_unix: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+        ..SecretBundle::default()
+    };
+
+    for provider_id in PROVIDER_IDS {
+        if let Some(key) = secret_store::get_api_key(provider_id, None)? {
+            bundle.keys.insert(provider_id.to_string(), key);
+        }
+    }
+
+    let profiles = credential_profiles::list_profiles().map_err(AiError::unknown)?;
+    for profile in profiles {
+        for provider_id in &profile.providers {
+            if let Some(key) = secret_store::get_profile_api_key(&profile.name, provider_id)? {
+                bundle
+                    .profiles
+                    .entry(profile.name.clone())
+                    .or_default()
+                    .insert(provider_id.clone(), key);
+            }
+        }
+    }
+
+    Ok(bundle)
+}
+
+fn read_bundle(path: &Path, passphrase: &str) -> Result<SecretBundle, AiError> {
+    let metadata = fs::metadata(path)
+        .map_err(|e| AiError::invalid(format!("Key bundle could not be read: {e}")))?;
+    if !metadata.is_file() || metadata.len() > MAX_BUNDLE_BYTES {
+        return Err(AiError::invalid("Key bundle is not a KForge export file"));
+    }
+
+    let raw = fs::read_to_string(path)
+        .map_err(|e| AiError::invalid(format!("Key bundle could not be read: {e}")))?;
+    open_bundle(&raw, passphrase)
+}
+
+fn open_bundle(raw: &str, passphrase: &str) -> Result<SecretBundle, AiError> {
+    let envelope: SealedEnvelope = serde_json::from_str(raw)
+        .map_err(|_| AiError::invalid("Key bundle is not a KForge export file"))?;
+    let plaintext = secret_vault::open(passphrase, BUNDLE_PURPOSE, &envelope)
+        .map_err(|e| AiError::auth(format!("Key bundle: {e}")))?;
+    let bundle: SecretBundle = serde_json::from_slice(&plaintext)
+        .map_err(|_| AiError::invalid("Key bundle contents are damaged"))?;
+
+    if bundle.version != BUNDLE_VERSION {
+        return Err(AiError::invalid(format!(
+            "Key bundle version {} is not supported",
+            bundle.version
+        )));
+    }
+    Ok(bundle)
+}
+
+fn bundle_entries(bundle: &SecretBundle) -> Vec<String> {
+    let mut entries: Vec<String> = bundle.keys.keys().cloned().collect();
+    for (profile, keys) in &bundle.profiles {
+        entries.extend(
+            keys.keys()
+                .map(|provider_id| format!("{profile}/{provider_id}")),
+        );
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bundle_entries, open_bundle, SecretBundle, BUNDLE_PURPOSE, BUNDLE_VERSION};
+    use crate::secret_vault;
+
+    fn sealed(bundle: &SecretBundle, purpose: &str) -> String {
+        let plaintext = serde_json::to_vec(bundle).unwrap();
+        let envelope = secret_vault::seal("correct horse battery", purpose, &plaintext).unwrap();
+        serde_json::to_string(&envelope).unwrap()
+    }
+
+    fn sample() -> SecretBundle {
+        let mut bundle = SecretBundle {
+            version: BUNDLE_VERSION,
+            ..SecretBundle::default()
+        };
+        bundle.keys.insert("openai".into(), "sk-global".into());
+        bundle
+            .profiles
+            .entry("client-acme".into())
+            .or_default()
+            .insert("openai".into(), "sk-client".into());
+        bundle
+    }
+
+    #[test]
+    fn bundles_round_trip_and_list_entries_by_name() {
+        let raw = sealed(&sample(), BUNDLE_PURPOSE);
+        assert!(!raw.contains("sk-global"));
+
+        let opened = open_bundle(&raw, "correct horse battery").unwrap();
+        assert_eq!(
+            opened.keys.get("openai").map(String::as_str),
+            Some("sk-global")
+        );
+        assert_eq!(
+            bundle_entries(&opened),
+            vec!["openai".to_string(), "client-acme/openai".to_string()]
+        );
+    }
+
+    #[test]
+    fn wrong_passphrase_and_foreign_envelopes_are_rejected() {
+        let raw = sealed(&sample(), BUNDLE_PURPOSE);
+        assert!(open_bundle(&raw, "wrong horse battery").is_err());
+
+        let vault_file = sealed(&sample(), "kforge-secret-vault");
+        assert!(open_bundle(&vault_file, "correct horse battery").is_err());
+        assert!(open_bundle("{}", "correct horse battery").is_err());
+    }
+}
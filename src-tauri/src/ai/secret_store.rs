@@ -1,8 +1,10 @@
 // src-tauri/src/ai/secret_store.rs
 
-use crate::ai::error::AiError;
+use crate::ai::{credential_profiles, error::AiError};
+use crate::secret_vault;
 
 use keyring::Entry;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::sync::{Mutex, OnceLock};
 
@@ -21,14 +23,51 @@ fn mem() -> &'static Mutex<HashMap<String, String>> {
 // Use a stable app identifier as the "service"
 const SERVICE: &str = "com.kforge.kforge";
 
+/// Where a provider key currently lives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyBackend {
+    /// Windows Credential Manager, macOS Keychain or Secret Service on Linux.
+    OsKeyring,
+    /// Passphrase-encrypted vault file (see `secret_vault`).
+    EncryptedFile,
+    /// In-memory only; lost on restart.
+    Session,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct KeyPersistence {
+    pub persisted: bool,
+    pub backend: Option<KeyBackend>,
+}
+
+#[cfg(target_os = "windows")]
+const OS_KEYRING_LABEL: &str = "Windows Credential Manager";
+#[cfg(target_os = "macos")]
+const OS_KEYRING_LABEL: &str = "macOS Keychain";
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const OS_KEYRING_LABEL: &str = "Secret Service";
+
 // Store each provider key as a separate keyring entry.
 // Use underscore to avoid delimiter quirks in some backends.
-fn entry_for(provider_id: &str) -> Result<Entry, AiError> {
-    let username = format!("provider_{provider_id}");
-    Entry::new(SERVICE, &username)
+fn account_for(provider_id: &str) -> String {
+    format!("provider_{provider_id}")
+}
+
+// Project profile overrides live next to the global keys under their own account.
+fn profile_account_for(profile: &str, provider_id: &str) -> String {
+    format!("profile_{profile}_provider_{provider_id}")
+}
+
+fn entry_for(account: &str) -> Result<Entry, AiError> {
+    Entry::new(SERVICE, account)
         .map_err(|e| AiError::unknown(format!("Keyring entry init failed: {e}")))
 }
 
+fn vault_error(e: String) -> AiError {
+    AiError::unknown(format!("Encrypted key vault: {e}"))
+}
+
 pub fn set_api_key(provider_id: &str, api_key: &str) -> Result<(), AiError> {
     let provider_id = provider_id.trim();
     if provider_id.is_empty() {
@@ -40,19 +79,122 @@ pub fn set_api_key(provider_id: &str, api_key: &str) -> Result<(), AiError> {
         return Err(AiError::invalid("API key is empty"));
     }
 
+    store_secret(&account_for(provider_id), provider_id, key)
+}
+
+/// Resolve the key for `provider_id`. When `project_path` is mapped to a
+/// credential profile that holds a key for this provider, that key wins;
+/// otherwise the global key is used. A project lookup fails instead of
+/// falling back when the profile registry could not be loaded.
+pub fn get_api_key(
+    provider_id: &str,
+    project_path: Option<&str>,
+) -> Result<Option<String>, AiError> {
+    let provider_id = provider_id.trim();
+    if provider_id.is_empty() {
+        return Ok(None);
+    }
+
+    let profile = match project_path {
+        Some(path) => credential_profiles::profile_for_project(path)
+            .map_err(|e| AiError::unknown(format!("Credential profiles: {e}")))?,
+        None => None,
+    };
+    if let Some(profile) = profile {
+        let label = format!("{provider_id} (profile {profile})");
+        if let Some(v) = load_secret(&profile_account_for(&profile, provider_id), &label)? {
+            return Ok(Some(v));
+        }
+    }
+
+    load_secret(&account_for(provider_id), provider_id)
+}
+
+pub fn clear_api_key(provider_id: &str) -> Result<(), AiError> {
+    let provider_id = provider_id.trim();
+    if provider_id.is_empty() {
+        return Ok(());
+    }
+
+    delete_secret(&account_for(provider_id), provider_id)
+}
+
+pub fn set_profile_api_key(profile: &str, provider_id: &str, api_key: &str) -> Result<(), AiError> {
+    let profile = credential_profiles::validate_profile_name(profile).map_err(AiError::invalid)?;
+    let provider_id = provider_id.trim();
+    if provider_id.is_empty() {
+        return Err(AiError::invalid("provider_id is empty"));
+    }
+
+    let key = api_key.trim().to_string();
+    if key.is_empty() {
+        return Err(AiError::invalid("API key is empty"));
+    }
+
+    let label = format!("{provider_id} (profile {profile})");
+    let stored = store_secret(&profile_account_for(&profile, provider_id), &label, key);
+    // Record the provider even when only the session copy was kept, so the
+    // profile shows up in listings and can be cleared.
+    credential_profiles::record_profile_provider(&profile, provider_id, true)
+        .map_err(AiError::unknown)?;
+    stored
+}
+
+/// The key stored for `provider_id` in `profile` only, without falling back
+/// to the global key.
+pub fn get_profile_api_key(profile: &str, provider_id: &str) -> Result<Option<String>, AiError> {
+    let profile = credential_profiles::validate_profile_name(profile).map_err(AiError::invalid)?;
+    let provider_id = provider_id.trim();
+    if provider_id.is_empty() {
+        return Ok(None);
+    }
+
+    let label = format!("{provider_id} (profile {profile})");
+    load_secret(&profile_account_for(&profile, provider_id), &label)
+}
+
+pub fn clear_profile_api_key(profile: &str, provider_id: &str) -> Result<(), AiError> {
+    let profile = credential_profiles::validate_profile_name(profile).map_err(AiError::invalid)?;
+    let provider_id = provider_id.trim();
+    if provider_id.is_empty() {
+        return Ok(());
+    }
+
+    let label = format!("{provider_id} (profile {profile})");
+    delete_secret(&profile_account_for(&profile, provider_id), &label)?;
+    credential_profiles::record_profile_provider(&profile, provider_id, false)
+        .map_err(AiError::unknown)
+}
+
+fn store_secret(account: &str, label: &str, key: String) -> Result<(), AiError> {
     // Always set in-memory first (so UI can immediately enable this session)
     {
         let mut m = mem()
             .lock()
             .map_err(|_| AiError::unknown("MEM_KEYS lock poisoned"))?;
-        m.insert(provider_id.to_string(), key.clone());
+        m.insert(account.to_string(), key.clone());
     }
 
-    // Best-effort secure storage
-    let entry = entry_for(provider_id)?;
-    entry
-        .set_password(&key)
-        .map_err(|e| AiError::unknown(format!("Keyring write failed for {provider_id}: {e}")))?;
+    // Best-effort secure storage. When no OS credential store is reachable
+    // (Linux without a Secret Service provider), use the encrypted vault.
+    let entry = entry_for(account)?;
+    match entry.set_password(&key) {
+        Ok(()) => {}
+        Err(e) if secret_vault::is_keyring_unavailable(&e) => {
+            let stored = secret_vault::set(SERVICE, account, &key).map_err(vault_error)?;
+            if stored {
+                return Ok(());
+            }
+            return Err(AiError::unknown(format!(
+                "{OS_KEYRING_LABEL} is unavailable ({e}). The key is kept for this session only; unlock the encrypted key vault to persist it."
+            )));
+        }
+        Err(e) => {
+            return Err(AiError::unknown(format!(
+                "Keyring write failed for {label}: {e}"
+            )))
+        }
+    }
 
     // Best-effort verify (do not fail on NoEntry; mem fallback handles session)
     match entry.get_password() {
@@ -62,79 +204,112 @@ pub fn set_api_key(provider_id: &str, api_key: &str) -> Result<(), AiError> {
     }
 }
 
-pub fn get_api_key(provider_id: &str) -> Result<Option<String>, AiError> {
-    let provider_id = provider_id.trim();
-    if provider_id.is_empty() {
-        return Ok(None);
-    }
-
+fn load_secret(account: &str, label: &str) -> Result<Option<String>, AiError> {
     // First try secure store
-    let entry = entry_for(provider_id)?;
-    match entry.get_password() {
+    let entry = entry_for(account)?;
+    let keyring_error = match entry.get_password() {
         Ok(v) => return Ok(Some(v)),
-        Err(keyring::Error::NoEntry) => {
-            // fall through to mem
-        }
-        Err(e) => {
-            // fall through to mem, but keep error discoverable if mem also misses
-            let m = mem()
-                .lock()
-                .map_err(|_| AiError::unknown("MEM_KEYS lock poisoned"))?;
-            if let Some(v) = m.get(provider_id) {
-                return Ok(Some(v.clone()));
-            }
-            return Err(AiError::unknown(format!(
-                "Keyring read failed for {provider_id}: {e}"
-            )));
-        }
+        Err(keyring::Error::NoEntry) => None,
+        Err(e) if secret_vault::is_keyring_unavailable(&e) => None,
+        // keep error discoverable if the fallbacks also miss
+        Err(e) => Some(e),
+    };
+
+    // Encrypted vault (persistent, only while unlocked)
+    if let Some(v) = secret_vault::get(SERVICE, account).map_err(vault_error)? {
+        return Ok(Some(v));
     }
 
     // In-memory fallback (session only)
     let m = mem()
         .lock()
         .map_err(|_| AiError::unknown("MEM_KEYS lock poisoned"))?;
-    Ok(m.get(provider_id).cloned())
-}
+    if let Some(v) = m.get(account) {
+        return Ok(Some(v.clone()));
+    }
 
-pub fn clear_api_key(provider_id: &str) -> Result<(), AiError> {
-    let provider_id = provider_id.trim();
-    if provider_id.is_empty() {
-        return Ok(());
+    match keyring_error {
+        Some(e) => Err(AiError::unknown(format!(
+            "Keyring read failed for {label}: {e}"
+        ))),
+        None => Ok(None),
     }
+}
 
+fn delete_secret(account: &str, label: &str) -> Result<(), AiError> {
     // Clear mem fallback
     {
         let mut m = mem()
             .lock()
             .map_err(|_| AiError::unknown("MEM_KEYS lock poisoned"))?;
-        m.remove(provider_id);
+        m.remove(account);
     }
 
+    // Clear vault copy (refused while a vault file is locked)
+    secret_vault::remove(SERVICE, account).map_err(vault_error)?;
+
     // Best-effort secure delete
-    let entry = entry_for(provider_id)?;
+    let entry = entry_for(account)?;
     match entry.delete_credential() {
         Ok(_) => Ok(()),
         Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) if secret_vault::is_keyring_unavailable(&e) => Ok(()),
         Err(e) => Err(AiError::unknown(format!(
-            "Keyring delete failed for {provider_id}: {e}"
+            "Keyring delete failed for {label}: {e}"
         ))),
     }
 }
 
-// True if the key exists in the OS keyring (Credential Manager),
-// false if not present. Errors only if the backend returns a real failure.
-pub fn is_persisted_in_keyring(provider_id: &str) -> Result<bool, AiError> {
+// Report which backend holds the key: OS keyring first, then the encrypted
+// vault, then the session cache. Errors only if the keyring returns a real failure.
+pub fn key_persistence(provider_id: &str) -> Result<KeyPersistence, AiError> {
     let provider_id = provider_id.trim();
+    let none = KeyPersistence {
+        persisted: false,
+        backend: None,
+    };
     if provider_id.is_empty() {
-        return Ok(false);
+        return Ok(none);
     }
 
-    let entry = entry_for(provider_id)?;
+    let account = account_for(provider_id);
+    let entry = entry_for(&account)?;
     match entry.get_password() {
-        Ok(_) => Ok(true),
-        Err(keyring::Error::NoEntry) => Ok(false),
-        Err(e) => Err(AiError::unknown(format!(
-            "Keyring read failed for {provider_id}: {e}"
-        ))),
+        Ok(_) => {
+            return Ok(KeyPersistence {
+                persisted: true,
+                backend: Some(KeyBackend::OsKeyring),
+            })
+        }
+        Err(keyring::Error::NoEntry) => {}
+        Err(e) if secret_vault::is_keyring_unavailable(&e) => {}
+        Err(e) => {
+            return Err(AiError::unknown(format!(
+                "Keyring read failed for {provider_id}: {e}"
+            )))
+        }
+    }
+
+    if secret_vault::get(SERVICE, &account)
+        .map_err(vault_error)?
+        .is_some()
+    {
+        return Ok(KeyPersistence {
+            persisted: true,
+            backend: Some(KeyBackend::EncryptedFile),
+        });
+    }
+
+    let in_session = mem()
+        .lock()
+        .map_err(|_| AiError::unknown("MEM_KEYS lock poisoned"))?
+        .contains_key(&account);
+    if in_session {
+        return Ok(KeyPersistence {
+            persisted: false,
+            backend: Some(KeyBackend::Session),
+        });
     }
+
+    Ok(none)
 }
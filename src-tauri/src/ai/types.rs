@@ -13,6 +13,10 @@ pub struct AiRequest {
 
     // Bring-your-own-endpoint (OpenAI-compatible) – used in later sub-phases
     pub endpoint: Option<String>,
+
+    // Open project folder; selects a per-project credential profile if one is assigned
+    #[serde(default)]
+    pub project_path: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
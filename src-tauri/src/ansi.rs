@@ -0,0 +1,43 @@
+// src-tauri/src/ansi.rs
+//
+// Terminal escape sequences in process output. Command logs, dev-server URL
+// detection and build reports all want the plain text.
+
+/// Drop CSI sequences (colors, cursor moves) and OSC sequences (titles,
+/// hyperlinks), keeping the visible text.
+pub fn strip_ansi_sequences(value: &str) -> String {
+    let mut output = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+
+    while let Some(character) = chars.next() {
+        if character != '\u{1b}' {
+            output.push(character);
+            continue;
+        }
+
+        match chars.next() {
+            Some('[') => {
+                for control_character in chars.by_ref() {
+                    if ('@'..='~').contains(&control_character) {
+                        break;
+                    }
+                }
+            }
+            Some(']') => {
+                while let Some(control_character) = chars.next() {
+                    if control_character == '\u{7}' {
+                        break;
+                    }
+
+                    if control_character == '\u{1b}' && chars.peek().copied() == Some('\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            Some(_) | None => {}
+        }
+    }
+
+    output
+}
@@ -1,8 +1,10 @@
-use std::io::{BufRead, BufReader};
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Read};
 use std::process::{Command, Stdio};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
 
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
@@ -10,50 +12,264 @@ use std::os::windows::process::CommandExt;
 #[cfg(target_os = "windows")]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
-use tauri::{AppHandle, Emitter};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
 
+use crate::ansi::strip_ansi_sequences;
+use crate::command_policy::CommandPolicyState;
+use crate::log_buffer::{now_ms, LogStore};
+use crate::process_tree::{self, ProcessTree, StopOutcome, STOP_GRACE_PERIOD};
+use crate::terminal::{self, LineSplitter, Terminal, TerminalSize, Utf8Decoder};
+
+/// Finished sessions kept for `command_sessions`; the oldest go first.
+const MAX_FINISHED_SESSIONS: usize = 20;
+/// Output `command_capture` returns per stream; the start is dropped first.
+const MAX_CAPTURE_BYTES: usize = 1024 * 1024;
+
+/// Every command session, running or recently finished, keyed by session id.
+/// Sessions run side by side, so a watcher does not block other commands.
 #[derive(Default)]
 pub struct CommandRunnerState {
-    pub running: bool,
-    pub child_pid: Option<u32>,
+    sessions: Mutex<HashMap<String, CommandSession>>,
+    next_id: AtomicU64,
+}
+
+struct CommandSession {
+    name: Option<String>,
+    command: String,
+    cwd: String,
+    status: String,
+    exit_code: Option<i32>,
+    started: Instant,
+    started_at_ms: u64,
+    finished_at_ms: Option<u64>,
+    outcome: Option<CommandOutcome>,
+    process: Option<Arc<ProcessTree>>,
+    uses_terminal: bool,
+    terminal: Option<Arc<Terminal>>,
+    stop_requested: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandSessionInfo {
+    /// Also the session's log runner id for `log_recent`.
+    pub id: String,
+    pub name: Option<String>,
+    pub command: String,
+    pub cwd: String,
+    /// "starting", "running", "stopping", "stopped", "exited" or "failed".
+    pub status: String,
+    pub exit_code: Option<i32>,
+    pub pid: Option<u32>,
+    /// Runs in a pseudo-terminal: output comes as raw `output` events and
+    /// the session accepts input and resizes.
+    pub terminal: bool,
+    pub started_at_ms: u64,
+    pub finished_at_ms: Option<u64>,
+    pub outcome: Option<CommandOutcome>,
+}
+
+/// How a session ended, sent as the `complete` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandOutcome {
+    pub session_id: String,
+    /// "exited", "stopped" or "failed" (could not start).
+    pub status: String,
+    /// Exited by itself with code 0.
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    /// Signal that ended the process (Unix), as the system describes it.
+    pub signal: Option<String>,
+    pub duration_ms: u64,
+    pub stdout_bytes: u64,
+    pub stdout_lines: u64,
+    /// Always zero for terminal sessions, where both streams arrive as one.
+    pub stderr_bytes: u64,
+    pub stderr_lines: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct StreamCounts {
+    bytes: u64,
+    lines: u64,
+}
+
+/// What the runner observed once the process ended.
+#[derive(Debug, Default)]
+struct ProcessExit {
+    exit_code: Option<i32>,
+    signal: Option<String>,
+    stdout: StreamCounts,
+    stderr: StreamCounts,
 }
 
-fn strip_ansi_sequences(value: &str) -> String {
-    let mut output = String::with_capacity(value.len());
-    let mut chars = value.chars().peekable();
+impl CommandSession {
+    fn info(&self, id: &str) -> CommandSessionInfo {
+        CommandSessionInfo {
+            id: id.to_string(),
+            name: self.name.clone(),
+            command: self.command.clone(),
+            cwd: self.cwd.clone(),
+            status: self.status.clone(),
+            exit_code: self.exit_code,
+            pid: self.process.as_ref().map(|process| process.pid()),
+            terminal: self.uses_terminal,
+            started_at_ms: self.started_at_ms,
+            finished_at_ms: self.finished_at_ms,
+            outcome: self.outcome.clone(),
+        }
+    }
 
-    while let Some(character) = chars.next() {
-        if character != '\u{1b}' {
-            output.push(character);
-            continue;
+    fn is_finished(&self) -> bool {
+        self.finished_at_ms.is_some()
+    }
+}
+
+impl CommandRunnerState {
+    fn create(
+        &self,
+        name: Option<String>,
+        command: &str,
+        cwd: &str,
+        uses_terminal: bool,
+    ) -> CommandSessionInfo {
+        let id = format!(
+            "command-{}",
+            self.next_id.fetch_add(1, Ordering::Relaxed) + 1
+        );
+        let session = CommandSession {
+            name,
+            command: command.to_string(),
+            cwd: cwd.to_string(),
+            status: "starting".to_string(),
+            exit_code: None,
+            started: Instant::now(),
+            started_at_ms: now_ms(),
+            finished_at_ms: None,
+            outcome: None,
+            process: None,
+            uses_terminal,
+            terminal: None,
+            stop_requested: false,
+        };
+        let info = session.info(&id);
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.insert(id, session);
         }
+        info
+    }
 
-        match chars.next() {
-            Some('[') => {
-                while let Some(control_character) = chars.next() {
-                    if ('@'..='~').contains(&control_character) {
-                        break;
-                    }
-                }
-            }
-            Some(']') => {
-                while let Some(control_character) = chars.next() {
-                    if control_character == '\u{7}' {
-                        break;
-                    }
-
-                    if control_character == '\u{1b}' && chars.peek().copied() == Some('\\') {
-                        chars.next();
-                        break;
-                    }
-                }
+    fn update(
+        &self,
+        id: &str,
+        apply: impl FnOnce(&mut CommandSession),
+    ) -> Option<CommandSessionInfo> {
+        let mut sessions = self.sessions.lock().ok()?;
+        let session = sessions.get_mut(id)?;
+        apply(session);
+        Some(session.info(id))
+    }
+
+    /// Mark the session finished with `status` (or "stopped" when a stop
+    /// was requested), record its outcome and drop the oldest finished
+    /// sessions beyond `MAX_FINISHED_SESSIONS`.
+    fn finish(&self, id: &str, status: &str, exit: ProcessExit) -> Option<CommandSessionInfo> {
+        let mut sessions = self.sessions.lock().ok()?;
+        let session = sessions.get_mut(id)?;
+        let status = if session.stop_requested {
+            "stopped"
+        } else {
+            status
+        };
+        session.status = status.to_string();
+        session.exit_code = exit.exit_code;
+        session.outcome = Some(CommandOutcome {
+            session_id: id.to_string(),
+            status: status.to_string(),
+            success: status == "exited" && exit.exit_code == Some(0),
+            exit_code: exit.exit_code,
+            signal: exit.signal,
+            duration_ms: session.started.elapsed().as_millis() as u64,
+            stdout_bytes: exit.stdout.bytes,
+            stdout_lines: exit.stdout.lines,
+            stderr_bytes: exit.stderr.bytes,
+            stderr_lines: exit.stderr.lines,
+        });
+        session.finished_at_ms = Some(now_ms());
+        session.process = None;
+        session.terminal = None;
+        let info = session.info(id);
+
+        let mut finished: Vec<(String, u64)> = sessions
+            .iter()
+            .filter_map(|(id, session)| session.finished_at_ms.map(|at| (id.clone(), at)))
+            .collect();
+        if finished.len() > MAX_FINISHED_SESSIONS {
+            finished.sort_by_key(|(id, at)| (*at, session_sequence(id)));
+            for (id, _) in &finished[..finished.len() - MAX_FINISHED_SESSIONS] {
+                sessions.remove(id);
             }
-            Some(_) | None => {}
         }
+        Some(info)
+    }
+
+    /// Flag the session as stopping and hand back its process to terminate.
+    /// None when the session is unknown or already finished.
+    fn request_stop(&self, id: &str) -> Option<Option<Arc<ProcessTree>>> {
+        let mut sessions = self.sessions.lock().ok()?;
+        let session = sessions.get_mut(id)?;
+        if session.is_finished() {
+            return None;
+        }
+        session.stop_requested = true;
+        session.status = "stopping".to_string();
+        Some(session.process.clone())
+    }
+
+    fn terminal(&self, id: &str) -> Result<Arc<Terminal>, String> {
+        let sessions = self
+            .sessions
+            .lock()
+            .map_err(|_| "Command state lock failed".to_string())?;
+        let session = sessions
+            .get(id)
+            .ok_or_else(|| format!("No command session with id {}", id))?;
+        session
+            .terminal
+            .clone()
+            .ok_or_else(|| format!("Command session {} has no running terminal", id))
+    }
+
+    fn list(&self) -> Vec<CommandSessionInfo> {
+        let Ok(sessions) = self.sessions.lock() else {
+            return Vec::new();
+        };
+        let mut list: Vec<CommandSessionInfo> = sessions
+            .iter()
+            .map(|(id, session)| session.info(id))
+            .collect();
+        list.sort_by_key(|info| session_sequence(&info.id));
+        list
+    }
+
+    fn running_ids(&self) -> Vec<String> {
+        self.list()
+            .into_iter()
+            .filter(|info| info.finished_at_ms.is_none())
+            .map(|info| info.id)
+            .collect()
     }
+}
 
-    output
+fn session_sequence(id: &str) -> u64 {
+    id.rsplit('-')
+        .next()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(0)
 }
+
 fn is_git_status_short(command: &str) -> bool {
     let normalized = command
         .split_whitespace()
@@ -64,210 +280,772 @@ fn is_git_status_short(command: &str) -> bool {
     normalized == "git status --short"
 }
 
-fn emit_clean_line(app: &AppHandle, line: String, had_visible_output: &AtomicBool) {
+/// Lines are recorded for `log_recent` under the session id, and go out on
+/// the session's own channel and on the shared one (with `sessionId`).
+fn emit_log(app: &AppHandle, session_id: &str, kind: &str, line: impl Into<String>) {
+    let line = line.into();
+    app.state::<LogStore>().push(session_id, kind, &line);
+    let payload = serde_json::json!({
+      "sessionId": session_id,
+      "kind": kind,
+      "line": line
+    });
+    let _ = app.emit(&format!("kforge://command/{}/log", session_id), &payload);
+    let _ = app.emit("kforge://command/log", payload);
+}
+
+fn emit_status(app: &AppHandle, session: Option<CommandSessionInfo>) {
+    let Some(session) = session else {
+        return;
+    };
+    let payload = serde_json::json!({
+      "sessionId": session.id,
+      "status": session.status,
+      "session": session
+    });
+    let _ = app.emit(&format!("kforge://command/{}/status", session.id), &payload);
+    let _ = app.emit("kforge://command/status", payload);
+}
+
+fn emit_complete(app: &AppHandle, outcome: &CommandOutcome) {
+    let _ = app.emit(
+        &format!("kforge://command/{}/complete", outcome.session_id),
+        outcome,
+    );
+    let _ = app.emit("kforge://command/complete", outcome);
+}
+
+/// Raw terminal output, escape sequences included. Lines are still recorded
+/// for `log_recent`, without the escape sequences.
+fn emit_output(app: &AppHandle, session_id: &str, data: &str) {
+    let payload = serde_json::json!({
+      "sessionId": session_id,
+      "data": data
+    });
+    let _ = app.emit(&format!("kforge://command/{}/output", session_id), &payload);
+    let _ = app.emit("kforge://command/output", payload);
+}
+
+fn emit_clean_line(
+    app: &AppHandle,
+    session_id: &str,
+    kind: &str,
+    line: String,
+    had_visible_output: &AtomicBool,
+) {
     let clean_line = strip_ansi_sequences(&line);
 
     if !clean_line.trim().is_empty() {
         had_visible_output.store(true, Ordering::Relaxed);
     }
 
-    let _ = app.emit("kforge://command/log", clean_line);
+    emit_log(app, session_id, kind, clean_line);
 }
 
-#[tauri::command]
-pub fn command_run(
+fn spawn_shell(command: &str, cwd: &str) -> std::io::Result<std::process::Child> {
+    #[cfg(target_os = "windows")]
+    {
+        let mut shell = Command::new("cmd");
+        shell
+            .args(["/D", "/S", "/C"])
+            .raw_arg(command)
+            .current_dir(cwd)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .creation_flags(CREATE_NO_WINDOW);
+        shell.spawn()
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let mut shell = Command::new("sh");
+        shell
+            .args(["-lc", command])
+            .current_dir(cwd)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        process_tree::configure(&mut shell);
+        shell.spawn()
+    }
+}
+
+fn run_session(
     app: AppHandle,
-    state: tauri::State<'_, Arc<Mutex<CommandRunnerState>>>,
+    session_id: String,
     command: String,
     cwd: String,
-) -> Result<(), String> {
-    let mut guard = state.lock().unwrap();
+    terminal_size: Option<TerminalSize>,
+) {
+    app.state::<LogStore>().push(
+        &session_id,
+        "command",
+        &format!("$ {} (cwd: {})", command, cwd),
+    );
+
+    match terminal_size {
+        Some(size) => run_in_terminal(&app, &session_id, &command, &cwd, size),
+        None => run_with_pipes(&app, &session_id, &command, &cwd, None),
+    };
+}
 
-    if guard.running {
-        return Err("Command already running".into());
+/// Record the started process, or stop it right away when the session was
+/// stopped while it was being spawned.
+fn attach_process(
+    app: &AppHandle,
+    session_id: &str,
+    process: &Arc<ProcessTree>,
+    terminal: Option<Arc<Terminal>>,
+) {
+    let started = app
+        .state::<CommandRunnerState>()
+        .update(session_id, |session| {
+            session.process = Some(Arc::clone(process));
+            session.terminal = terminal;
+            if !session.stop_requested {
+                session.status = "running".to_string();
+            }
+        });
+    if started
+        .as_ref()
+        .is_some_and(|info| info.status == "stopping")
+    {
+        process.terminate(STOP_GRACE_PERIOD);
     }
+    emit_status(app, started);
+}
 
-    guard.running = true;
-    guard.child_pid = None;
-    drop(guard);
+fn report_silent_success(app: &AppHandle, session_id: &str, command: &str) {
+    if is_git_status_short(command) {
+        emit_log(app, session_id, "status", "Working tree clean.");
+    } else {
+        emit_log(app, session_id, "status", "Command completed successfully.");
+        emit_log(app, session_id, "status", "No output returned.");
+    }
+}
 
-    let app_handle = app.clone();
-    let state_handle = Arc::clone(&state.inner());
+/// Finish the session and send its `complete` and status events.
+fn complete(
+    app: &AppHandle,
+    session_id: &str,
+    status: &str,
+    exit: ProcessExit,
+) -> Option<CommandOutcome> {
+    let info = app
+        .state::<CommandRunnerState>()
+        .finish(session_id, status, exit);
+    let outcome = info.as_ref().and_then(|info| info.outcome.clone());
+    if let Some(outcome) = &outcome {
+        emit_complete(app, outcome);
+    }
+    emit_status(app, info);
+    outcome
+}
 
-    thread::spawn(move || {
-        let trimmed = command.trim().to_string();
+fn fail_to_start(app: &AppHandle, session_id: &str, message: String) -> Option<CommandOutcome> {
+    emit_log(app, session_id, "stderr", message);
+    complete(app, session_id, "failed", ProcessExit::default())
+}
 
-        if trimmed.is_empty() {
-            let _ = app_handle.emit("kforge://command/log", "Empty command");
+#[cfg(unix)]
+fn exit_signal(status: &std::process::ExitStatus) -> Option<String> {
+    use std::os::unix::process::ExitStatusExt;
 
-            if let Ok(mut guard) = state_handle.lock() {
-                guard.running = false;
-                guard.child_pid = None;
-            }
+    let signal = status.signal()?;
+    // strsignal returns a static, NUL-terminated description or null.
+    let description = unsafe { libc::strsignal(signal) };
+    if description.is_null() {
+        return Some(format!("Signal {}", signal));
+    }
+    let description = unsafe { std::ffi::CStr::from_ptr(description) };
+    Some(description.to_string_lossy().into_owned())
+}
+
+#[cfg(not(unix))]
+fn exit_signal(_status: &std::process::ExitStatus) -> Option<String> {
+    None
+}
 
-            let _ = app_handle.emit("kforge://command/status", "idle");
+/// One captured stream: whole lines, each counted with its newline.
+#[derive(Default)]
+struct CapturedLines {
+    lines: VecDeque<String>,
+    bytes: usize,
+}
+
+/// Output kept for `command_capture`, each stream trimmed from the start
+/// once it passes `MAX_CAPTURE_BYTES`.
+#[derive(Default)]
+struct CapturedOutput {
+    stdout: Mutex<CapturedLines>,
+    stderr: Mutex<CapturedLines>,
+    truncated: AtomicBool,
+}
+
+impl CapturedOutput {
+    fn stream(&self, kind: &str) -> &Mutex<CapturedLines> {
+        if kind == "stderr" {
+            &self.stderr
+        } else {
+            &self.stdout
+        }
+    }
+
+    fn append(&self, kind: &str, line: &str) {
+        let Ok(mut guard) = self.stream(kind).lock() else {
+            return;
+        };
+        let stream = &mut *guard;
+        stream.bytes += line.len() + 1;
+        stream.lines.push_back(line.to_string());
+        if stream.bytes <= MAX_CAPTURE_BYTES {
             return;
         }
 
-        #[cfg(target_os = "windows")]
-        let mut child = {
-            let mut command = Command::new("cmd");
-            command
-                .args(["/D", "/S", "/C"])
-                .raw_arg(&trimmed)
-                .current_dir(&cwd)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .creation_flags(CREATE_NO_WINDOW);
-
-            match command.spawn() {
-                Ok(child) => child,
-                Err(e) => {
-                    let _ =
-                        app_handle.emit("kforge://command/log", format!("Failed to start: {}", e));
-
-                    if let Ok(mut guard) = state_handle.lock() {
-                        guard.running = false;
-                        guard.child_pid = None;
-                    }
-
-                    let _ = app_handle.emit("kforge://command/status", "idle");
-                    return;
+        self.truncated.store(true, Ordering::Relaxed);
+        while stream.bytes > MAX_CAPTURE_BYTES && stream.lines.len() > 1 {
+            if let Some(dropped) = stream.lines.pop_front() {
+                stream.bytes -= dropped.len() + 1;
+            }
+        }
+        // A single line longer than the limit keeps its end.
+        if stream.bytes > MAX_CAPTURE_BYTES {
+            if let Some(only) = stream.lines.front_mut() {
+                let mut cut = stream.bytes - MAX_CAPTURE_BYTES;
+                while !only.is_char_boundary(cut) {
+                    cut += 1;
                 }
+                only.drain(..cut);
+                stream.bytes = only.len() + 1;
             }
+        }
+    }
+
+    fn take(&self, kind: &str) -> String {
+        let Ok(mut stream) = self.stream(kind).lock() else {
+            return String::new();
         };
+        let captured = std::mem::take(&mut *stream);
+        let mut text = String::with_capacity(captured.bytes);
+        for line in captured.lines {
+            text.push_str(&line);
+            text.push('\n');
+        }
+        text
+    }
+}
 
-        #[cfg(not(target_os = "windows"))]
-        let mut child = match Command::new("sh")
-            .args(["-lc", &trimmed])
-            .current_dir(&cwd)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-        {
-            Ok(child) => child,
-            Err(e) => {
-                let _ = app_handle.emit("kforge://command/log", format!("Failed to start: {}", e));
-
-                if let Ok(mut guard) = state_handle.lock() {
-                    guard.running = false;
-                    guard.child_pid = None;
+fn run_in_terminal(
+    app: &AppHandle,
+    session_id: &str,
+    command: &str,
+    cwd: &str,
+    size: TerminalSize,
+) -> Option<CommandOutcome> {
+    let terminal::TerminalProcess {
+        terminal,
+        mut child,
+        mut output,
+    } = match terminal::spawn(command, cwd, size) {
+        Ok(process) => process,
+        Err(e) => return fail_to_start(app, session_id, e),
+    };
+
+    let Some(pid) = child.process_id() else {
+        let _ = child.kill();
+        return fail_to_start(app, session_id, "Failed to start: no process id".into());
+    };
+    let process = Arc::new(ProcessTree::attach_leader(pid));
+    attach_process(app, session_id, &process, Some(Arc::new(terminal)));
+
+    let had_visible_output = Arc::new(AtomicBool::new(false));
+    let reader = {
+        let app_clone = app.clone();
+        let id = session_id.to_string();
+        let output_seen = Arc::clone(&had_visible_output);
+
+        thread::spawn(move || {
+            let mut decoder = Utf8Decoder::default();
+            let mut lines = LineSplitter::default();
+            let mut counts = StreamCounts::default();
+            let mut buffer = [0u8; 8192];
+            let log = app_clone.state::<LogStore>();
+            let mut record = |line: String| {
+                let clean_line = strip_ansi_sequences(&line);
+                if !clean_line.trim().is_empty() {
+                    output_seen.store(true, Ordering::Relaxed);
                 }
+                log.push(&id, "output", &clean_line);
+            };
 
-                let _ = app_handle.emit("kforge://command/status", "idle");
-                return;
+            // Ends with EOF, or an error once the terminal has closed.
+            while let Ok(read) = output.read(&mut buffer) {
+                if read == 0 {
+                    break;
+                }
+                counts.bytes += read as u64;
+                counts.lines += buffer[..read].iter().filter(|&&b| b == b'\n').count() as u64;
+                let data = decoder.decode(&buffer[..read]);
+                if data.is_empty() {
+                    continue;
+                }
+                emit_output(&app_clone, &id, &data);
+                lines.push(&data).into_iter().for_each(&mut record);
             }
-        };
+            if let Some(line) = lines.finish() {
+                record(line);
+            }
+            counts
+        })
+    };
+
+    let exit_status = child.wait();
+
+    if process.is_alive() {
+        process.terminate(STOP_GRACE_PERIOD);
+    }
+
+    // Closing the terminal ends the output on platforms that keep it open
+    // until then.
+    app.state::<CommandRunnerState>()
+        .update(session_id, |session| session.terminal = None);
+    let stdout = reader.join().unwrap_or_default();
 
-        if let Ok(mut guard) = state_handle.lock() {
-            guard.child_pid = Some(child.id());
+    let mut exit = ProcessExit {
+        stdout,
+        ..ProcessExit::default()
+    };
+    if let Ok(status) = exit_status {
+        if status.success() && !had_visible_output.load(Ordering::Relaxed) {
+            report_silent_success(app, session_id, command);
         }
+        exit.exit_code = Some(status.exit_code() as i32);
+        exit.signal = status.signal().map(str::to_string);
+    }
+    complete(app, session_id, "exited", exit)
+}
 
-        let _ = app_handle.emit("kforge://command/status", "running");
+/// Emit (and optionally capture) each line of a piped stream; returns what
+/// went through it.
+fn pump_lines(
+    app: &AppHandle,
+    session_id: &str,
+    kind: &str,
+    stream: impl Read,
+    had_visible_output: &AtomicBool,
+    capture: Option<&CapturedOutput>,
+) -> StreamCounts {
+    let mut reader = BufReader::new(stream);
+    let mut counts = StreamCounts::default();
+    let mut buffer = Vec::new();
 
-        let had_visible_output = Arc::new(AtomicBool::new(false));
-        let stdout = child.stdout.take();
-        let stderr = child.stderr.take();
+    loop {
+        buffer.clear();
+        match reader.read_until(b'\n', &mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => {
+                counts.bytes += read as u64;
+                counts.lines += 1;
+            }
+        }
+        let line = String::from_utf8_lossy(&buffer);
+        let line = line.trim_end_matches(['\n', '\r']).to_string();
+        if let Some(capture) = capture {
+            capture.append(kind, &strip_ansi_sequences(&line));
+        }
+        emit_clean_line(app, session_id, kind, line, had_visible_output);
+    }
+    counts
+}
 
-        let stdout_handle = stdout.map(|stdout| {
-            let app_clone = app_handle.clone();
-            let output_seen = Arc::clone(&had_visible_output);
+fn run_with_pipes(
+    app: &AppHandle,
+    session_id: &str,
+    command: &str,
+    cwd: &str,
+    capture: Option<Arc<CapturedOutput>>,
+) -> Option<CommandOutcome> {
+    let mut child = match spawn_shell(command, cwd) {
+        Ok(child) => child,
+        Err(e) => return fail_to_start(app, session_id, format!("Failed to start: {}", e)),
+    };
 
-            thread::spawn(move || {
-                let reader = BufReader::new(stdout);
+    let process = Arc::new(ProcessTree::attach(&child));
+    attach_process(app, session_id, &process, None);
 
-                for line in reader.lines().map_while(Result::ok) {
-                    emit_clean_line(&app_clone, line, &output_seen);
-                }
-            })
-        });
+    let had_visible_output = Arc::new(AtomicBool::new(false));
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
 
-        let stderr_handle = stderr.map(|stderr| {
-            let app_clone = app_handle.clone();
-            let output_seen = Arc::clone(&had_visible_output);
+    let stdout_handle = stdout.map(|stdout| {
+        let app_clone = app.clone();
+        let id = session_id.to_string();
+        let output_seen = Arc::clone(&had_visible_output);
+        let capture = capture.clone();
 
-            thread::spawn(move || {
-                let reader = BufReader::new(stderr);
+        thread::spawn(move || {
+            pump_lines(
+                &app_clone,
+                &id,
+                "stdout",
+                stdout,
+                &output_seen,
+                capture.as_deref(),
+            )
+        })
+    });
 
-                for line in reader.lines().map_while(Result::ok) {
-                    emit_clean_line(&app_clone, line, &output_seen);
-                }
-            })
-        });
+    let stderr_handle = stderr.map(|stderr| {
+        let app_clone = app.clone();
+        let id = session_id.to_string();
+        let output_seen = Arc::clone(&had_visible_output);
+        let capture = capture.clone();
 
-        let exit_status = child.wait();
+        thread::spawn(move || {
+            pump_lines(
+                &app_clone,
+                &id,
+                "stderr",
+                stderr,
+                &output_seen,
+                capture.as_deref(),
+            )
+        })
+    });
 
-        if let Some(handle) = stdout_handle {
-            let _ = handle.join();
-        }
+    let exit_status = child.wait();
 
-        if let Some(handle) = stderr_handle {
-            let _ = handle.join();
-        }
+    // Background jobs started by the shell would otherwise outlive it
+    // and keep the output pipes open.
+    if process.is_alive() {
+        process.terminate(STOP_GRACE_PERIOD);
+    }
+
+    let mut exit = ProcessExit::default();
+    if let Some(handle) = stdout_handle {
+        exit.stdout = handle.join().unwrap_or_default();
+    }
+
+    if let Some(handle) = stderr_handle {
+        exit.stderr = handle.join().unwrap_or_default();
+    }
+
+    let completed_successfully = exit_status
+        .as_ref()
+        .map(|status| status.success())
+        .unwrap_or(false);
+
+    if completed_successfully && !had_visible_output.load(Ordering::Relaxed) {
+        report_silent_success(app, session_id, command);
+    }
+
+    if let Ok(status) = &exit_status {
+        exit.exit_code = status.code();
+        exit.signal = exit_signal(status);
+    }
+    complete(app, session_id, "exited", exit)
+}
+
+/// Stop a running session. False when it is unknown or already finished.
+fn stop_session(app: &AppHandle, session_id: &str) -> bool {
+    let state = app.state::<CommandRunnerState>();
+    let Some(process) = state.request_stop(session_id) else {
+        return false;
+    };
+    emit_status(app, state.update(session_id, |_| {}));
 
-        let completed_successfully = exit_status
-            .as_ref()
-            .map(|status| status.success())
-            .unwrap_or(false);
-
-        if completed_successfully && !had_visible_output.load(Ordering::Relaxed) {
-            if is_git_status_short(&trimmed) {
-                let _ = app_handle.emit("kforge://command/log", "Working tree clean.");
-            } else {
-                let _ = app_handle.emit("kforge://command/log", "Command completed successfully.");
-                let _ = app_handle.emit("kforge://command/log", "No output returned.");
+    // Not spawned yet: `attach_process` terminates it once it is.
+    if let Some(process) = process {
+        let app_stop = app.clone();
+        let id_stop = session_id.to_string();
+        thread::spawn(move || {
+            if process.terminate(STOP_GRACE_PERIOD) == StopOutcome::Killed {
+                emit_log(
+                    &app_stop,
+                    &id_stop,
+                    "stderr",
+                    format!(
+                        "Process did not exit within {}s and was force killed.",
+                        STOP_GRACE_PERIOD.as_secs()
+                    ),
+                );
             }
-        }
+        });
+    }
 
-        if let Ok(mut guard) = state_handle.lock() {
-            guard.running = false;
-            guard.child_pid = None;
-        }
+    emit_log(app, session_id, "status", "Process stopped.");
+    true
+}
 
-        let _ = app_handle.emit("kforge://command/status", "idle");
-    });
+/// Start `command` in `cwd` as a new session and return it. `name` is a
+/// label for the UI; sessions are addressed by id. With `terminal` the
+/// command runs in a pseudo-terminal of that size, for interactive tools.
+/// Commands the policy flags as destructive need the `confirmation_token`
+/// that `command_check` issued for them.
+pub fn start_session(
+    app: &AppHandle,
+    command: &str,
+    cwd: String,
+    name: Option<String>,
+    terminal: Option<TerminalSize>,
+    confirmation_token: Option<&str>,
+) -> Result<CommandSessionInfo, String> {
+    let trimmed = command.trim().to_string();
+    if trimmed.is_empty() {
+        return Err("Empty command".into());
+    }
+    app.state::<CommandPolicyState>()
+        .authorize(&trimmed, &cwd, confirmation_token)?;
+
+    let session =
+        app.state::<CommandRunnerState>()
+            .create(name, &trimmed, &cwd, terminal.is_some());
+    emit_status(app, Some(session.clone()));
+
+    let app_handle = app.clone();
+    let session_id = session.id.clone();
+    thread::spawn(move || run_session(app_handle, session_id, trimmed, cwd, terminal));
 
-    Ok(())
+    Ok(session)
 }
 
+/// See [`start_session`].
 #[tauri::command]
-pub fn command_stop(
+pub fn command_run(
+    app: AppHandle,
+    command: String,
+    cwd: String,
+    name: Option<String>,
+    terminal: Option<TerminalSize>,
+    confirmation_token: Option<String>,
+) -> Result<CommandSessionInfo, String> {
+    start_session(
+        &app,
+        &command,
+        cwd,
+        name,
+        terminal,
+        confirmation_token.as_deref(),
+    )
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandCapture {
+    #[serde(flatten)]
+    pub outcome: CommandOutcome,
+    /// Output without escape sequences, one line per line.
+    pub stdout: String,
+    pub stderr: String,
+    /// Output beyond the capture limit was dropped from the start.
+    pub truncated: bool,
+    /// Stopped because `timeoutSecs` ran out.
+    pub timed_out: bool,
+}
+
+fn capture_session(
+    app: &AppHandle,
+    session_id: &str,
+    command: &str,
+    cwd: &str,
+    timeout: Option<Duration>,
+) -> Result<CommandCapture, String> {
+    app.state::<LogStore>().push(
+        session_id,
+        "command",
+        &format!("$ {} (cwd: {})", command, cwd),
+    );
+
+    let timed_out = Arc::new(AtomicBool::new(false));
+    let (done_tx, done_rx) = mpsc::channel::<()>();
+    if let Some(timeout) = timeout {
+        let app_timer = app.clone();
+        let id_timer = session_id.to_string();
+        let timed_out = Arc::clone(&timed_out);
+        thread::spawn(move || {
+            if done_rx.recv_timeout(timeout) == Err(mpsc::RecvTimeoutError::Timeout) {
+                timed_out.store(true, Ordering::Relaxed);
+                stop_session(&app_timer, &id_timer);
+            }
+        });
+    }
+
+    let capture = Arc::new(CapturedOutput::default());
+    let outcome = run_with_pipes(app, session_id, command, cwd, Some(Arc::clone(&capture)));
+    let _ = done_tx.send(());
+    let outcome = outcome.ok_or_else(|| format!("Command session {} was lost", session_id))?;
+
+    Ok(CommandCapture {
+        outcome,
+        stdout: capture.take("stdout"),
+        stderr: capture.take("stderr"),
+        truncated: capture.truncated.load(Ordering::Relaxed),
+        timed_out: timed_out.load(Ordering::Relaxed),
+    })
+}
+
+/// Run `command` to completion and return its outcome with the captured
+/// output, for agent loops that need the result before going on. It is an
+/// ordinary session meanwhile: listed, logged and stoppable.
+#[tauri::command]
+pub async fn command_capture(
     app: AppHandle,
-    state: tauri::State<'_, Arc<Mutex<CommandRunnerState>>>,
+    command: String,
+    cwd: String,
+    name: Option<String>,
+    timeout_secs: Option<u64>,
+    confirmation_token: Option<String>,
+) -> Result<CommandCapture, String> {
+    let trimmed = command.trim().to_string();
+    if trimmed.is_empty() {
+        return Err("Empty command".into());
+    }
+    app.state::<CommandPolicyState>()
+        .authorize(&trimmed, &cwd, confirmation_token.as_deref())?;
+
+    let session = app
+        .state::<CommandRunnerState>()
+        .create(name, &trimmed, &cwd, false);
+    emit_status(&app, Some(session.clone()));
+
+    tauri::async_runtime::spawn_blocking(move || {
+        capture_session(
+            &app,
+            &session.id,
+            &trimmed,
+            &cwd,
+            timeout_secs.map(Duration::from_secs),
+        )
+    })
+    .await
+    .map_err(|e| format!("Command task join error: {}", e))?
+}
+
+/// Input for a terminal session, as typed (control keys included).
+#[tauri::command]
+pub fn command_write(
+    state: tauri::State<'_, CommandRunnerState>,
+    session_id: String,
+    data: String,
 ) -> Result<(), String> {
-    let pid = {
-        let mut guard = state.lock().unwrap();
+    state.terminal(&session_id)?.write(data.as_bytes())
+}
 
-        if !guard.running {
-            return Ok(());
+#[tauri::command]
+pub fn command_resize(
+    state: tauri::State<'_, CommandRunnerState>,
+    session_id: String,
+    size: TerminalSize,
+) -> Result<(), String> {
+    state.terminal(&session_id)?.resize(size)
+}
+
+/// Running and recently finished sessions, oldest first.
+#[tauri::command]
+pub fn command_sessions(
+    state: tauri::State<'_, CommandRunnerState>,
+) -> Result<Vec<CommandSessionInfo>, String> {
+    Ok(state.list())
+}
+
+/// Stop one session, or every running session when no id is given. Returns
+/// the ids that were asked to stop.
+#[tauri::command]
+pub fn command_stop(
+    app: AppHandle,
+    state: tauri::State<'_, CommandRunnerState>,
+    session_id: Option<String>,
+) -> Result<Vec<String>, String> {
+    let targets = match session_id {
+        Some(id) => {
+            if !state.list().iter().any(|info| info.id == id) {
+                return Err(format!("No command session with id {}", id));
+            }
+            vec![id]
         }
+        None => state.running_ids(),
+    };
+
+    Ok(targets
+        .into_iter()
+        .filter(|id| stop_session(&app, id))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
 
-        let pid = guard.child_pid;
-        guard.running = false;
-        guard.child_pid = None;
-        pid
+    use super::{
+        CapturedOutput, CommandRunnerState, ProcessExit, StreamCounts, MAX_CAPTURE_BYTES,
+        MAX_FINISHED_SESSIONS,
     };
 
-    if let Some(pid) = pid {
-        #[cfg(target_os = "windows")]
-        {
-            let mut command = Command::new("taskkill");
-            command
-                .args(["/PID", &pid.to_string(), "/T", "/F"])
-                .creation_flags(CREATE_NO_WINDOW);
-            let _ = command.status();
+    #[test]
+    fn keeps_running_sessions_and_prunes_old_finished_ones() {
+        let state = CommandRunnerState::default();
+        let watcher = state.create(Some("tests".to_string()), "pnpm test --watch", "/p", false);
+        let build = state.create(None, "pnpm build", "/p", true);
+        assert_ne!(watcher.id, build.id);
+
+        let exit = ProcessExit {
+            exit_code: Some(1),
+            stderr: StreamCounts {
+                bytes: 42,
+                lines: 2,
+            },
+            ..ProcessExit::default()
+        };
+        let finished = state.finish(&build.id, "exited", exit).unwrap();
+        assert_eq!(finished.status, "exited");
+        assert_eq!(finished.exit_code, Some(1));
+        let outcome = finished.outcome.unwrap();
+        assert!(!outcome.success);
+        assert_eq!((outcome.stderr_bytes, outcome.stderr_lines), (42, 2));
+        assert_eq!(state.running_ids(), vec![watcher.id.clone()]);
+
+        assert!(state.request_stop(&watcher.id).is_some());
+        assert!(state.request_stop(&build.id).is_none());
+        let exit = ProcessExit {
+            exit_code: Some(0),
+            ..ProcessExit::default()
+        };
+        let stopped = state.finish(&watcher.id, "exited", exit).unwrap();
+        assert_eq!(stopped.status, "stopped");
+        assert!(!stopped.outcome.unwrap().success);
+
+        for _ in 0..MAX_FINISHED_SESSIONS {
+            let extra = state.create(None, "true", "/p", false);
+            state.finish(&extra.id, "exited", ProcessExit::default());
         }
+        let ids: Vec<String> = state.list().into_iter().map(|info| info.id).collect();
+        assert_eq!(ids.len(), MAX_FINISHED_SESSIONS);
+        assert!(!ids.contains(&watcher.id));
+        assert!(!ids.contains(&build.id));
+    }
 
-        #[cfg(not(target_os = "windows"))]
-        {
-            let _ = Command::new("kill")
-                .args(["-TERM", &pid.to_string()])
-                .status();
+    #[test]
+    fn capture_keeps_the_end_of_long_output() {
+        let capture = CapturedOutput::default();
+        capture.append("stderr", "warning: é");
+        assert!(!capture.truncated.load(Ordering::Relaxed));
+
+        let line = "é".repeat(500);
+        for _ in 0..(MAX_CAPTURE_BYTES / 1000 + 10) {
+            capture.append("stdout", &line);
         }
+        capture.append("stdout", "last line");
 
-        let _ = app.emit("kforge://command/log", "Process stopped.");
-    }
+        let stdout = capture.take("stdout");
+        assert!(capture.truncated.load(Ordering::Relaxed));
+        assert!(stdout.len() <= MAX_CAPTURE_BYTES);
+        // Whole lines are dropped from the start.
+        assert!(stdout.starts_with(&format!("{line}\n")));
+        assert!(stdout.ends_with("last line\n"));
+        assert_eq!(capture.take("stderr"), "warning: é\n");
 
-    let _ = app.emit("kforge://command/status", "idle");
-    Ok(())
+        capture.append("stdout", &"x".repeat(MAX_CAPTURE_BYTES + 10));
+        let stdout = capture.take("stdout");
+        assert_eq!(stdout.len(), MAX_CAPTURE_BYTES);
+        assert!(stdout.ends_with("xx\n"));
+    }
 }
@@ -0,0 +1,1097 @@
+// src-tauri/src/edit_protocol.rs
+//
+// Applies model-proposed edits to a project. Two formats are accepted, and may
+// be mixed in one response:
+//
+// - unified diffs (`--- a/path`, `+++ b/path`, `@@ -l,c +l,c @@` hunks)
+// - SEARCH/REPLACE blocks:
+//
+//       src/App.jsx
+//       <<<<<<< SEARCH
+//       old lines
+//       =======
+//       new lines
+//       >>>>>>> REPLACE
+//
+// Every hunk is checked against the current file contents first. If any hunk
+// does not apply, nothing is written and each failure is reported. Otherwise
+// all files are written together (temp file + rename, rolled back on failure)
+// and the previous contents are kept as a snapshot that `edit_undo` restores.
+
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+use crate::temp_file;
+
+const MAX_EDIT_FILE_BYTES: u64 = 2_000_000;
+const MAX_SNAPSHOTS: usize = 20;
+
+const SEARCH_MARKER: &str = "<<<<<<< SEARCH";
+const DIVIDER_MARKER: &str = "=======";
+const REPLACE_MARKER: &str = ">>>>>>> REPLACE";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FileAction {
+    Modify,
+    Create,
+    Delete,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Hunk {
+    /// 1-based old-file line from a `@@` header. Used to place pure
+    /// insertions and to choose between repeated matches.
+    line_hint: Option<usize>,
+    old: Vec<String>,
+    new: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct FileEdit {
+    path: String,
+    action: FileAction,
+    hunks: Vec<Hunk>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HunkDiagnostic {
+    pub path: String,
+    /// 1-based hunk number within the file; 0 for problems with the file itself.
+    pub hunk: usize,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditFileSummary {
+    pub path: String,
+    pub action: &'static str, // "create" | "modify" | "delete"
+    pub hunks: usize,
+    pub added_lines: usize,
+    pub removed_lines: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditReport {
+    pub applied: bool,
+    pub files: Vec<EditFileSummary>,
+    pub diagnostics: Vec<HunkDiagnostic>,
+    /// Set when the edits were written; pass to `edit_undo`.
+    pub snapshot_id: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditSnapshotSummary {
+    pub id: u64,
+    pub project_path: String,
+    pub created_at_unix: u64,
+    pub files: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+struct SnapshotFile {
+    path: PathBuf,
+    /// Contents before the edit; None when the edit created the file.
+    before: Option<String>,
+    /// Contents written by the edit; None when the edit deleted the file.
+    after: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+struct EditSnapshot {
+    id: u64,
+    root: PathBuf,
+    created_at_unix: u64,
+    rel_paths: Vec<String>,
+    files: Vec<SnapshotFile>,
+}
+
+#[derive(Default)]
+pub struct EditHistoryState {
+    inner: Mutex<EditHistory>,
+}
+
+#[derive(Default)]
+struct EditHistory {
+    next_id: u64,
+    snapshots: Vec<EditSnapshot>,
+}
+
+/// One file's planned change. `before`/`after` are the raw on-disk texts.
+struct FileChange {
+    rel: String,
+    abs: PathBuf,
+    before: Option<String>,
+    after: Option<String>,
+    action: FileAction,
+    hunks: usize,
+    added: usize,
+    removed: usize,
+}
+
+/// Working copy of a file while hunks are applied. Lines keep their own
+/// `\r`, so untouched lines are written back exactly as they were; lines an
+/// edit adds get the file's majority ending.
+struct WorkingText {
+    lines: Vec<String>,
+    trailing_newline: bool,
+    crlf: bool,
+}
+
+impl WorkingText {
+    fn parse(raw: &str) -> Self {
+        let crlf = raw.matches("\r\n").count() * 2 > raw.matches('\n').count();
+        let trailing_newline = raw.is_empty() || raw.ends_with('\n');
+        let body = raw.strip_suffix('\n').unwrap_or(raw);
+        let lines = if body.is_empty() && raw.len() <= 1 {
+            Vec::new()
+        } else {
+            body.split('\n').map(str::to_string).collect()
+        };
+        Self {
+            lines,
+            trailing_newline,
+            crlf,
+        }
+    }
+
+    /// `new` lines as stored in this file, with its majority line ending.
+    fn incoming(&self, new: &[String]) -> Vec<String> {
+        new.iter()
+            .map(|line| {
+                if self.crlf {
+                    format!("{line}\r")
+                } else {
+                    line.clone()
+                }
+            })
+            .collect()
+    }
+
+    fn render(&self) -> String {
+        if self.lines.is_empty() {
+            return String::new();
+        }
+        let mut out = self.lines.join("\n");
+        if self.trailing_newline {
+            out.push('\n');
+        }
+        out
+    }
+}
+
+// -------------------- Parsing --------------------
+
+fn parse_edits(text: &str) -> Result<Vec<FileEdit>, String> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut edits = Vec::new();
+    let mut last_text_line: Option<&str> = None;
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+
+        if line.starts_with("--- ") && lines.get(i + 1).is_some_and(|l| l.starts_with("+++ ")) {
+            let (edit, next) = parse_unified_file(&lines, i)?;
+            edits.push(edit);
+            i = next;
+            last_text_line = None;
+            continue;
+        }
+
+        if line.trim() == SEARCH_MARKER {
+            let path = last_text_line
+                .map(clean_block_path)
+                .filter(|p| !p.is_empty())
+                .ok_or_else(|| {
+                    format!(
+                        "SEARCH block at line {} is not preceded by a file path",
+                        i + 1
+                    )
+                })?;
+            let (edit, next) = parse_search_replace(&lines, i, path)?;
+            edits.push(edit);
+            i = next;
+            last_text_line = None;
+            continue;
+        }
+
+        let trimmed = line.trim();
+        if !trimmed.is_empty() && !trimmed.starts_with("```") {
+            last_text_line = Some(trimmed);
+        }
+        i += 1;
+    }
+
+    if edits.is_empty() {
+        return Err("No unified diff or SEARCH/REPLACE blocks were found".into());
+    }
+    Ok(edits)
+}
+
+fn parse_unified_file(lines: &[&str], start: usize) -> Result<(FileEdit, usize), String> {
+    let old_path = diff_header_path(&lines[start][4..]);
+    let new_path = diff_header_path(&lines[start + 1][4..]);
+
+    let (path, action) = match (old_path, new_path) {
+        (None, Some(new)) => (new, FileAction::Create),
+        (Some(old), None) => (old, FileAction::Delete),
+        (Some(_), Some(new)) => (new, FileAction::Modify),
+        (None, None) => return Err(format!("Diff header at line {} names no file", start + 1)),
+    };
+
+    let mut hunks = Vec::new();
+    let mut i = start + 2;
+    while i < lines.len() && lines[i].starts_with("@@") {
+        let line_hint = parse_hunk_start(lines[i])
+            .ok_or_else(|| format!("Malformed hunk header at line {}: {}", i + 1, lines[i]))?;
+        i += 1;
+
+        let mut hunk = Hunk {
+            line_hint: Some(line_hint),
+            old: Vec::new(),
+            new: Vec::new(),
+        };
+        // Blank lines count as blank context, but only when more hunk lines follow.
+        let mut pending_blank = 0;
+        while i < lines.len() {
+            let line = lines[i];
+            if line.starts_with("@@") || is_file_header(lines, i) || line.starts_with("diff ") {
+                break;
+            }
+            if line.is_empty() {
+                pending_blank += 1;
+                i += 1;
+                continue;
+            }
+            let marker = line.as_bytes()[0];
+            if !matches!(marker, b' ' | b'-' | b'+' | b'\\') {
+                break;
+            }
+            let body = &line[1..];
+            for _ in 0..pending_blank {
+                hunk.old.push(String::new());
+                hunk.new.push(String::new());
+            }
+            pending_blank = 0;
+            match marker {
+                b' ' => {
+                    hunk.old.push(body.to_string());
+                    hunk.new.push(body.to_string());
+                }
+                b'-' => hunk.old.push(body.to_string()),
+                b'+' => hunk.new.push(body.to_string()),
+                _ => {} // "\ No newline at end of file"
+            }
+            i += 1;
+        }
+
+        if hunk.old.is_empty() && hunk.new.is_empty() {
+            return Err(format!("Empty hunk in diff for {path}"));
+        }
+        hunks.push(hunk);
+    }
+
+    if hunks.is_empty() && action != FileAction::Delete {
+        return Err(format!("Diff for {path} has no hunks"));
+    }
+
+    Ok((
+        FileEdit {
+            path,
+            action,
+            hunks,
+        },
+        i,
+    ))
+}
+
+fn parse_search_replace(
+    lines: &[&str],
+    start: usize,
+    path: String,
+) -> Result<(FileEdit, usize), String> {
+    let mut search = Vec::new();
+    let mut replace = Vec::new();
+    let mut i = start + 1;
+
+    while i < lines.len() && lines[i].trim() != DIVIDER_MARKER {
+        search.push(lines[i].to_string());
+        i += 1;
+    }
+    if i == lines.len() {
+        return Err(format!(
+            "SEARCH block for {path} at line {} has no ======= divider",
+            start + 1
+        ));
+    }
+    i += 1;
+
+    while i < lines.len() && lines[i].trim() != REPLACE_MARKER {
+        replace.push(lines[i].to_string());
+        i += 1;
+    }
+    if i == lines.len() {
+        return Err(format!(
+            "SEARCH block for {path} at line {} has no >>>>>>> REPLACE marker",
+            start + 1
+        ));
+    }
+
+    // An empty SEARCH section creates a new file.
+    let action = if search.iter().all(|l| l.trim().is_empty()) {
+        search.clear();
+        FileAction::Create
+    } else {
+        FileAction::Modify
+    };
+
+    Ok((
+        FileEdit {
+            path,
+            action,
+            hunks: vec![Hunk {
+                line_hint: None,
+                old: search,
+                new: replace,
+            }],
+        },
+        i + 1,
+    ))
+}
+
+fn is_file_header(lines: &[&str], i: usize) -> bool {
+    lines[i].starts_with("--- ") && lines.get(i + 1).is_some_and(|l| l.starts_with("+++ "))
+}
+
+/// `a/src/App.jsx\t2024-01-01 ...` -> Some("src/App.jsx"); `/dev/null` -> None.
+fn diff_header_path(raw: &str) -> Option<String> {
+    let raw = raw.split('\t').next().unwrap_or(raw).trim();
+    if raw == "/dev/null" || raw.is_empty() {
+        return None;
+    }
+    let path = raw
+        .strip_prefix("a/")
+        .or_else(|| raw.strip_prefix("b/"))
+        .unwrap_or(raw);
+    Some(path.to_string())
+}
+
+/// Models often decorate the path line with markdown: `**src/App.jsx**`, `` `x` ``.
+fn clean_block_path(raw: &str) -> String {
+    raw.trim()
+        .trim_start_matches('#')
+        .trim()
+        .trim_matches(|c| matches!(c, '`' | '*' | ':' | '"' | '\''))
+        .trim()
+        .to_string()
+}
+
+/// `@@ -12,7 +12,8 @@ fn x()` -> 12. `-0,0` (new file) -> 0.
+fn parse_hunk_start(header: &str) -> Option<usize> {
+    let old_range = header
+        .strip_prefix("@@")?
+        .split_whitespace()
+        .find(|part| part.starts_with('-'))?;
+    old_range[1..].split(',').next()?.parse().ok()
+}
+
+// -------------------- Planning --------------------
+
+fn resolve_edit_path(root: &Path, rel: &str) -> Result<PathBuf, String> {
+    let rel = rel.trim().replace('\\', "/");
+    let rel = rel.strip_prefix("./").unwrap_or(&rel);
+    if rel.is_empty() {
+        return Err("file path is empty".into());
+    }
+
+    let path = Path::new(rel);
+    for component in path.components() {
+        match component {
+            Component::Normal(part) if part == ".git" => {
+                return Err("edits inside .git are not allowed".into())
+            }
+            Component::Normal(_) | Component::CurDir => {}
+            _ => return Err("file path must be relative to the project and stay inside it".into()),
+        }
+    }
+
+    let joined = root.join(path);
+
+    // Guard against symlinks that point out of the project.
+    let canonical_root = root
+        .canonicalize()
+        .map_err(|e| format!("project folder is unavailable: {e}"))?;
+    let mut existing = joined.as_path();
+    while !existing.exists() {
+        existing = match existing.parent() {
+            Some(parent) => parent,
+            None => break,
+        };
+    }
+    if let Ok(canonical) = existing.canonicalize() {
+        if !canonical.starts_with(&canonical_root) {
+            return Err("file path resolves outside the project".into());
+        }
+    }
+
+    Ok(joined)
+}
+
+fn read_edit_target(path: &Path) -> Result<Option<String>, String> {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("file is unavailable: {e}")),
+    };
+    if !metadata.is_file() {
+        return Err("path is not a regular file".into());
+    }
+    if metadata.len() > MAX_EDIT_FILE_BYTES {
+        return Err("file is too large to edit".into());
+    }
+    fs::read_to_string(path)
+        .map(Some)
+        .map_err(|_| "file is not valid UTF-8 text".into())
+}
+
+/// Validate every edit against the current files. Returns the planned changes
+/// in first-touched order, plus diagnostics for every hunk that did not apply.
+fn plan_edits(root: &Path, edits: &[FileEdit]) -> (Vec<FileChange>, Vec<HunkDiagnostic>) {
+    struct Working {
+        change: FileChange,
+        text: WorkingText,
+        exists: bool,
+        next_hunk: usize,
+    }
+
+    let mut working: Vec<Working> = Vec::new();
+    let mut diagnostics = Vec::new();
+
+    for edit in edits {
+        let file_diag = |message: String| HunkDiagnostic {
+            path: edit.path.clone(),
+            hunk: 0,
+            message,
+        };
+
+        let abs = match resolve_edit_path(root, &edit.path) {
+            Ok(abs) => abs,
+            Err(e) => {
+                diagnostics.push(file_diag(e));
+                continue;
+            }
+        };
+
+        let index = match working.iter().position(|w| w.change.abs == abs) {
+            Some(index) => index,
+            None => {
+                let before = match read_edit_target(&abs) {
+                    Ok(before) => before,
+                    Err(e) => {
+                        diagnostics.push(file_diag(e));
+                        continue;
+                    }
+                };
+                let text = before
+                    .as_deref()
+                    .map(WorkingText::parse)
+                    .unwrap_or(WorkingText {
+                        lines: Vec::new(),
+                        trailing_newline: true,
+                        crlf: false,
+                    });
+                working.push(Working {
+                    exists: before.is_some(),
+                    change: FileChange {
+                        rel: edit.path.clone(),
+                        abs,
+                        before,
+                        after: None,
+                        action: FileAction::Modify,
+                        hunks: 0,
+                        added: 0,
+                        removed: 0,
+                    },
+                    text,
+                    next_hunk: 1,
+                });
+                working.len() - 1
+            }
+        };
+        let w = &mut working[index];
+
+        match (edit.action, w.exists) {
+            (FileAction::Create, true) if !w.text.lines.is_empty() => {
+                diagnostics.push(file_diag(
+                    "file already exists; new-file edits must target a missing or empty file"
+                        .into(),
+                ));
+                continue;
+            }
+            (FileAction::Modify | FileAction::Delete, false) => {
+                diagnostics.push(file_diag("file does not exist".into()));
+                continue;
+            }
+            _ => {}
+        }
+
+        // Hints refer to the original file; shift them by earlier hunks in this diff.
+        let mut offset: isize = 0;
+        for hunk in &edit.hunks {
+            let number = w.next_hunk;
+            w.next_hunk += 1;
+            let hint = hunk
+                .line_hint
+                .map(|h| (h as isize + offset).max(0) as usize);
+            match apply_hunk(&mut w.text, hunk, hint) {
+                Ok(()) => {
+                    offset += hunk.new.len() as isize - hunk.old.len() as isize;
+                    w.change.hunks += 1;
+                    w.change.added += hunk.new.len();
+                    w.change.removed += hunk.old.len();
+                }
+                Err(message) => diagnostics.push(HunkDiagnostic {
+                    path: edit.path.clone(),
+                    hunk: number,
+                    message,
+                }),
+            }
+        }
+
+        match edit.action {
+            FileAction::Delete => {
+                if !w.text.lines.iter().all(|l| l.trim().is_empty()) {
+                    diagnostics.push(file_diag(
+                        "deletion diff does not remove the whole file".into(),
+                    ));
+                }
+                w.text.lines.clear();
+                w.exists = false;
+                w.change.action = FileAction::Delete;
+            }
+            FileAction::Create => {
+                w.exists = true;
+                if w.change.before.is_none() {
+                    w.change.action = FileAction::Create;
+                }
+            }
+            FileAction::Modify => {}
+        }
+    }
+
+    let changes = working
+        .into_iter()
+        .map(|mut w| {
+            w.change.after = w.exists.then(|| w.text.render());
+            w.change
+        })
+        .filter(|c| c.after != c.before)
+        .collect();
+
+    (changes, diagnostics)
+}
+
+fn apply_hunk(text: &mut WorkingText, hunk: &Hunk, hint: Option<usize>) -> Result<(), String> {
+    let new = text.incoming(&hunk.new);
+    let lines = &mut text.lines;
+    if hunk.old.is_empty() {
+        return match hint {
+            // `@@ -N,0 ...` inserts after line N.
+            Some(after) if after <= lines.len() => {
+                lines.splice(after..after, new);
+                Ok(())
+            }
+            Some(after) => Err(format!(
+                "insertion after line {after} is past the end of the file ({} lines)",
+                lines.len()
+            )),
+            None if lines.is_empty() => {
+                lines.extend(new);
+                Ok(())
+            }
+            None => {
+                Err("empty SEARCH section only creates new files; this file has content".into())
+            }
+        };
+    }
+
+    let mut matches = find_block(lines, &hunk.old, |a, b| {
+        a.strip_suffix('\r').unwrap_or(a) == b
+    });
+    if matches.is_empty() {
+        // Models frequently drop trailing whitespace.
+        matches = find_block(lines, &hunk.old, |a, b| a.trim_end() == b.trim_end());
+    }
+
+    let start = match matches.len() {
+        0 => return Err(mismatch_message(lines, &hunk.old)),
+        1 => matches[0],
+        n => {
+            let Some(hint) = hint else {
+                return Err(format!(
+                    "search text matches {n} places; include more surrounding lines so it is unique"
+                ));
+            };
+            let target = hint.saturating_sub(1);
+            let mut ranked: Vec<(usize, usize)> =
+                matches.iter().map(|&m| (m.abs_diff(target), m)).collect();
+            ranked.sort();
+            if ranked[0].0 == ranked[1].0 {
+                return Err(format!(
+                    "hunk context matches {n} places equally close to line {hint}"
+                ));
+            }
+            ranked[0].1
+        }
+    };
+
+    lines.splice(start..start + hunk.old.len(), new);
+    Ok(())
+}
+
+fn find_block(lines: &[String], block: &[String], eq: impl Fn(&str, &str) -> bool) -> Vec<usize> {
+    if block.len() > lines.len() {
+        return Vec::new();
+    }
+    (0..=lines.len() - block.len())
+        .filter(|&start| {
+            block
+                .iter()
+                .zip(&lines[start..])
+                .all(|(expected, actual)| eq(actual, expected))
+        })
+        .collect()
+}
+
+fn mismatch_message(lines: &[String], old: &[String]) -> String {
+    let missing = old.iter().find(|expected| {
+        !expected.trim().is_empty() && !lines.iter().any(|l| l.trim() == expected.trim())
+    });
+    match missing {
+        Some(line) => format!(
+            "does not match the current file; this line was not found: {}",
+            line.trim()
+        ),
+        None => "does not match the current file; the lines exist but not in this order".into(),
+    }
+}
+
+// -------------------- Writing --------------------
+
+/// Write every change or none. New contents are staged next to their targets
+/// first, so the rename phase only fails on unusual filesystem errors; if it
+/// does, files already replaced are restored.
+fn commit_changes(changes: &[FileChange]) -> Result<(), String> {
+    // The temp file of each change, in order; None for deletions.
+    let mut staged: Vec<Option<PathBuf>> = Vec::new();
+    let cleanup = |staged: &[Option<PathBuf>]| {
+        for temp in staged.iter().flatten() {
+            let _ = fs::remove_file(temp);
+        }
+    };
+
+    for change in changes {
+        let Some(after) = &change.after else {
+            staged.push(None);
+            continue;
+        };
+        let temp = temp_file::sibling(&change.abs);
+        let result = (|| {
+            if let Some(parent) = change.abs.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&temp, after)?;
+            if let Ok(metadata) = fs::metadata(&change.abs) {
+                fs::set_permissions(&temp, metadata.permissions())?;
+            }
+            Ok::<(), std::io::Error>(())
+        })();
+        staged.push(Some(temp));
+        if let Err(e) = result {
+            cleanup(&staged);
+            return Err(format!("Failed to write {}: {e}", change.rel));
+        }
+    }
+
+    for (done, (change, temp)) in changes.iter().zip(&staged).enumerate() {
+        let result = match temp {
+            Some(temp) => fs::rename(temp, &change.abs),
+            None => fs::remove_file(&change.abs),
+        };
+        if let Err(e) = result {
+            for previous in &changes[..done] {
+                restore(previous);
+            }
+            cleanup(&staged);
+            return Err(format!(
+                "Failed to update {}: {e}. Earlier files in this edit were restored.",
+                change.rel
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn restore(change: &FileChange) {
+    let _ = match &change.before {
+        Some(before) => fs::write(&change.abs, before),
+        None => fs::remove_file(&change.abs),
+    };
+}
+
+fn summarize(changes: &[FileChange]) -> Vec<EditFileSummary> {
+    changes
+        .iter()
+        .map(|c| EditFileSummary {
+            path: c.rel.clone(),
+            action: match c.action {
+                FileAction::Create => "create",
+                FileAction::Modify => "modify",
+                FileAction::Delete => "delete",
+            },
+            hunks: c.hunks,
+            added_lines: c.added,
+            removed_lines: c.removed,
+        })
+        .collect()
+}
+
+fn validate_project_path(path: &str) -> Result<PathBuf, String> {
+    let trimmed = path.trim();
+    if trimmed.is_empty() {
+        return Err("Project path is empty".to_string());
+    }
+
+    let pb = PathBuf::from(trimmed);
+    if !pb.is_dir() {
+        return Err(format!("Project path is not a folder: {}", trimmed));
+    }
+
+    Ok(pb)
+}
+
+fn check_edits(
+    project_path: &str,
+    patch_text: &str,
+) -> Result<(PathBuf, Vec<FileChange>, EditReport), String> {
+    let root = validate_project_path(project_path)?;
+    let edits = parse_edits(patch_text)?;
+    let (changes, diagnostics) = plan_edits(&root, &edits);
+    let report = EditReport {
+        applied: false,
+        files: summarize(&changes),
+        diagnostics,
+        snapshot_id: None,
+    };
+    Ok((root, changes, report))
+}
+
+fn apply_edits(
+    history: &EditHistoryState,
+    project_path: &str,
+    patch_text: &str,
+) -> Result<EditReport, String> {
+    let (root, changes, mut report) = check_edits(project_path, patch_text)?;
+    if !report.diagnostics.is_empty() || changes.is_empty() {
+        return Ok(report);
+    }
+
+    commit_changes(&changes)?;
+
+    let mut guard = history
+        .inner
+        .lock()
+        .map_err(|_| "Edit history lock poisoned".to_string())?;
+    guard.next_id += 1;
+    let id = guard.next_id;
+    guard.snapshots.push(EditSnapshot {
+        id,
+        root,
+        created_at_unix: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+        rel_paths: changes.iter().map(|c| c.rel.clone()).collect(),
+        files: changes
+            .into_iter()
+            .map(|c| SnapshotFile {
+                path: c.abs,
+                before: c.before,
+                after: c.after,
+            })
+            .collect(),
+    });
+    if guard.snapshots.len() > MAX_SNAPSHOTS {
+        guard.snapshots.remove(0);
+    }
+
+    report.applied = true;
+    report.snapshot_id = Some(id);
+    Ok(report)
+}
+
+fn undo_edits(
+    history: &EditHistoryState,
+    snapshot_id: u64,
+    force: bool,
+) -> Result<Vec<String>, String> {
+    let mut guard = history
+        .inner
+        .lock()
+        .map_err(|_| "Edit history lock poisoned".to_string())?;
+    let index = guard
+        .snapshots
+        .iter()
+        .position(|s| s.id == snapshot_id)
+        .ok_or_else(|| format!("No edit snapshot with id {snapshot_id}"))?;
+    let snapshot = &guard.snapshots[index];
+
+    let mut changes = Vec::new();
+    let mut modified_since = Vec::new();
+    for (file, rel) in snapshot.files.iter().zip(&snapshot.rel_paths) {
+        let current = read_edit_target(&file.path).unwrap_or(None);
+        if current != file.after {
+            modified_since.push(rel.clone());
+        }
+        changes.push(FileChange {
+            rel: rel.clone(),
+            abs: file.path.clone(),
+            before: current,
+            after: file.before.clone(),
+            action: FileAction::Modify,
+            hunks: 0,
+            added: 0,
+            removed: 0,
+        });
+    }
+
+    if !modified_since.is_empty() && !force {
+        return Err(format!(
+            "These files changed after the edit was applied: {}. Undo with force to overwrite them.",
+            modified_since.join(", ")
+        ));
+    }
+
+    commit_changes(&changes)?;
+    let snapshot = guard.snapshots.remove(index);
+    Ok(snapshot.rel_paths)
+}
+
+// -------------------- Commands --------------------
+
+/// Parse and validate edits without writing anything.
+#[tauri::command]
+pub fn edit_preview(project_path: String, patch_text: String) -> Result<EditReport, String> {
+    check_edits(&project_path, &patch_text).map(|(_, _, report)| report)
+}
+
+/// Apply all edits, or none when any hunk fails (see `diagnostics`).
+#[tauri::command]
+pub fn edit_apply(
+    state: tauri::State<EditHistoryState>,
+    project_path: String,
+    patch_text: String,
+) -> Result<EditReport, String> {
+    apply_edits(&state, &project_path, &patch_text)
+}
+
+/// Restore the files touched by an applied edit. Refuses when any of them
+/// changed afterwards, unless `force` is set. Returns the restored paths.
+#[tauri::command]
+pub fn edit_undo(
+    state: tauri::State<EditHistoryState>,
+    snapshot_id: u64,
+    force: Option<bool>,
+) -> Result<Vec<String>, String> {
+    undo_edits(&state, snapshot_id, force.unwrap_or(false))
+}
+
+#[tauri::command]
+pub fn edit_list_snapshots(
+    state: tauri::State<EditHistoryState>,
+) -> Result<Vec<EditSnapshotSummary>, String> {
+    let guard = state
+        .inner
+        .lock()
+        .map_err(|_| "Edit history lock poisoned".to_string())?;
+    Ok(guard
+        .snapshots
+        .iter()
+        .rev()
+        .map(|s| EditSnapshotSummary {
+            id: s.id,
+            project_path: s.root.to_string_lossy().to_string(),
+            created_at_unix: s.created_at_unix,
+            files: s.rel_paths.clone(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::tempdir;
+
+    use super::{apply_edits, parse_edits, undo_edits, EditHistoryState, FileAction};
+
+    #[test]
+    fn parses_unified_diffs_and_search_replace_blocks_together() {
+        let text = [
+            "Here is the change:",
+            "```diff",
+            "--- a/src/a.js",
+            "+++ b/src/a.js",
+            "@@ -1,2 +1,2 @@",
+            " one",
+            "-two",
+            "+TWO",
+            "```",
+            "**src/b.js**",
+            "```js",
+            "<<<<<<< SEARCH",
+            "old",
+            "=======",
+            "new",
+            ">>>>>>> REPLACE",
+            "```",
+            "--- /dev/null",
+            "+++ b/src/c.js",
+            "@@ -0,0 +1 @@",
+            "+created",
+        ]
+        .join("\n");
+
+        let edits = parse_edits(&text).unwrap();
+        assert_eq!(edits.len(), 3);
+        assert_eq!(edits[0].path, "src/a.js");
+        assert_eq!(edits[0].hunks[0].old, vec!["one", "two"]);
+        assert_eq!(edits[0].hunks[0].new, vec!["one", "TWO"]);
+        assert_eq!(edits[1].path, "src/b.js");
+        assert_eq!(edits[1].hunks[0].new, vec!["new"]);
+        assert_eq!(edits[2].action, FileAction::Create);
+        assert!(parse_edits("no edits here").is_err());
+    }
+
+    #[test]
+    fn applies_across_files_atomically_and_undoes() {
+        let project = tempdir().unwrap();
+        fs::write(project.path().join("a.txt"), "alpha\r\nbeta\r\n").unwrap();
+        fs::write(project.path().join("b.txt"), "one\ntwo\n").unwrap();
+        let history = EditHistoryState::default();
+        let root = project.path().to_string_lossy().to_string();
+
+        let text = "a.txt\n<<<<<<< SEARCH\nbeta\n=======\nBETA\n>>>>>>> REPLACE\n\
+--- a/b.txt\n+++ /dev/null\n@@ -1,2 +0,0 @@\n-one\n-two\n\
+--- /dev/null\n+++ b/new/c.txt\n@@ -0,0 +1 @@\n+fresh\n";
+        let report = apply_edits(&history, &root, text).unwrap();
+        assert!(report.applied, "{:?}", report.diagnostics);
+        assert_eq!(
+            fs::read_to_string(project.path().join("a.txt")).unwrap(),
+            "alpha\r\nBETA\r\n"
+        );
+        assert!(!project.path().join("b.txt").exists());
+        assert_eq!(
+            fs::read_to_string(project.path().join("new/c.txt")).unwrap(),
+            "fresh\n"
+        );
+
+        undo_edits(&history, report.snapshot_id.unwrap(), false).unwrap();
+        assert_eq!(
+            fs::read_to_string(project.path().join("a.txt")).unwrap(),
+            "alpha\r\nbeta\r\n"
+        );
+        assert_eq!(
+            fs::read_to_string(project.path().join("b.txt")).unwrap(),
+            "one\ntwo\n"
+        );
+        assert!(!project.path().join("new/c.txt").exists());
+    }
+
+    #[test]
+    fn keeps_each_line_ending_and_uses_the_majority_for_new_lines() {
+        let project = tempdir().unwrap();
+        let path = project.path().join("mixed.txt");
+        fs::write(&path, "one\ntwo\r\nthree\nfour\n").unwrap();
+        let history = EditHistoryState::default();
+        let root = project.path().to_string_lossy().to_string();
+
+        let text = "mixed.txt\n<<<<<<< SEARCH\nthree\n=======\nTHREE\nand more\n>>>>>>> REPLACE\n";
+        let report = apply_edits(&history, &root, text).unwrap();
+        assert!(report.applied, "{:?}", report.diagnostics);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "one\ntwo\r\nTHREE\nand more\nfour\n"
+        );
+
+        let text = "mixed.txt\n<<<<<<< SEARCH\ntwo\n=======\nTWO\n>>>>>>> REPLACE\n";
+        assert!(apply_edits(&history, &root, text).unwrap().applied);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "one\nTWO\nTHREE\nand more\nfour\n"
+        );
+    }
+
+    #[test]
+    fn rejects_everything_when_one_hunk_fails() {
+        let project = tempdir().unwrap();
+        fs::write(project.path().join("a.txt"), "keep\nme\n").unwrap();
+        fs::write(project.path().join("b.txt"), "x\nx\n").unwrap();
+        let history = EditHistoryState::default();
+        let root = project.path().to_string_lossy().to_string();
+
+        let text = "a.txt\n<<<<<<< SEARCH\nme\n=======\nyou\n>>>>>>> REPLACE\n\
+b.txt\n<<<<<<< SEARCH\nx\n=======\ny\n>>>>>>> REPLACE\n\
+c.txt\n<<<<<<< SEARCH\nmissing\n=======\nz\n>>>>>>> REPLACE\n\
+../escape.txt\n<<<<<<< SEARCH\n=======\nz\n>>>>>>> REPLACE\n";
+        let report = apply_edits(&history, &root, text).unwrap();
+
+        assert!(!report.applied);
+        assert!(report.snapshot_id.is_none());
+        let by_path: Vec<(&str, usize)> = report
+            .diagnostics
+            .iter()
+            .map(|d| (d.path.as_str(), d.hunk))
+            .collect();
+        assert_eq!(
+            by_path,
+            vec![("b.txt", 1), ("c.txt", 0), ("../escape.txt", 0)]
+        );
+        assert!(report.diagnostics[0].message.contains("matches 2 places"));
+        assert_eq!(
+            fs::read_to_string(project.path().join("a.txt")).unwrap(),
+            "keep\nme\n"
+        );
+    }
+
+    #[test]
+    fn unified_hunk_line_numbers_break_ties_and_undo_refuses_later_changes() {
+        let project = tempdir().unwrap();
+        let path = project.path().join("a.txt");
+        fs::write(&path, "x\nend\nx\nend\n").unwrap();
+        let history = EditHistoryState::default();
+        let root = project.path().to_string_lossy().to_string();
+
+        let text = "--- a/a.txt\n+++ b/a.txt\n@@ -3,2 +3,2 @@\n x\n-end\n+END\n";
+        let report = apply_edits(&history, &root, text).unwrap();
+        assert!(report.applied, "{:?}", report.diagnostics);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "x\nend\nx\nEND\n");
+
+        fs::write(&path, "edited by hand\n").unwrap();
+        let id = report.snapshot_id.unwrap();
+        assert!(undo_edits(&history, id, false).is_err());
+        undo_edits(&history, id, true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "x\nend\nx\nend\n");
+    }
+}
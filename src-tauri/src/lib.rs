@@ -6,11 +6,22 @@ use tauri_plugin_fs::FsExt;
 use tauri_plugin_shell::ShellExt;
 
 mod ai;
+mod ansi;
+mod command_policy;
 mod command_runner;
+mod edit_protocol;
+mod log_buffer;
+mod package_manager;
 mod preview;
+mod process_tree;
+mod project_files;
 mod scaffold;
+mod secret_vault;
 mod service;
 mod supabase_autopilot;
+mod task_discovery;
+mod temp_file;
+mod terminal;
 
 /// Allow a user-selected directory to be used by the FS plugin.
 /// This updates the runtime FS scope (safer than broad wildcards).
@@ -33,15 +44,26 @@ pub fn run() {
     let _ = rustls::crypto::ring::default_provider().install_default();
 
     tauri::Builder::default()
+        .setup(|app| {
+            secret_vault::init(app.handle());
+            // A broken registry file does not block startup; key lookups for
+            // a project and ai_list_credential_profiles report the error.
+            ai::credential_profiles::init(app.handle());
+            // A policy that fails to load blocks commands and reports why
+            // through command_policy_get.
+            command_policy::init(app.handle());
+            Ok(())
+        })
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_opener::init())
         .manage(preview::PreviewState::default())
-        .manage(Arc::new(Mutex::new(
-            command_runner::CommandRunnerState::default(),
-        )))
+        .manage(log_buffer::LogStore::default())
+        .manage(command_runner::CommandRunnerState::default())
+        .manage(command_policy::CommandPolicyState::default())
         .manage(Arc::new(Mutex::new(service::ServiceRunnerState::default())))
         .manage(supabase_autopilot::SupabaseAutopilotState::default())
+        .manage(edit_protocol::EditHistoryState::default())
         .plugin(tauri_plugin_shell::init())
         .invoke_handler(tauri::generate_handler![
             fs_allow_directory,
@@ -51,15 +73,48 @@ pub fn run() {
             ai::commands::ai_has_api_key,
             ai::commands::ai_clear_api_key,
             ai::commands::ai_is_key_persisted,
+            ai::commands::ai_key_vault_status,
+            ai::commands::ai_unlock_key_vault,
+            ai::commands::ai_lock_key_vault,
+            ai::commands::ai_set_profile_api_key,
+            ai::commands::ai_clear_profile_api_key,
+            ai::commands::ai_list_credential_profiles,
+            ai::commands::ai_list_project_profiles,
+            ai::commands::ai_assign_project_profile,
+            ai::commands::ai_remove_project_profile,
+            ai::commands::ai_export_secrets,
+            ai::commands::ai_import_secrets,
+            ai::commands::ai_rotate_api_key,
+            ai::commands::ai_pack_project_context,
             ai::commands::ai_generate,
             ai::commands::ai_ollama_list_models,
             preview::preview_detect_kind,
             preview::preview_get_status,
+            preview::preview_https_certificate,
+            preview::preview_requests,
             preview::preview_install,
+            preview::preview_list,
             preview::preview_start,
             preview::preview_stop,
             command_runner::command_run,
+            command_runner::command_capture,
+            command_runner::command_sessions,
             command_runner::command_stop,
+            command_runner::command_write,
+            command_runner::command_resize,
+            task_discovery::task_discover,
+            task_discovery::task_run,
+            command_policy::command_check,
+            command_policy::command_policy_get,
+            command_policy::command_policy_set,
+            command_policy::command_audit_log,
+            log_buffer::log_recent,
+            log_buffer::log_search,
+            log_buffer::log_export,
+            edit_protocol::edit_preview,
+            edit_protocol::edit_apply,
+            edit_protocol::edit_undo,
+            edit_protocol::edit_list_snapshots,
             service::service_setup,
             service::github_detect_repo,
             service::github_open_repo,
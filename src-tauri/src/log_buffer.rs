@@ -0,0 +1,368 @@
+// src-tauri/src/log_buffer.rs
+//
+// Recent output of every runner (each preview and command session), kept in
+// Rust so a panel that mounts late or a reloaded frontend can catch up.
+// Events stay the live feed; these buffers are the backlog behind them.
+//
+// Each runner gets a bounded ring of timestamped lines. Buffers outlive the
+// process that wrote them so a finished build or command can still be read,
+// up to `MAX_RUNNERS`, after which the least recently written one goes.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+/// Lines kept per runner.
+pub const LOG_CAPACITY: usize = 5000;
+const MAX_RUNNERS: usize = 32;
+const DEFAULT_RECENT_LINES: usize = 500;
+const DEFAULT_SEARCH_RESULTS: usize = 200;
+
+/// Preview messages that belong to no single preview (e.g. a refused start).
+/// Otherwise previews and command sessions use their own ids as runner ids.
+pub const PREVIEW_RUNNER: &str = "preview";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogLine {
+    /// Increases by one per line within a runner; use it to page.
+    pub seq: u64,
+    pub timestamp_ms: u64,
+    pub kind: String,
+    pub line: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogMatch {
+    pub runner: String,
+    #[serde(flatten)]
+    pub line: LogLine,
+}
+
+struct LogBuffer {
+    lines: VecDeque<LogLine>,
+    capacity: usize,
+    next_seq: u64,
+    last_write: u64,
+}
+
+impl LogBuffer {
+    fn new(capacity: usize) -> Self {
+        Self {
+            lines: VecDeque::new(),
+            capacity,
+            next_seq: 0,
+            last_write: 0,
+        }
+    }
+
+    fn push(&mut self, kind: &str, line: &str, write_order: u64) {
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+        self.next_seq += 1;
+        self.last_write = write_order;
+        self.lines.push_back(LogLine {
+            seq: self.next_seq,
+            timestamp_ms: now_ms(),
+            kind: kind.to_string(),
+            line: line.to_string(),
+        });
+    }
+
+    /// Lines written before the oldest one still held.
+    fn dropped(&self) -> u64 {
+        self.lines
+            .front()
+            .map(|first| first.seq - 1)
+            .unwrap_or(self.next_seq)
+    }
+}
+
+#[derive(Default)]
+pub struct LogStore {
+    inner: Mutex<LogStoreInner>,
+}
+
+#[derive(Default)]
+struct LogStoreInner {
+    buffers: HashMap<String, LogBuffer>,
+    writes: u64,
+}
+
+impl LogStore {
+    pub fn push(&self, runner: &str, kind: &str, line: &str) {
+        let Ok(mut inner) = self.inner.lock() else {
+            return;
+        };
+        inner.writes += 1;
+        let write_order = inner.writes;
+
+        if !inner.buffers.contains_key(runner) && inner.buffers.len() >= MAX_RUNNERS {
+            let oldest = inner
+                .buffers
+                .iter()
+                .min_by_key(|(_, buffer)| buffer.last_write)
+                .map(|(id, _)| id.clone());
+            if let Some(oldest) = oldest {
+                inner.buffers.remove(&oldest);
+            }
+        }
+
+        inner
+            .buffers
+            .entry(runner.to_string())
+            .or_insert_with(|| LogBuffer::new(LOG_CAPACITY))
+            .push(kind, line, write_order);
+    }
+
+    /// The last `limit` lines, or only those after `after_seq` when given.
+    pub fn recent(&self, runner: &str, limit: usize, after_seq: Option<u64>) -> Vec<LogLine> {
+        let Ok(inner) = self.inner.lock() else {
+            return Vec::new();
+        };
+        let Some(buffer) = inner.buffers.get(runner) else {
+            return Vec::new();
+        };
+
+        let matching: Vec<&LogLine> = buffer
+            .lines
+            .iter()
+            .filter(|line| after_seq.is_none_or(|after| line.seq > after))
+            .collect();
+        let start = matching.len().saturating_sub(limit);
+        matching[start..]
+            .iter()
+            .map(|line| (*line).clone())
+            .collect()
+    }
+
+    /// Case-insensitive substring search, oldest match first, keeping the
+    /// newest `limit` matches. Searches every runner when `runner` is None.
+    pub fn search(&self, runner: Option<&str>, query: &str, limit: usize) -> Vec<LogMatch> {
+        let needle = query.to_lowercase();
+        let Ok(inner) = self.inner.lock() else {
+            return Vec::new();
+        };
+
+        let mut matches: Vec<LogMatch> = inner
+            .buffers
+            .iter()
+            .filter(|(id, _)| runner.is_none_or(|runner| runner == id.as_str()))
+            .flat_map(|(id, buffer)| {
+                buffer
+                    .lines
+                    .iter()
+                    .filter(|line| line.line.to_lowercase().contains(&needle))
+                    .map(move |line| LogMatch {
+                        runner: id.clone(),
+                        line: line.clone(),
+                    })
+            })
+            .collect();
+        matches.sort_by_key(|found| (found.line.timestamp_ms, found.line.seq));
+        let excess = matches.len().saturating_sub(limit);
+        matches.drain(..excess);
+        matches
+    }
+
+    /// Write everything held for `runner` to `path`. Returns the line count.
+    pub fn export(&self, runner: &str, path: &Path) -> Result<usize, String> {
+        let (lines, dropped) = {
+            let inner = self
+                .inner
+                .lock()
+                .map_err(|_| "Log store lock poisoned".to_string())?;
+            let buffer = inner
+                .buffers
+                .get(runner)
+                .ok_or_else(|| format!("No log recorded for {}", runner))?;
+            (
+                buffer.lines.iter().cloned().collect::<Vec<_>>(),
+                buffer.dropped(),
+            )
+        };
+
+        let file = fs::File::create(path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        let mut out = BufWriter::new(file);
+        let write_err = |e: std::io::Error| format!("Failed to write {}: {}", path.display(), e);
+
+        writeln!(out, "# KForge log: {}", runner).map_err(write_err)?;
+        if dropped > 0 {
+            writeln!(
+                out,
+                "# {} earlier lines were dropped (only the last {} are kept)",
+                dropped, LOG_CAPACITY
+            )
+            .map_err(write_err)?;
+        }
+        for line in &lines {
+            writeln!(
+                out,
+                "{} [{}] {}",
+                format_timestamp(line.timestamp_ms),
+                line.kind,
+                line.line
+            )
+            .map_err(write_err)?;
+        }
+        out.flush().map_err(write_err)?;
+
+        Ok(lines.len())
+    }
+}
+
+/// Milliseconds since the Unix epoch; shared by every timestamped record.
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// `2024-05-01T12:34:56.789Z` without pulling in a date crate.
+fn format_timestamp(ms: u64) -> String {
+    let secs = ms / 1000;
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60,
+        ms % 1000
+    )
+}
+
+#[tauri::command]
+pub fn log_recent(
+    store: tauri::State<LogStore>,
+    runner: String,
+    limit: Option<usize>,
+    after_seq: Option<u64>,
+) -> Vec<LogLine> {
+    store.recent(
+        &runner,
+        limit.unwrap_or(DEFAULT_RECENT_LINES).min(LOG_CAPACITY),
+        after_seq,
+    )
+}
+
+#[tauri::command]
+pub fn log_search(
+    store: tauri::State<LogStore>,
+    query: String,
+    runner: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<LogMatch>, String> {
+    if query.trim().is_empty() {
+        return Err("Search text is empty".to_string());
+    }
+    Ok(store.search(
+        runner.as_deref(),
+        &query,
+        limit.unwrap_or(DEFAULT_SEARCH_RESULTS),
+    ))
+}
+
+#[tauri::command]
+pub fn log_export(
+    store: tauri::State<LogStore>,
+    runner: String,
+    path: String,
+) -> Result<usize, String> {
+    let path = PathBuf::from(path.trim());
+    if path.as_os_str().is_empty() {
+        return Err("Export path is empty".to_string());
+    }
+    store.export(&runner, &path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::tempdir;
+
+    use super::{format_timestamp, LogBuffer, LogStore, LOG_CAPACITY};
+
+    #[test]
+    fn keeps_a_bounded_window_and_pages_by_sequence() {
+        let mut buffer = LogBuffer::new(3);
+        for n in 1..=5 {
+            buffer.push("stdout", &format!("line {n}"), n);
+        }
+        assert_eq!(buffer.lines.len(), 3);
+        assert_eq!(buffer.lines.front().unwrap().seq, 3);
+        assert_eq!(buffer.dropped(), 2);
+
+        let store = LogStore::default();
+        for n in 1..=4 {
+            store.push("preview-1", "stdout", &format!("ready on port {n}"));
+        }
+        store.push("command", "output", "Port 3000 READY");
+
+        let recent = store.recent("preview-1", 2, None);
+        assert_eq!(recent.iter().map(|l| l.seq).collect::<Vec<_>>(), vec![3, 4]);
+        assert_eq!(store.recent("preview-1", 10, Some(3)).len(), 1);
+        assert!(store.recent("missing", 10, None).is_empty());
+
+        assert_eq!(store.search(None, "ready", 10).len(), 5);
+        let scoped = store.search(Some("command"), "ready", 10);
+        assert_eq!(scoped.len(), 1);
+        assert_eq!(scoped[0].runner, "command");
+
+        let newest = store.search(Some("preview-1"), "ready", 2);
+        assert_eq!(
+            newest.iter().map(|m| m.line.seq).collect::<Vec<_>>(),
+            vec![3, 4]
+        );
+    }
+
+    #[test]
+    fn exports_the_session_with_timestamps() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(
+            format_timestamp(1_709_210_096_789),
+            "2024-02-29T12:34:56.789Z"
+        );
+
+        let store = LogStore::default();
+        for n in 0..LOG_CAPACITY + 2 {
+            store.push("preview-2", "stderr", &format!("line {n}"));
+        }
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("preview.log");
+        assert_eq!(store.export("preview-2", &path).unwrap(), LOG_CAPACITY);
+
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.contains("2 earlier lines were dropped"), "{text}");
+        assert!(text.trim_end().ends_with("[stderr] line 5001"));
+        assert!(store.export("missing", &path).is_err());
+    }
+}
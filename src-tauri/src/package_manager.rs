@@ -0,0 +1,321 @@
+// src-tauri/src/package_manager.rs
+//
+// Which JavaScript package manager a project uses, and how to invoke it.
+// Shared by preview (install/dev/build), scaffolding and local inspection.
+
+use std::{collections::BTreeSet, fs, path::Path, process::Command};
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+use serde::Serialize;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageManager {
+    Npm,
+    Yarn,
+    Pnpm,
+    Bun,
+}
+
+pub const LOCKFILES: &[(&str, PackageManager)] = &[
+    ("pnpm-lock.yaml", PackageManager::Pnpm),
+    ("package-lock.json", PackageManager::Npm),
+    ("yarn.lock", PackageManager::Yarn),
+    ("bun.lock", PackageManager::Bun),
+    ("bun.lockb", PackageManager::Bun),
+];
+
+/// KForge's own templates and docs assume pnpm.
+pub const DEFAULT_PACKAGE_MANAGER: PackageManager = PackageManager::Pnpm;
+
+impl PackageManager {
+    pub fn parse(value: &str) -> Option<Self> {
+        // `packageManager` values look like `pnpm@9.1.0`.
+        let name = value.trim().split('@').next().unwrap_or_default();
+        match name.to_ascii_lowercase().as_str() {
+            "npm" => Some(Self::Npm),
+            "yarn" => Some(Self::Yarn),
+            "pnpm" => Some(Self::Pnpm),
+            "bun" => Some(Self::Bun),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Npm => "npm",
+            Self::Yarn => "yarn",
+            Self::Pnpm => "pnpm",
+            Self::Bun => "bun",
+        }
+    }
+
+    /// Executable to spawn directly (Windows needs the `.cmd` shims).
+    pub fn program(self) -> &'static str {
+        match (self, cfg!(windows)) {
+            (Self::Npm, true) => "npm.cmd",
+            (Self::Yarn, true) => "yarn.cmd",
+            (Self::Pnpm, true) => "pnpm.cmd",
+            (Self::Bun, _) => "bun",
+            (other, false) => other.name(),
+        }
+    }
+
+    pub fn install_args(self) -> Vec<String> {
+        vec!["install".to_string()]
+    }
+
+    /// Arguments for running a package.json script.
+    pub fn run_args(self, script: &str) -> Vec<String> {
+        match self {
+            Self::Npm | Self::Bun => vec!["run".to_string(), script.to_string()],
+            Self::Yarn | Self::Pnpm => vec![script.to_string()],
+        }
+    }
+
+    /// Program and arguments for running a package binary without installing
+    /// it (`npx`, `pnpm dlx`, `yarn dlx`, `bunx`). Yarn 1.x has no `dlx`, so
+    /// classic Yarn goes through `npx` like npm.
+    pub fn exec(self, package: &str, args: &[&str]) -> (&'static str, Vec<String>) {
+        let classic_yarn = self == Self::Yarn && is_classic_yarn(yarn_version().as_deref());
+        self.exec_with(classic_yarn, package, args)
+    }
+
+    fn exec_with(
+        self,
+        classic_yarn: bool,
+        package: &str,
+        args: &[&str],
+    ) -> (&'static str, Vec<String>) {
+        let (program, mut full_args) = match self {
+            Self::Yarn if classic_yarn => return Self::Npm.exec_with(false, package, args),
+            Self::Npm => (
+                if cfg!(windows) { "npx.cmd" } else { "npx" },
+                vec!["--yes".to_string()],
+            ),
+            Self::Pnpm | Self::Yarn => (self.program(), vec!["dlx".to_string()]),
+            Self::Bun => ("bunx", Vec::new()),
+        };
+        full_args.push(package.to_string());
+        full_args.extend(args.iter().map(|arg| arg.to_string()));
+        (program, full_args)
+    }
+
+    /// Whether the manager can be found on PATH.
+    pub fn is_installed(self) -> bool {
+        is_on_path(self.name())
+    }
+
+    pub fn not_installed_message(self, reason: &str) -> String {
+        format!(
+            "This project uses {} ({}), but {} could not be found. Install {} or restart KForge after adding it to your PATH.",
+            self.name(),
+            reason,
+            self.name(),
+            self.name()
+        )
+    }
+}
+
+/// Whether `program` can be found on PATH (through a login shell on Unix,
+/// so version managers set up in the profile are seen too).
+pub fn is_on_path(program: &str) -> bool {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("where");
+        command.arg(program);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.args(["-lc", &format!("command -v {}", program)]);
+        command
+    };
+
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW);
+
+    command
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// Output of `yarn --version` (through a login shell on Unix, so version
+/// managers set up in the profile are seen too).
+fn yarn_version() -> Option<String> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new(PackageManager::Yarn.program());
+        command.arg("--version");
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.args(["-lc", "yarn --version"]);
+        command
+    };
+
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW);
+
+    let output = command.output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Yarn 1.x (or a version we could not read, where `npx` is the safer bet).
+fn is_classic_yarn(version: Option<&str>) -> bool {
+    match version.and_then(|v| v.split('.').next()?.parse::<u32>().ok()) {
+        Some(major) => major < 2,
+        None => true,
+    }
+}
+
+/// A single lockfile decides; with none, a valid `packageManager` field does.
+/// Conflicting lockfiles are ambiguous and give None.
+pub fn detect_package_manager(
+    declared: Option<&str>,
+    root_files: &BTreeSet<String>,
+) -> Option<PackageManager> {
+    let managers = LOCKFILES
+        .iter()
+        .filter(|(file, _)| root_files.contains(*file))
+        .map(|(_, manager)| manager.name())
+        .collect::<BTreeSet<_>>();
+
+    match managers.len() {
+        1 => managers
+            .iter()
+            .next()
+            .and_then(|name| PackageManager::parse(name)),
+        0 => declared.and_then(PackageManager::parse),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectPackageManager {
+    pub manager: PackageManager,
+    /// Why this manager was picked, for logs and warnings.
+    pub reason: String,
+}
+
+/// The manager to run a project's scripts with. Never fails: ambiguous or
+/// empty projects fall back to the declared manager, then pnpm.
+pub fn for_project(root: &Path) -> ProjectPackageManager {
+    let declared = fs::read_to_string(root.join("package.json"))
+        .ok()
+        .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+        .and_then(|package| {
+            package
+                .get("packageManager")
+                .and_then(|value| value.as_str())
+                .map(str::to_string)
+        });
+    let declared_manager = declared.as_deref().and_then(PackageManager::parse);
+
+    let lockfiles: Vec<&str> = LOCKFILES
+        .iter()
+        .map(|(file, _)| *file)
+        .filter(|file| root.join(file).is_file())
+        .collect();
+    let root_files: BTreeSet<String> = lockfiles.iter().map(|file| file.to_string()).collect();
+
+    if let Some(manager) = detect_package_manager(declared.as_deref(), &root_files) {
+        let reason = match lockfiles.first() {
+            Some(lockfile) => format!("found {}", lockfile),
+            None => "packageManager in package.json".to_string(),
+        };
+        return ProjectPackageManager { manager, reason };
+    }
+
+    if lockfiles.len() > 1 {
+        let (manager, source) = match declared_manager {
+            Some(manager) => (manager, "packageManager in package.json"),
+            None => (
+                LOCKFILES
+                    .iter()
+                    .find(|(file, _)| *file == lockfiles[0])
+                    .map(|(_, manager)| *manager)
+                    .unwrap_or(DEFAULT_PACKAGE_MANAGER),
+                "first lockfile",
+            ),
+        };
+        return ProjectPackageManager {
+            manager,
+            reason: format!(
+                "several lockfiles ({}); using {} from {}",
+                lockfiles.join(", "),
+                manager.name(),
+                source
+            ),
+        };
+    }
+
+    ProjectPackageManager {
+        manager: DEFAULT_PACKAGE_MANAGER,
+        reason: "no lockfile; defaulting to pnpm".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::tempdir;
+
+    use super::{for_project, is_classic_yarn, PackageManager};
+
+    #[test]
+    fn picks_the_manager_from_lockfiles_then_package_json() {
+        let project = tempdir().unwrap();
+        fs::write(project.path().join("package.json"), "{}").unwrap();
+        assert_eq!(for_project(project.path()).manager, PackageManager::Pnpm);
+
+        fs::write(
+            project.path().join("package.json"),
+            r#"{"packageManager":"yarn@4.1.0"}"#,
+        )
+        .unwrap();
+        assert_eq!(for_project(project.path()).manager, PackageManager::Yarn);
+
+        fs::write(project.path().join("package-lock.json"), "{}").unwrap();
+        let detected = for_project(project.path());
+        assert_eq!(detected.manager, PackageManager::Npm);
+        assert_eq!(detected.reason, "found package-lock.json");
+
+        fs::write(project.path().join("bun.lockb"), "").unwrap();
+        let ambiguous = for_project(project.path());
+        assert_eq!(ambiguous.manager, PackageManager::Yarn);
+        assert!(ambiguous.reason.starts_with("several lockfiles"));
+    }
+
+    #[test]
+    fn builds_commands_for_each_manager() {
+        assert_eq!(PackageManager::Npm.run_args("dev"), ["run", "dev"]);
+        assert_eq!(PackageManager::Pnpm.run_args("build"), ["build"]);
+
+        let (_, npm) = PackageManager::Npm.exec("create-vite@latest", &["."]);
+        assert_eq!(npm, ["--yes", "create-vite@latest", "."]);
+        let (program, bun) = PackageManager::Bun.exec("create-vite@latest", &["."]);
+        assert_eq!((program, bun.len()), ("bunx", 2));
+
+        let (program, berry) = PackageManager::Yarn.exec_with(false, "create-vite@latest", &[]);
+        assert_eq!(program, PackageManager::Yarn.program());
+        assert_eq!(berry, ["dlx", "create-vite@latest"]);
+        let (_, classic) = PackageManager::Yarn.exec_with(true, "create-vite@latest", &[]);
+        assert_eq!(classic, ["--yes", "create-vite@latest"]);
+        assert!(is_classic_yarn(Some("1.22.22")));
+        assert!(!is_classic_yarn(Some("4.5.0")));
+        assert!(is_classic_yarn(None));
+        assert_eq!(
+            PackageManager::parse(" PNPM@9.0.0"),
+            Some(PackageManager::Pnpm)
+        );
+    }
+}
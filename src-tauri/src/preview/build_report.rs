@@ -0,0 +1,316 @@
+// src-tauri/src/preview/build_report.rs
+//
+// Production previews: finding the folder a build wrote, and turning a
+// failed build's output into structured diagnostics the UI can list.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::ansi::strip_ansi_sequences;
+
+/// Checked in order; the first one holding an `index.html` is served.
+const OUTPUT_DIRS: &[&str] = &["dist", "build", "out", ".output/public", "dist/client"];
+const NEXT_CONFIGS: &[&str] = &[
+    "next.config.js",
+    "next.config.mjs",
+    "next.config.cjs",
+    "next.config.ts",
+];
+const MAX_DIAGNOSTICS: usize = 20;
+/// Output lines kept with a failure report.
+pub const OUTPUT_TAIL_LINES: usize = 60;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildDiagnostic {
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildFailure {
+    pub exit_code: Option<i32>,
+    pub summary: String,
+    pub diagnostics: Vec<BuildDiagnostic>,
+    pub output_tail: Vec<String>,
+}
+
+/// The folder to serve after a successful build.
+pub fn detect_build_output(root: &Path) -> Result<PathBuf, String> {
+    let is_next = NEXT_CONFIGS.iter().any(|name| root.join(name).is_file());
+    if is_next {
+        let out = root.join("out");
+        if out.join("index.html").is_file() {
+            return Ok(out);
+        }
+        return Err(
+            "Next.js builds can only be previewed as a static export. Set `output: 'export'` in next.config and build again."
+                .to_string(),
+        );
+    }
+
+    OUTPUT_DIRS
+        .iter()
+        .map(|dir| root.join(dir))
+        .find(|dir| dir.join("index.html").is_file())
+        .ok_or_else(|| {
+            format!(
+                "The build finished but no output folder with index.html was found (looked in {}).",
+                OUTPUT_DIRS.join(", ")
+            )
+        })
+}
+
+fn split_trailing_number(text: &str) -> Option<(&str, u32)> {
+    let (head, tail) = text.rsplit_once(':')?;
+    Some((head, tail.parse().ok()?))
+}
+
+/// A source position found in a line, plus the byte range of its token.
+struct Location {
+    file: String,
+    line: Option<u32>,
+    column: Option<u32>,
+    start: usize,
+    end: usize,
+}
+
+/// `src/App.tsx:12:5`, `src/App.tsx(12,5)` or `src/App.tsx:12`.
+fn find_location(text: &str) -> Option<Location> {
+    for (start, token) in token_spans(text) {
+        let bare = token
+            .trim_start_matches(['(', '"', '\''])
+            .trim_end_matches([':', ',', '.', '"', '\'']);
+
+        let (path, line, column) = if let Some((path, rest)) = bare.split_once('(') {
+            let mut numbers = rest
+                .trim_end_matches(')')
+                .split(',')
+                .map(|n| n.trim().parse::<u32>().ok());
+            (path, numbers.next().flatten(), numbers.next().flatten())
+        } else {
+            match split_trailing_number(bare) {
+                Some((head, last)) => match split_trailing_number(head) {
+                    Some((path, line)) => (path, Some(line), Some(last)),
+                    None => (head, Some(last), None),
+                },
+                None => continue,
+            }
+        };
+
+        if line.is_some() && file_like(path) {
+            return Some(Location {
+                file: path.to_string(),
+                line,
+                column,
+                start,
+                end: start + token.len(),
+            });
+        }
+    }
+    None
+}
+
+fn token_spans(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split_whitespace().map(move |token| {
+        let start = token.as_ptr() as usize - text.as_ptr() as usize;
+        (start, token)
+    })
+}
+
+fn file_like(path: &str) -> bool {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    match name.rsplit_once('.') {
+        Some((stem, ext)) => {
+            !stem.is_empty()
+                && (1..=5).contains(&ext.len())
+                && ext.chars().all(|c| c.is_ascii_alphanumeric())
+        }
+        None => false,
+    }
+}
+
+fn is_error_line(lower: &str) -> bool {
+    // Skip tallies such as "Found 2 errors." or "0 errors".
+    let is_tally = lower.starts_with("found ") || lower.contains("0 errors");
+    (lower.contains("error") && !is_tally && !lower.contains("no errors"))
+        || lower.starts_with("failed to compile")
+        || lower.contains("could not resolve")
+        || lower.contains("cannot find module")
+}
+
+fn clean_message(text: &str) -> String {
+    text.trim()
+        .trim_start_matches(['✘', '×', '-', ':', '>', ' '])
+        .trim_start_matches("[ERROR]")
+        .trim_start_matches("ERROR")
+        .trim_start_matches(['-', ':', ' '])
+        .trim()
+        .to_string()
+}
+
+/// Best-effort diagnostics from TypeScript, Vite/Rollup, esbuild and Next
+/// build output. Lines that only carry a location are attached to the
+/// neighbouring error message.
+pub fn parse_build_output(lines: &[String]) -> Vec<BuildDiagnostic> {
+    let mut diagnostics: Vec<BuildDiagnostic> = Vec::new();
+    let mut pending_location: Option<Location> = None;
+
+    for raw in lines {
+        let line = strip_ansi_sequences(raw);
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let lower = trimmed.to_ascii_lowercase();
+        let location = find_location(trimmed);
+
+        if is_error_line(&lower) {
+            let (file, line_no, column, message) = match location {
+                Some(location) => {
+                    let before = &trimmed[..location.start];
+                    let after = &trimmed[location.end..];
+                    let message = if after.trim().is_empty() {
+                        before
+                    } else {
+                        after
+                    };
+                    (
+                        Some(location.file),
+                        location.line,
+                        location.column,
+                        clean_message(message),
+                    )
+                }
+                None => match pending_location.take() {
+                    Some(location) => (
+                        Some(location.file),
+                        location.line,
+                        location.column,
+                        clean_message(trimmed),
+                    ),
+                    None => (None, None, None, clean_message(trimmed)),
+                },
+            };
+            if message.is_empty() || message.eq_ignore_ascii_case("failed to compile.") {
+                continue;
+            }
+
+            let diagnostic = BuildDiagnostic {
+                file,
+                line: line_no,
+                column,
+                message,
+            };
+            if !diagnostics.contains(&diagnostic) {
+                diagnostics.push(diagnostic);
+            }
+            if diagnostics.len() >= MAX_DIAGNOSTICS {
+                break;
+            }
+            continue;
+        }
+
+        // A bare location line, e.g. Next's `./src/app/page.tsx:5:1` or the
+        // indented source position esbuild prints under `[ERROR]`.
+        if let Some(location) = location {
+            if location.start > 3 || trimmed.len() - location.end > 2 {
+                continue;
+            }
+            match diagnostics.last_mut() {
+                Some(last) if last.file.is_none() => {
+                    last.file = Some(location.file);
+                    last.line = location.line;
+                    last.column = location.column;
+                }
+                _ => pending_location = Some(location),
+            }
+        }
+    }
+
+    diagnostics
+}
+
+pub fn build_failure(exit_code: Option<i32>, output: &[String]) -> BuildFailure {
+    let diagnostics = parse_build_output(output);
+    let summary = match (diagnostics.first(), exit_code) {
+        (Some(first), _) => first.message.clone(),
+        (None, Some(code)) => format!("Build failed (exit code {})", code),
+        (None, None) => "Build was interrupted".to_string(),
+    };
+    let tail_start = output.len().saturating_sub(OUTPUT_TAIL_LINES);
+
+    BuildFailure {
+        exit_code,
+        summary,
+        diagnostics,
+        output_tail: output[tail_start..]
+            .iter()
+            .map(|l| strip_ansi_sequences(l))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::tempdir;
+
+    use super::{build_failure, detect_build_output, parse_build_output};
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn extracts_locations_from_common_build_tools() {
+        let tsc = parse_build_output(&lines(
+            "src/App.tsx(12,5): error TS2322: Type 'number' is not assignable to type 'string'.\n\
+             Found 1 error.",
+        ));
+        assert_eq!(tsc[0].file.as_deref(), Some("src/App.tsx"));
+        assert_eq!((tsc[0].line, tsc[0].column), (Some(12), Some(5)));
+        assert!(tsc[0].message.starts_with("error TS2322"), "{:?}", tsc[0]);
+
+        let esbuild = parse_build_output(&lines(
+            "✘ [ERROR] Could not resolve \"./missing\"\n\n    src/main.ts:3:19:\n      3 │ import x from \"./missing\";",
+        ));
+        assert_eq!(esbuild[0].message, "Could not resolve \"./missing\"");
+        assert_eq!(esbuild[0].file.as_deref(), Some("src/main.ts"));
+        assert_eq!(esbuild[0].line, Some(3));
+
+        let next = parse_build_output(&lines(
+            "Failed to compile.\n\n./src/app/page.tsx:5:1\nType error: Cannot find name 'foo'.",
+        ));
+        assert_eq!(next.len(), 1);
+        assert_eq!(next[0].file.as_deref(), Some("./src/app/page.tsx"));
+        assert_eq!(next[0].message, "Type error: Cannot find name 'foo'.");
+
+        let failure = build_failure(Some(2), &lines("vite v5.0.0 building\nsomething broke"));
+        assert!(failure.diagnostics.is_empty());
+        assert_eq!(failure.summary, "Build failed (exit code 2)");
+        assert_eq!(failure.output_tail.len(), 2);
+    }
+
+    #[test]
+    fn finds_the_build_output_folder() {
+        let project = tempdir().unwrap();
+        assert!(detect_build_output(project.path()).is_err());
+
+        fs::create_dir_all(project.path().join("build")).unwrap();
+        fs::write(project.path().join("build/index.html"), "<p>built</p>").unwrap();
+        assert_eq!(
+            detect_build_output(project.path()).unwrap(),
+            project.path().join("build")
+        );
+
+        fs::write(project.path().join("next.config.mjs"), "export default {}").unwrap();
+        let err = detect_build_output(project.path()).unwrap_err();
+        assert!(err.contains("static export"), "{err}");
+    }
+}
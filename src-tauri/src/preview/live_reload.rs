@@ -0,0 +1,235 @@
+// src-tauri/src/preview/live_reload.rs
+//
+// Live reload for the static preview server. A file watcher on the preview
+// root pushes server-sent events to every open page; a small script injected
+// into served HTML reloads the page, or swaps stylesheets in place when only
+// CSS changed.
+
+use std::{
+    io::{self, Write},
+    net::TcpStream,
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// SSE endpoint the injected script subscribes to.
+pub const LIVE_RELOAD_PATH: &str = "/__kforge/live-reload";
+
+/// Editors and KForge's own edit applier write several events per save.
+const DEBOUNCE: Duration = Duration::from_millis(150);
+/// Comment frames keep idle connections open and prune closed pages.
+const KEEPALIVE: Duration = Duration::from_secs(15);
+const CLIENT_WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+const IGNORED_DIRS: &[&str] = &[".git", "node_modules", ".kforge"];
+
+const RELOAD_SCRIPT: &str = r#"<script>
+(() => {
+  if (window.__kforgeLiveReload) return;
+  window.__kforgeLiveReload = true;
+  const source = new EventSource("/__kforge/live-reload");
+  source.addEventListener("reload", () => location.reload());
+  source.addEventListener("css", (event) => {
+    const changed = JSON.parse(event.data);
+    let swapped = false;
+    for (const link of document.querySelectorAll('link[rel="stylesheet"]')) {
+      const url = new URL(link.href, location.href);
+      if (url.origin !== location.origin) continue;
+      if (!changed.includes(decodeURI(url.pathname))) continue;
+      url.searchParams.set("kforge-reload", Date.now().toString());
+      link.href = url.href;
+      swapped = true;
+    }
+    if (!swapped) location.reload();
+  });
+})();
+</script>
+"#;
+
+type Clients = Arc<Mutex<Vec<TcpStream>>>;
+
+pub struct LiveReload {
+    clients: Clients,
+    // Dropping the watcher closes its channel, which ends the dispatch thread.
+    _watcher: RecommendedWatcher,
+}
+
+impl LiveReload {
+    pub fn start(root: &Path) -> Result<Self, String> {
+        let root = root
+            .canonicalize()
+            .map_err(|e| format!("Live reload could not resolve the preview folder: {}", e))?;
+        let (tx, rx) = mpsc::channel::<PathBuf>();
+
+        let mut watcher =
+            notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+                let Ok(event) = result else {
+                    return;
+                };
+                if matches!(event.kind, EventKind::Access(_)) {
+                    return;
+                }
+                for path in event.paths {
+                    let _ = tx.send(path);
+                }
+            })
+            .map_err(|e| format!("Live reload watcher failed to start: {}", e))?;
+        watcher
+            .watch(&root, RecursiveMode::Recursive)
+            .map_err(|e| format!("Live reload could not watch {}: {}", root.display(), e))?;
+
+        let clients: Clients = Arc::new(Mutex::new(Vec::new()));
+        let dispatch_clients = Arc::clone(&clients);
+        thread::spawn(move || dispatch_changes(&root, rx, &dispatch_clients));
+
+        Ok(Self {
+            clients,
+            _watcher: watcher,
+        })
+    }
+
+    /// Take over `stream` as an event-stream connection.
+    pub fn attach(&self, mut stream: TcpStream) -> io::Result<()> {
+        stream.set_write_timeout(Some(CLIENT_WRITE_TIMEOUT))?;
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-store\r\nConnection: keep-alive\r\n\r\n: connected\n\n"
+        )?;
+        stream.flush()?;
+
+        if let Ok(mut clients) = self.clients.lock() {
+            clients.push(stream);
+        }
+        Ok(())
+    }
+}
+
+fn dispatch_changes(root: &Path, rx: mpsc::Receiver<PathBuf>, clients: &Clients) {
+    loop {
+        let first = match rx.recv_timeout(KEEPALIVE) {
+            Ok(path) => path,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                broadcast(clients, ": ping\n\n");
+                continue;
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        };
+
+        let mut changed = vec![first];
+        while let Ok(path) = rx.recv_timeout(DEBOUNCE) {
+            changed.push(path);
+        }
+
+        let relevant: Vec<String> = changed
+            .iter()
+            .filter_map(|path| relative_url_path(root, path))
+            .collect();
+        if let Some(frame) = change_frame(&relevant) {
+            broadcast(clients, &frame);
+        }
+    }
+}
+
+fn broadcast(clients: &Clients, frame: &str) {
+    let Ok(mut clients) = clients.lock() else {
+        return;
+    };
+    clients.retain_mut(|stream| {
+        stream
+            .write_all(frame.as_bytes())
+            .and_then(|_| stream.flush())
+            .is_ok()
+    });
+}
+
+/// `/root/css/site.css` -> `/css/site.css`, or None for ignored paths.
+fn relative_url_path(root: &Path, path: &Path) -> Option<String> {
+    let rel = path.strip_prefix(root).ok()?;
+    let rel = rel.to_string_lossy().replace('\\', "/");
+    let name = rel.rsplit('/').next().unwrap_or(&rel);
+
+    if rel.is_empty()
+        || rel.split('/').any(|part| IGNORED_DIRS.contains(&part))
+        || name.ends_with('~')
+        || name.ends_with(".swp")
+        || name.ends_with(".tmp")
+    {
+        return None;
+    }
+    Some(format!("/{}", rel))
+}
+
+/// A `css` event when every change is a stylesheet, otherwise `reload`.
+fn change_frame(paths: &[String]) -> Option<String> {
+    if paths.is_empty() {
+        return None;
+    }
+
+    let css_only = paths
+        .iter()
+        .all(|path| path.to_ascii_lowercase().ends_with(".css"));
+    if css_only {
+        let mut unique = paths.to_vec();
+        unique.sort();
+        unique.dedup();
+        let data = serde_json::to_string(&unique).unwrap_or_else(|_| "[]".to_string());
+        Some(format!("event: css\ndata: {}\n\n", data))
+    } else {
+        Some("event: reload\ndata: {}\n\n".to_string())
+    }
+}
+
+/// Insert the reload script before the last `</body>`, or append it.
+pub fn inject_script(html: &[u8]) -> Vec<u8> {
+    let lower = html.to_ascii_lowercase();
+    let position = lower
+        .windows(b"</body>".len())
+        .rposition(|window| window == b"</body>")
+        .unwrap_or(html.len());
+
+    let mut out = Vec::with_capacity(html.len() + RELOAD_SCRIPT.len());
+    out.extend_from_slice(&html[..position]);
+    out.extend_from_slice(RELOAD_SCRIPT.as_bytes());
+    out.extend_from_slice(&html[position..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{change_frame, inject_script, relative_url_path};
+
+    #[test]
+    fn script_goes_before_the_closing_body_tag() {
+        let html = inject_script(b"<html><BODY><p>hi</p></BODY></html>");
+        let html = String::from_utf8(html).unwrap();
+        assert!(html.contains("<p>hi</p><script>"));
+        assert!(html.ends_with("</script>\n</BODY></html>"));
+
+        let fragment = String::from_utf8(inject_script(b"<p>no body</p>")).unwrap();
+        assert!(fragment.starts_with("<p>no body</p><script>"));
+    }
+
+    #[test]
+    fn stylesheet_only_changes_hot_swap_and_others_reload() {
+        let root = Path::new("/site");
+        let css = relative_url_path(root, Path::new("/site/css/main.css")).unwrap();
+        assert_eq!(css, "/css/main.css");
+        assert!(relative_url_path(root, Path::new("/site/.git/index")).is_none());
+        assert!(relative_url_path(root, Path::new("/site/.app.js.kforge-edit-1.tmp")).is_none());
+
+        assert_eq!(
+            change_frame(&[css.clone(), css.clone()]).unwrap(),
+            "event: css\ndata: [\"/css/main.css\"]\n\n"
+        );
+        assert!(change_frame(&[css, "/index.html".into()])
+            .unwrap()
+            .starts_with("event: reload"));
+        assert!(change_frame(&[]).is_none());
+    }
+}
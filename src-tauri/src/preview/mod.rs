@@ -0,0 +1,1490 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+mod build_report;
+mod live_reload;
+mod mime;
+mod network;
+mod proxy;
+mod ready;
+mod request_log;
+mod runners;
+mod static_server;
+mod supervisor;
+mod tls;
+
+use crate::log_buffer::{LogStore, PREVIEW_RUNNER};
+use crate::package_manager::{self, PackageManager};
+use crate::process_tree::{self, ProcessTree, StopOutcome, STOP_GRACE_PERIOD};
+use build_report::BuildFailure;
+use live_reload::LiveReload;
+use network::ServeOptions;
+use proxy::{ProxyRule, ProxyRuleConfig};
+use request_log::{RequestLog, RequestQuery, RequestRecord};
+use runners::{PreviewRunner, RunnerCommand};
+use supervisor::{Supervisor, SupervisorConfig};
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// Every running preview (dev server, static server or install), keyed by
+/// preview id. One project can only have one entry at a time.
+#[derive(Default)]
+pub struct PreviewState {
+    previews: Mutex<HashMap<String, PreviewEntry>>,
+    next_id: AtomicU64,
+    requests: RequestLog,
+}
+
+struct PreviewEntry {
+    project_path: String,
+    root: PathBuf,
+    kind: &'static str,
+    label: String,
+    status: String,
+    port: Option<u16>,
+    url: Option<String>,
+    /// Addresses other devices can use when the preview is exposed on the LAN.
+    lan_urls: Vec<String>,
+    /// Self-signed certificate to trust when served over HTTPS.
+    certificate_path: Option<String>,
+    /// Supervisor restarts since the last stable run.
+    restarts: u32,
+    handle: PreviewHandle,
+}
+
+enum PreviewHandle {
+    /// Reserved while the process or server is being set up.
+    Pending,
+    Child {
+        tree: Arc<ProcessTree>,
+    },
+    Static {
+        stop_tx: mpsc::Sender<()>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewInfo {
+    pub id: String,
+    pub project_path: String,
+    pub kind: String,
+    pub label: String,
+    pub status: String,
+    pub port: Option<u16>,
+    pub url: Option<String>,
+    pub lan_urls: Vec<String>,
+    pub certificate_path: Option<String>,
+    pub restarts: u32,
+    pub pid: Option<u32>,
+}
+
+impl PreviewEntry {
+    fn info(&self, id: &str) -> PreviewInfo {
+        PreviewInfo {
+            id: id.to_string(),
+            project_path: self.project_path.clone(),
+            kind: self.kind.to_string(),
+            label: self.label.clone(),
+            status: self.status.clone(),
+            port: self.port,
+            url: self.url.clone(),
+            lan_urls: self.lan_urls.clone(),
+            certificate_path: self.certificate_path.clone(),
+            restarts: self.restarts,
+            pid: match self.handle {
+                PreviewHandle::Child { ref tree } => Some(tree.pid()),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl PreviewState {
+    /// Claim a slot for `root`, refusing if that project already has a
+    /// preview or install running.
+    fn reserve(
+        &self,
+        root: &Path,
+        project_path: &str,
+        kind: &'static str,
+        label: &str,
+    ) -> Result<String, String> {
+        let mut previews = self
+            .previews
+            .lock()
+            .map_err(|_| "Preview state lock failed".to_string())?;
+
+        if let Some(existing) = previews.values().find(|entry| entry.root == root) {
+            return Err(format!(
+                "{} is already running for this project. Stop it first.",
+                existing.label
+            ));
+        }
+
+        let id = format!(
+            "preview-{}",
+            self.next_id.fetch_add(1, Ordering::Relaxed) + 1
+        );
+        previews.insert(
+            id.clone(),
+            PreviewEntry {
+                project_path: project_path.to_string(),
+                root: root.to_path_buf(),
+                kind,
+                label: label.to_string(),
+                status: "starting".to_string(),
+                port: None,
+                url: None,
+                lan_urls: Vec::new(),
+                certificate_path: None,
+                restarts: 0,
+                handle: PreviewHandle::Pending,
+            },
+        );
+        Ok(id)
+    }
+
+    fn update(&self, id: &str, apply: impl FnOnce(&mut PreviewEntry)) -> Option<PreviewInfo> {
+        let mut previews = self.previews.lock().ok()?;
+        let entry = previews.get_mut(id)?;
+        apply(entry);
+        Some(entry.info(id))
+    }
+
+    fn remove(&self, id: &str) -> Option<PreviewEntry> {
+        self.previews.lock().ok()?.remove(id)
+    }
+
+    fn list(&self) -> Vec<PreviewInfo> {
+        let Ok(previews) = self.previews.lock() else {
+            return Vec::new();
+        };
+        let mut list: Vec<PreviewInfo> =
+            previews.iter().map(|(id, entry)| entry.info(id)).collect();
+        list.sort_by_key(|info| preview_sequence(&info.id));
+        list
+    }
+
+    fn id_for_root(&self, root: &Path) -> Option<String> {
+        let previews = self.previews.lock().ok()?;
+        previews
+            .iter()
+            .find(|(_, entry)| entry.root == root)
+            .map(|(id, _)| id.clone())
+    }
+}
+
+fn preview_sequence(id: &str) -> u64 {
+    id.rsplit('-')
+        .next()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(0)
+}
+
+enum PreviewKind {
+    PackageProject,
+    StaticSite,
+    /// A non-Node project, with why it was recognised.
+    Runner(&'static dyn PreviewRunner, String),
+}
+
+/// Log lines go out on the preview's own channel and on the shared one (with
+/// `previewId`) that older listeners subscribe to.
+fn emit_log(app: &AppHandle, preview_id: &str, kind: &str, line: &str) {
+    app.state::<LogStore>().push(preview_id, kind, line);
+    let payload = serde_json::json!({
+      "previewId": preview_id,
+      "kind": kind,
+      "line": line
+    });
+    let _ = app.emit(&format!("kforge://preview/{}/log", preview_id), &payload);
+    let _ = app.emit("kforge://preview/log", payload);
+}
+
+/// For messages that belong to no preview entry (e.g. a refused start).
+fn emit_shared_log(app: &AppHandle, kind: &str, line: &str) {
+    app.state::<LogStore>().push(PREVIEW_RUNNER, kind, line);
+    let _ = app.emit(
+        "kforge://preview/log",
+        serde_json::json!({
+          "previewId": null,
+          "kind": kind,
+          "line": line
+        }),
+    );
+}
+
+fn emit_status(app: &AppHandle, preview_id: &str, status: &str) {
+    let info = app.state::<PreviewState>().update(preview_id, |entry| {
+        entry.status = status.to_string();
+    });
+    let payload = serde_json::json!({
+      "previewId": preview_id,
+      "status": status,
+      "preview": info
+    });
+    let _ = app.emit(&format!("kforge://preview/{}/status", preview_id), &payload);
+    let _ = app.emit("kforge://preview/status", payload);
+}
+
+fn emit_request(app: &AppHandle, preview_id: &str, record: RequestRecord) {
+    let record = app
+        .state::<PreviewState>()
+        .requests
+        .push(preview_id, record);
+    let payload = serde_json::json!({
+      "previewId": preview_id,
+      "request": record
+    });
+    let _ = app.emit(
+        &format!("kforge://preview/{}/request", preview_id),
+        &payload,
+    );
+    let _ = app.emit("kforge://preview/request", payload);
+}
+
+fn validate_project_path(path: &str) -> Result<PathBuf, String> {
+    let trimmed = path.trim();
+    if trimmed.is_empty() {
+        return Err("Project path is empty".to_string());
+    }
+
+    let pb = PathBuf::from(trimmed);
+    if !pb.exists() {
+        return Err(format!("Project path does not exist: {}", trimmed));
+    }
+    if !pb.is_dir() {
+        return Err(format!("Project path is not a folder: {}", trimmed));
+    }
+
+    Ok(pb)
+}
+
+/// Canonical folder used to tell projects apart, whatever spelling the
+/// caller used for the path.
+fn project_root(path: &str) -> Result<PathBuf, String> {
+    let root = validate_project_path(path)?;
+    Ok(root.canonicalize().unwrap_or(root))
+}
+
+fn detect_preview_kind(project_path: &Path) -> Result<PreviewKind, String> {
+    if let Some((runner, reason)) = runners::detect(project_path) {
+        return Ok(PreviewKind::Runner(runner, reason));
+    }
+
+    let package_json = project_path.join("package.json");
+    if package_json.is_file() {
+        return Ok(PreviewKind::PackageProject);
+    }
+
+    let index_html = project_path.join("index.html");
+    if index_html.is_file() {
+        return Ok(PreviewKind::StaticSite);
+    }
+
+    Err(
+        "No supported preview target found. Expected package.json, index.html, or a Python, Deno, Rust web or PHP project."
+            .to_string(),
+    )
+}
+
+/// Package-manager tasks a preview entry can run.
+#[derive(Clone, PartialEq, Eq)]
+enum PackageTask {
+    Install,
+    Dev,
+    /// Production build; a successful one is then served statically.
+    Build {
+        serve: ServeOptions,
+    },
+}
+
+impl PackageTask {
+    fn kind(&self) -> &'static str {
+        match self {
+            PackageTask::Install => "install",
+            PackageTask::Dev => "package",
+            PackageTask::Build { .. } => "production",
+        }
+    }
+
+    fn running_status(&self) -> &'static str {
+        match self {
+            PackageTask::Install => "installing",
+            PackageTask::Dev => "running",
+            PackageTask::Build { .. } => "building",
+        }
+    }
+
+    fn args(&self, manager: PackageManager) -> Vec<String> {
+        match self {
+            PackageTask::Install => manager.install_args(),
+            PackageTask::Dev => manager.run_args("dev"),
+            PackageTask::Build { .. } => manager.run_args("build"),
+        }
+    }
+}
+
+fn completion_message(task: &PackageTask, exit_code: Option<i32>, stop_requested: bool) -> String {
+    if is_expected_stop(task, exit_code, stop_requested) {
+        return "✔ Preview stopped".to_string();
+    }
+
+    match (exit_code, task) {
+        (Some(0), PackageTask::Install) => "✔ Dependencies installed successfully".to_string(),
+        (Some(0), PackageTask::Dev) => "✔ Preview stopped".to_string(),
+        (Some(code), PackageTask::Install) => format!("❌ Install failed (exit code {})", code),
+        (Some(code), PackageTask::Dev) => format!("❌ Preview failed (exit code {})", code),
+        (None, PackageTask::Install) => "Install process ended".to_string(),
+        (None, PackageTask::Dev) => "❌ Preview process was killed".to_string(),
+        (Some(0), PackageTask::Build { .. }) => "✔ Build finished".to_string(),
+        (Some(code), PackageTask::Build { .. }) => format!("❌ Build failed (exit code {})", code),
+        (None, PackageTask::Build { .. }) => "Build process ended".to_string(),
+    }
+}
+
+/// Whether a dev server ended the way a stop ends: a clean exit, or the
+/// codes a terminated process reports when KForge asked it to stop.
+fn is_expected_stop(task: &PackageTask, exit_code: Option<i32>, stop_requested: bool) -> bool {
+    *task == PackageTask::Dev
+        && (exit_code == Some(0)
+            || stop_requested && matches!(exit_code, Some(1) | Some(130) | Some(143) | None))
+}
+
+/// Finish a preview: drop its entry, then tell listeners it is idle.
+fn finish_preview(app: &AppHandle, preview_id: &str) {
+    app.state::<PreviewState>().remove(preview_id);
+    emit_status(app, preview_id, "idle");
+}
+
+/// Record where a preview is serving and announce it on
+/// `kforge://preview/ready`. Fires once, except that a browsable http(s)
+/// URL replaces an earlier Expo `exp://` one.
+fn mark_ready(app: &AppHandle, preview_id: &str, url: &str, source: &str) -> bool {
+    let port = ready::port_from_url(url);
+    let mut changed = false;
+    app.state::<PreviewState>().update(preview_id, |entry| {
+        let upgrade = entry
+            .url
+            .as_deref()
+            .is_some_and(|current| current.starts_with("exp://") && url.starts_with("http"));
+        if entry.url.is_none() || upgrade {
+            entry.url = Some(url.to_string());
+            entry.port = port;
+            changed = true;
+        }
+    });
+    if !changed {
+        return false;
+    }
+
+    let payload = serde_json::json!({
+      "previewId": preview_id,
+      "url": url,
+      "port": port,
+      "source": source
+    });
+    let _ = app.emit(&format!("kforge://preview/{}/ready", preview_id), &payload);
+    let _ = app.emit("kforge://preview/ready", payload);
+    true
+}
+
+/// Lines of build output kept for failure reports.
+const BUILD_OUTPUT_LINES: usize = 2000;
+
+type CapturedOutput = Arc<Mutex<VecDeque<String>>>;
+
+fn stream_output(
+    app: AppHandle,
+    preview_id: String,
+    kind: &'static str,
+    reader: impl std::io::Read + Send + 'static,
+    url_markers: Option<&'static [&'static str]>,
+    capture: Option<CapturedOutput>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let reader = BufReader::new(reader);
+        for line in reader.lines().map_while(Result::ok) {
+            emit_log(&app, &preview_id, kind, &line);
+            if let Some(captured) = &capture {
+                if let Ok(mut captured) = captured.lock() {
+                    if captured.len() == BUILD_OUTPUT_LINES {
+                        captured.pop_front();
+                    }
+                    captured.push_back(line.clone());
+                }
+            }
+            let Some(markers) = url_markers else {
+                continue;
+            };
+            if let Some(url) = ready::detect_dev_server_url(&line, markers) {
+                if mark_ready(&app, &preview_id, &url, "output") {
+                    emit_log(
+                        &app,
+                        &preview_id,
+                        "status",
+                        &format!("Preview ready: {}", url),
+                    );
+                }
+            }
+        }
+    })
+}
+
+/// Fallback for dev servers whose output we do not recognise: wait for the
+/// expected port to open, unless the output already gave us a URL.
+fn poll_dev_server_port(app: AppHandle, preview_id: String, port: u16) {
+    thread::spawn(move || {
+        let waiting = || {
+            app.state::<PreviewState>()
+                .update(&preview_id, |_| {})
+                .is_some_and(|info| info.url.is_none())
+        };
+        if ready::wait_for_port(port, ready::PORT_POLL_TIMEOUT, waiting) && waiting() {
+            let url = format!("http://localhost:{}/", port);
+            if mark_ready(&app, &preview_id, &url, "port") {
+                emit_log(
+                    &app,
+                    &preview_id,
+                    "status",
+                    &format!("Preview ready: {}", url),
+                );
+            }
+        }
+    });
+}
+
+/// What a preview entry runs: usually one command, or several in turn for
+/// an install that has to set something up first.
+struct ProcessPlan {
+    kind: &'static str,
+    steps: Vec<RunnerCommand>,
+    /// How the commands were chosen, logged once the entry exists.
+    intro: String,
+    /// Polled when the output never names a URL.
+    expected_port: Option<u16>,
+    ready_markers: &'static [&'static str],
+}
+
+fn package_plan(root: &Path, task: &PackageTask) -> Result<ProcessPlan, String> {
+    let detected = package_manager::for_project(root);
+    let manager = detected.manager;
+    if !manager.is_installed() {
+        return Err(manager.not_installed_message(&detected.reason));
+    }
+
+    Ok(ProcessPlan {
+        kind: task.kind(),
+        steps: vec![RunnerCommand {
+            program: manager.program().to_string(),
+            args: task.args(manager),
+            env: Vec::new(),
+        }],
+        intro: format!("Using {} ({})", manager.name(), detected.reason),
+        expected_port: if *task == PackageTask::Dev {
+            ready::expected_dev_port(root)
+        } else {
+            None
+        },
+        ready_markers: &[],
+    })
+}
+
+/// None when the project has nothing to install.
+fn runner_install_plan(
+    runner: &'static dyn PreviewRunner,
+    reason: &str,
+    root: &Path,
+) -> Result<Option<ProcessPlan>, String> {
+    let steps = runner.install(root);
+    let Some(first) = steps.first() else {
+        return Ok(None);
+    };
+    first.check_available()?;
+
+    Ok(Some(ProcessPlan {
+        kind: PackageTask::Install.kind(),
+        steps,
+        intro: format!("{} project ({})", runner.name(), reason),
+        expected_port: None,
+        ready_markers: &[],
+    }))
+}
+
+fn runner_dev_plan(
+    runner: &'static dyn PreviewRunner,
+    reason: &str,
+    root: &Path,
+) -> Result<ProcessPlan, String> {
+    // Claim a free port near the runner's usual one, then hand it over.
+    let port = network::bind_listener(&ServeOptions {
+        preferred_port: Some(runner.default_port(root)),
+        ..ServeOptions::default()
+    })
+    .and_then(|listener| {
+        listener
+            .local_addr()
+            .map(|address| address.port())
+            .map_err(|e| format!("Failed to pick a dev server port: {}", e))
+    })?;
+    let server = runner.dev_server(root, port)?;
+    server.command.check_available()?;
+
+    Ok(ProcessPlan {
+        kind: runner.id(),
+        steps: vec![server.command],
+        intro: format!("{} project ({}): {}", runner.name(), reason, server.reason),
+        expected_port: Some(port),
+        ready_markers: runner.ready_markers(),
+    })
+}
+
+/// Everything needed to start, and with a supervisor restart, the process
+/// behind a preview entry.
+#[derive(Clone)]
+struct Launch {
+    app: AppHandle,
+    preview_id: String,
+    project_path: String,
+    root: PathBuf,
+    command: RunnerCommand,
+    /// Install steps still to run after `command` succeeds.
+    next_steps: Vec<RunnerCommand>,
+    task: PackageTask,
+    label: String,
+    expected_port: Option<u16>,
+    ready_markers: &'static [&'static str],
+    supervisor: Option<Arc<Supervisor>>,
+}
+
+fn spawn_preview_process(
+    app: AppHandle,
+    state: tauri::State<PreviewState>,
+    project_path: String,
+    task: PackageTask,
+    plan: ProcessPlan,
+    supervise: Option<SupervisorConfig>,
+) -> Result<PreviewInfo, String> {
+    let root = project_root(&project_path)?;
+    let mut steps = plan.steps.into_iter();
+    let command = steps
+        .next()
+        .ok_or_else(|| "Nothing to run for this preview".to_string())?;
+
+    let label = command.label();
+    let preview_id = state.reserve(&root, &project_path, plan.kind, &label)?;
+    emit_log(&app, &preview_id, "status", &plan.intro);
+
+    // Only long-running dev servers are worth supervising.
+    let supervisor = supervise
+        .filter(|_| task == PackageTask::Dev)
+        .map(|config| Arc::new(Supervisor::new(&config)));
+    let launch = Launch {
+        app: app.clone(),
+        preview_id: preview_id.clone(),
+        project_path,
+        root,
+        command,
+        next_steps: steps.collect(),
+        task,
+        label: label.clone(),
+        expected_port: plan.expected_port,
+        ready_markers: plan.ready_markers,
+        supervisor,
+    };
+
+    if let Err(err) = start_process(&launch) {
+        state.remove(&preview_id);
+        return Err(err);
+    }
+    if let Some(supervisor) = &launch.supervisor {
+        emit_log(
+            &app,
+            &preview_id,
+            "status",
+            &format!(
+                "Supervisor on: restarts after a crash, up to {} times.",
+                supervisor.max_restarts()
+            ),
+        );
+        supervisor::watch_health(app.clone(), preview_id.clone(), Arc::clone(supervisor));
+    }
+
+    state
+        .update(&preview_id, |_| {})
+        .ok_or_else(|| format!("{} exited immediately", label))
+}
+
+/// Spawn the process for `launch` plus the threads that stream its output
+/// and wait for it to exit.
+fn start_process(launch: &Launch) -> Result<(), String> {
+    let app = &launch.app;
+    let preview_id = &launch.preview_id;
+    let project_path = &launch.project_path;
+    let task = &launch.task;
+    let label = &launch.label;
+    let program = &launch.command;
+
+    #[cfg(target_os = "windows")]
+    let spawned = {
+        let mut command = Command::new("cmd");
+        command
+            .arg("/C")
+            .arg(&program.program)
+            .args(&program.args)
+            .envs(program.env.iter().cloned())
+            .current_dir(project_path)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .creation_flags(CREATE_NO_WINDOW);
+
+        command.spawn()
+    };
+
+    #[cfg(not(target_os = "windows"))]
+    let spawned = {
+        let mut command = Command::new(&program.program);
+        command
+            .current_dir(project_path)
+            .args(&program.args)
+            .envs(program.env.iter().cloned())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        process_tree::configure(&mut command);
+
+        command.spawn()
+    };
+
+    let mut child = spawned.map_err(|e| format!("Failed to spawn {}: {}", label, e))?;
+
+    let tree = Arc::new(ProcessTree::attach(&child));
+    let pid = tree.pid();
+
+    // Both pipes were requested above, so these are always present.
+    let (Some(stdout), Some(stderr)) = (child.stdout.take(), child.stderr.take()) else {
+        let _ = child.kill();
+        return Err("Failed to capture process output".to_string());
+    };
+
+    let attached = app.state::<PreviewState>().update(preview_id, |entry| {
+        entry.label = label.clone();
+        entry.handle = PreviewHandle::Child {
+            tree: Arc::clone(&tree),
+        };
+    });
+    if attached.is_none() {
+        // Stopped while a restart was pending; the waiter below reports it.
+        let tree_stop = Arc::clone(&tree);
+        thread::spawn(move || tree_stop.terminate(STOP_GRACE_PERIOD));
+    }
+
+    emit_status(app, preview_id, task.running_status());
+    emit_log(
+        app,
+        preview_id,
+        "status",
+        &format!("Running: {} (cwd: {}) [pid={}]", label, project_path, pid),
+    );
+
+    let is_dev_server = *task == PackageTask::Dev;
+    let capture: Option<CapturedOutput> =
+        matches!(task, PackageTask::Build { .. }).then(|| Arc::new(Mutex::new(VecDeque::new())));
+    // A supervisor keeps the current run's stderr for crash reports.
+    let stderr_capture = match &launch.supervisor {
+        Some(supervisor) => {
+            supervisor.clear_stderr();
+            Some(supervisor.stderr())
+        }
+        None => capture.clone(),
+    };
+    let readers = [
+        stream_output(
+            app.clone(),
+            preview_id.clone(),
+            "stdout",
+            stdout,
+            is_dev_server.then_some(launch.ready_markers),
+            capture.clone(),
+        ),
+        stream_output(
+            app.clone(),
+            preview_id.clone(),
+            "stderr",
+            stderr,
+            is_dev_server.then_some(launch.ready_markers),
+            stderr_capture,
+        ),
+    ];
+    if let Some(port) = launch.expected_port.filter(|_| is_dev_server) {
+        poll_dev_server_port(app.clone(), preview_id.clone(), port);
+    }
+
+    let launch_wait = launch.clone();
+    let tree_wait = Arc::clone(&tree);
+    let started = Instant::now();
+    thread::spawn(move || {
+        let status = child.wait();
+        let app = &launch_wait.app;
+        let id = &launch_wait.preview_id;
+        let task = &launch_wait.task;
+
+        // A package manager can exit while the server it forked is still
+        // holding the port.
+        if tree_wait.is_alive() {
+            tree_wait.terminate(STOP_GRACE_PERIOD);
+        }
+
+        // Build reports need every line, so wait for the pipes to drain.
+        if let Some(capture) = capture {
+            for reader in readers {
+                let _ = reader.join();
+            }
+            let output: Vec<String> = capture
+                .lock()
+                .map(|captured| captured.iter().cloned().collect())
+                .unwrap_or_default();
+            let exit_code = status.as_ref().ok().and_then(|status| status.code());
+            emit_log(
+                app,
+                id,
+                "status",
+                &completion_message(task, exit_code, false),
+            );
+            if let PackageTask::Build { serve } = task {
+                finish_build(app, id, &launch_wait.root, serve, exit_code, &output);
+            }
+            return;
+        }
+
+        let exit_code = match status {
+            Ok(exit_status) => exit_status.code(),
+            Err(err) => {
+                emit_log(
+                    app,
+                    id,
+                    "stderr",
+                    &format!("{} wait failed: {}", launch_wait.label, err),
+                );
+                finish_preview(app, id);
+                return;
+            }
+        };
+
+        // stop_preview drops the entry before signalling the process.
+        let stop_requested = app.state::<PreviewState>().update(id, |_| {}).is_none();
+
+        if exit_code == Some(0) && !stop_requested {
+            if let Some((next, rest)) = launch_wait.next_steps.split_first() {
+                emit_log(
+                    app,
+                    id,
+                    "status",
+                    &format!("✔ {} finished", launch_wait.label),
+                );
+                let launch_next = Launch {
+                    command: next.clone(),
+                    next_steps: rest.to_vec(),
+                    label: next.label(),
+                    ..launch_wait.clone()
+                };
+                if let Err(err) = start_process(&launch_next) {
+                    emit_log(app, id, "stderr", &err);
+                    finish_preview(app, id);
+                }
+                return;
+            }
+        }
+
+        emit_log(
+            app,
+            id,
+            "status",
+            &completion_message(task, exit_code, stop_requested),
+        );
+
+        if let Some(supervisor) = &launch_wait.supervisor {
+            let unhealthy = supervisor.take_unhealthy();
+            if !stop_requested && (unhealthy || !is_expected_stop(task, exit_code, false)) {
+                supervisor::restart_after_exit(
+                    launch_wait.clone(),
+                    exit_code,
+                    started.elapsed(),
+                    unhealthy,
+                );
+                return;
+            }
+        }
+
+        finish_preview(app, id);
+    });
+
+    Ok(())
+}
+
+fn start_static_preview(
+    app: AppHandle,
+    state: tauri::State<PreviewState>,
+    project_path: String,
+    live_reload: bool,
+    serve: ServeOptions,
+) -> Result<PreviewInfo, String> {
+    let root = project_root(&project_path)?;
+
+    let index_html = root.join("index.html");
+    if !index_html.is_file() {
+        return Err(format!(
+            "Static preview requires index.html in: {}",
+            project_path
+        ));
+    }
+
+    let preview_id = state.reserve(&root, &project_path, "static", "static preview")?;
+    if let Err(err) = serve_static(&app, &preview_id, root, live_reload, serve) {
+        state.remove(&preview_id);
+        return Err(err);
+    }
+
+    state
+        .update(&preview_id, |_| {})
+        .ok_or_else(|| "Static preview stopped before it started".to_string())
+}
+
+/// Serve `site_root` for an already reserved preview entry. Does nothing if
+/// the entry was stopped in the meantime.
+fn serve_static(
+    app: &AppHandle,
+    preview_id: &str,
+    root: PathBuf,
+    live_reload: bool,
+    serve: ServeOptions,
+) -> Result<(), String> {
+    let preview_id = preview_id.to_string();
+
+    if serve.expose_on_lan {
+        network::check_lan_exposure(&root)?;
+    }
+
+    let bound = network::bind_listener(&serve).and_then(|listener| {
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("Failed to configure static preview server: {}", e))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| format!("Failed to read static preview address: {}", e))?
+            .port();
+        Ok((listener, port))
+    });
+    let (listener, port) = bound?;
+
+    let certificate = if serve.https {
+        let lan_hosts: Vec<String> = if serve.expose_on_lan {
+            network::lan_addresses()
+                .iter()
+                .map(|ip| ip.to_string())
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Some(tls::load_or_create(
+            &tls::certificate_dir(app)?,
+            &lan_hosts,
+        )?)
+    } else {
+        None
+    };
+    let scheme = if certificate.is_some() {
+        "https"
+    } else {
+        "http"
+    };
+
+    let url = format!("{}://127.0.0.1:{}/", scheme, port);
+    let lan_urls = if serve.expose_on_lan {
+        network::lan_urls(scheme, port)
+    } else {
+        Vec::new()
+    };
+
+    // A watcher failure only costs the auto-refresh; the preview still serves.
+    let live_reload = if live_reload {
+        match LiveReload::start(&root) {
+            Ok(live_reload) => Some(live_reload),
+            Err(err) => {
+                emit_log(app, &preview_id, "stderr", &err);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let (stop_tx, stop_rx) = mpsc::channel::<()>();
+
+    let attached = app.state::<PreviewState>().update(&preview_id, |entry| {
+        entry.handle = PreviewHandle::Static { stop_tx };
+        entry.lan_urls = lan_urls.clone();
+        entry.certificate_path = certificate
+            .as_ref()
+            .map(|certificate| certificate.info.cert_path.clone());
+    });
+    if attached.is_none() {
+        return Ok(());
+    }
+
+    emit_status(app, &preview_id, "running");
+    emit_log(
+        app,
+        &preview_id,
+        "status",
+        &format!("Serving static site from {}", root.display()),
+    );
+    if live_reload.is_some() {
+        emit_log(
+            app,
+            &preview_id,
+            "status",
+            "Live reload on: pages refresh when files change.",
+        );
+    }
+    if let Some(preferred) = serve.preferred_port.filter(|p| *p != 0 && *p != port) {
+        emit_log(
+            app,
+            &preview_id,
+            "status",
+            &format!("Port {} is in use; using {} instead.", preferred, port),
+        );
+    }
+    mark_ready(app, &preview_id, &url, "server");
+    emit_log(
+        app,
+        &preview_id,
+        "status",
+        &format!("Preview ready: {}", url),
+    );
+    if serve.expose_on_lan {
+        let message = if lan_urls.is_empty() {
+            "Shared on the network, but no LAN address was found.".to_string()
+        } else {
+            format!("On your network: {}", lan_urls.join(", "))
+        };
+        emit_log(app, &preview_id, "status", &message);
+    }
+    for rule in &serve.proxy {
+        emit_log(
+            app,
+            &preview_id,
+            "status",
+            &format!("Proxying {}", rule.describe()),
+        );
+    }
+    if let Some(certificate) = &certificate {
+        let message = if certificate.created {
+            format!(
+                "Created a new self-signed HTTPS certificate: {}. Trust it in your system or browser to avoid the security warning.",
+                certificate.info.cert_path
+            )
+        } else {
+            format!(
+                "HTTPS with the self-signed certificate at {} (trust it once if the browser warns).",
+                certificate.info.cert_path
+            )
+        };
+        emit_log(app, &preview_id, "status", &message);
+    }
+
+    let app_server = app.clone();
+    let id_server = preview_id.clone();
+    let root = Arc::new(root);
+    let live_reload = live_reload.map(Arc::new);
+    let proxy_rules: Arc<[ProxyRule]> = serve.proxy.into();
+    let tls_config = certificate.map(|certificate| certificate.config);
+    let shared_on_lan = serve.expose_on_lan;
+
+    thread::spawn(move || {
+        loop {
+            if stop_rx.try_recv().is_ok() {
+                break;
+            }
+
+            match listener.accept() {
+                Ok((stream, address)) => {
+                    if let Err(err) = stream.set_nonblocking(false) {
+                        emit_log(
+                            &app_server,
+                            &id_server,
+                            "stderr",
+                            &format!("Static preview request setup failed: {}", err),
+                        );
+                        continue;
+                    }
+
+                    // One thread per connection so a long media stream does
+                    // not hold up page loads.
+                    let root = Arc::clone(&root);
+                    let live_reload = live_reload.clone();
+                    let proxy_rules = Arc::clone(&proxy_rules);
+                    let tls_config = tls_config.clone();
+                    // The original peer: with HTTPS the handler only sees
+                    // the relay's loopback socket.
+                    let client = static_server::Client {
+                        address: Some(address.ip()),
+                        https: tls_config.is_some(),
+                    };
+                    let app_request = app_server.clone();
+                    let id_request = id_server.clone();
+                    thread::spawn(move || {
+                        let stream = match tls_config {
+                            Some(config) => tls::terminate(stream, config),
+                            None => Ok(stream),
+                        };
+                        let handled = stream.and_then(|stream| {
+                            static_server::handle_connection(
+                                stream,
+                                client,
+                                &root,
+                                live_reload.as_deref(),
+                                &proxy_rules,
+                                shared_on_lan,
+                                |record| emit_request(&app_request, &id_request, record),
+                            )
+                        });
+                        if let Err(err) = handled {
+                            // Browsers routinely abort media and prefetch requests.
+                            if !matches!(
+                                err.kind(),
+                                std::io::ErrorKind::WouldBlock
+                                    | std::io::ErrorKind::BrokenPipe
+                                    | std::io::ErrorKind::ConnectionReset
+                                    | std::io::ErrorKind::ConnectionAborted
+                            ) {
+                                emit_log(
+                                    &app_request,
+                                    &id_request,
+                                    "stderr",
+                                    &format!("Static preview request failed: {}", err),
+                                );
+                            }
+                        }
+                    });
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(100));
+                }
+                Err(err) => {
+                    emit_log(
+                        &app_server,
+                        &id_server,
+                        "stderr",
+                        &format!("Static preview server failed: {}", err),
+                    );
+                    break;
+                }
+            }
+        }
+
+        emit_log(&app_server, &id_server, "status", "✔ Preview stopped");
+        finish_preview(&app_server, &id_server);
+    });
+
+    Ok(())
+}
+
+fn report_build_failure(app: &AppHandle, preview_id: &str, failure: &BuildFailure) {
+    emit_log(
+        app,
+        preview_id,
+        "stderr",
+        &format!("❌ {}", failure.summary),
+    );
+    for diagnostic in &failure.diagnostics {
+        if let Some(file) = &diagnostic.file {
+            let position = match (diagnostic.line, diagnostic.column) {
+                (Some(line), Some(column)) => format!(":{}:{}", line, column),
+                (Some(line), None) => format!(":{}", line),
+                _ => String::new(),
+            };
+            emit_log(
+                app,
+                preview_id,
+                "stderr",
+                &format!("  {}{} {}", file, position, diagnostic.message),
+            );
+        }
+    }
+
+    let payload = serde_json::json!({
+      "previewId": preview_id,
+      "failure": failure
+    });
+    let _ = app.emit(
+        &format!("kforge://preview/{}/build-failed", preview_id),
+        &payload,
+    );
+    let _ = app.emit("kforge://preview/build-failed", payload);
+}
+
+/// After a production build: serve its output, or report why not.
+fn finish_build(
+    app: &AppHandle,
+    preview_id: &str,
+    project_root: &Path,
+    serve: &ServeOptions,
+    exit_code: Option<i32>,
+    output: &[String],
+) {
+    // Stopped while building; stop_preview already cleaned up.
+    if app
+        .state::<PreviewState>()
+        .update(preview_id, |_| {})
+        .is_none()
+    {
+        return;
+    }
+
+    let served = if exit_code == Some(0) {
+        build_report::detect_build_output(project_root).and_then(|output_dir| {
+            emit_log(
+                app,
+                preview_id,
+                "status",
+                &format!("Production preview of {}", output_dir.display()),
+            );
+            serve_static(app, preview_id, output_dir, false, serve.clone())
+        })
+    } else {
+        report_build_failure(
+            app,
+            preview_id,
+            &build_report::build_failure(exit_code, output),
+        );
+        finish_preview(app, preview_id);
+        return;
+    };
+
+    if let Err(err) = served {
+        let mut failure = build_report::build_failure(exit_code, output);
+        failure.summary = err;
+        failure.diagnostics.clear();
+        report_build_failure(app, preview_id, &failure);
+        finish_preview(app, preview_id);
+    }
+}
+
+/// Stop one preview. Its entry is dropped straight away so the project can
+/// be started again while the old process winds down.
+fn stop_preview(app: &AppHandle, state: &PreviewState, preview_id: &str) {
+    let Some(entry) = state.remove(preview_id) else {
+        return;
+    };
+
+    emit_log(app, preview_id, "status", "Stopping preview…");
+    match entry.handle {
+        PreviewHandle::Child { tree } => {
+            emit_log(
+                app,
+                preview_id,
+                "status",
+                &format!("Stopping {}", entry.label),
+            );
+
+            let app_stop = app.clone();
+            let id_stop = preview_id.to_string();
+            thread::spawn(move || {
+                if tree.terminate(STOP_GRACE_PERIOD) == StopOutcome::Killed {
+                    emit_log(
+                        &app_stop,
+                        &id_stop,
+                        "status",
+                        &format!(
+                            "{} did not exit within {}s and was force killed.",
+                            entry.label,
+                            STOP_GRACE_PERIOD.as_secs()
+                        ),
+                    );
+                }
+            });
+        }
+        PreviewHandle::Static { stop_tx } => {
+            let _ = stop_tx.send(());
+        }
+        PreviewHandle::Pending => {}
+    }
+
+    emit_status(app, preview_id, "idle");
+}
+
+#[tauri::command]
+pub fn preview_detect_kind(project_path: String) -> Result<String, String> {
+    let root = validate_project_path(&project_path)?;
+    match detect_preview_kind(&root)? {
+        PreviewKind::PackageProject => Ok("package".to_string()),
+        PreviewKind::StaticSite => Ok("static".to_string()),
+        PreviewKind::Runner(runner, _) => Ok(runner.id().to_string()),
+    }
+}
+
+/// Status of the given project's preview, or "running" when any preview is
+/// active if no project is given.
+#[tauri::command]
+pub fn preview_get_status(
+    state: tauri::State<PreviewState>,
+    project_path: Option<String>,
+) -> Result<String, String> {
+    let previews = state.list();
+
+    let Some(project_path) = project_path else {
+        return Ok(if previews.is_empty() {
+            "idle"
+        } else {
+            "running"
+        }
+        .to_string());
+    };
+
+    let root = project_root(&project_path)?;
+    let status = state
+        .id_for_root(&root)
+        .and_then(|id| previews.into_iter().find(|info| info.id == id))
+        .map(|info| info.status)
+        .unwrap_or_else(|| "idle".to_string());
+    Ok(status)
+}
+
+#[tauri::command]
+pub fn preview_list(state: tauri::State<PreviewState>) -> Result<Vec<PreviewInfo>, String> {
+    Ok(state.list())
+}
+
+#[tauri::command]
+pub fn preview_install(
+    app: AppHandle,
+    state: tauri::State<PreviewState>,
+    project_path: String,
+) -> Result<Option<PreviewInfo>, String> {
+    let root = project_root(&project_path)?;
+    let plan = match detect_preview_kind(&root)? {
+        PreviewKind::PackageProject => package_plan(&root, &PackageTask::Install).map(Some),
+        PreviewKind::Runner(runner, reason) => runner_install_plan(runner, &reason, &root),
+        PreviewKind::StaticSite => {
+            emit_shared_log(&app, "status", "Install not needed for static preview.");
+            return Ok(None);
+        }
+    }
+    .inspect_err(|message| emit_shared_log(&app, "stderr", message))?;
+
+    match plan {
+        Some(plan) => {
+            spawn_preview_process(app, state, project_path, PackageTask::Install, plan, None)
+                .map(Some)
+        }
+        None => {
+            emit_shared_log(&app, "status", "Nothing to install for this project.");
+            Ok(None)
+        }
+    }
+}
+
+fn has_build_script(root: &Path) -> bool {
+    std::fs::read_to_string(root.join("package.json"))
+        .ok()
+        .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+        .is_some_and(|package| package.pointer("/scripts/build").is_some())
+}
+
+/// How a static or production preview is served, as sent by the frontend.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServeConfig {
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub expose_on_lan: bool,
+    /// Paths forwarded to a local backend, e.g. `/api/*`.
+    #[serde(default)]
+    pub proxy: Vec<ProxyRuleConfig>,
+    /// Serve over HTTPS with KForge's self-signed certificate.
+    #[serde(default)]
+    pub https: bool,
+    /// Reload the page on file changes (default on). Only static dev
+    /// previews use it; dev servers bring their own hot reload.
+    pub live_reload: Option<bool>,
+}
+
+impl ServeConfig {
+    fn into_options(self) -> Result<ServeOptions, String> {
+        Ok(ServeOptions {
+            preferred_port: self.port,
+            expose_on_lan: self.expose_on_lan,
+            proxy: self
+                .proxy
+                .iter()
+                .map(ProxyRule::from_config)
+                .collect::<Result<_, _>>()?,
+            https: self.https,
+        })
+    }
+}
+
+/// `mode` is "dev" (default) or "production", which builds the project and
+/// serves the output folder. `serve` applies whenever KForge's own static
+/// server is used. `supervise` restarts a dev server that crashes or stops
+/// answering.
+#[tauri::command]
+pub fn preview_start(
+    app: AppHandle,
+    state: tauri::State<PreviewState>,
+    project_path: String,
+    mode: Option<String>,
+    serve: Option<ServeConfig>,
+    supervise: Option<SupervisorConfig>,
+) -> Result<PreviewInfo, String> {
+    let production = match mode.as_deref().unwrap_or("dev") {
+        "dev" => false,
+        "production" => true,
+        other => return Err(format!("Unknown preview mode: {}", other)),
+    };
+    let serve = serve.unwrap_or_default();
+    let live_reload = !production && serve.live_reload.unwrap_or(true);
+    let serve = serve.into_options()?;
+
+    let root = project_root(&project_path)?;
+    match detect_preview_kind(&root)? {
+        PreviewKind::PackageProject if production => {
+            if !has_build_script(&root) {
+                return Err("Production preview needs a \"build\" script in package.json.".into());
+            }
+            let task = PackageTask::Build { serve };
+            let plan = package_plan(&root, &task)
+                .inspect_err(|message| emit_shared_log(&app, "stderr", message))?;
+            spawn_preview_process(app, state, project_path, task, plan, None)
+        }
+        PreviewKind::PackageProject => {
+            let plan = package_plan(&root, &PackageTask::Dev)
+                .inspect_err(|message| emit_shared_log(&app, "stderr", message))?;
+            spawn_preview_process(app, state, project_path, PackageTask::Dev, plan, supervise)
+        }
+        PreviewKind::Runner(runner, _) if production => Err(format!(
+            "Production preview is not available for {} projects. Start the dev preview instead.",
+            runner.name()
+        )),
+        PreviewKind::Runner(runner, reason) => {
+            let plan = runner_dev_plan(runner, &reason, &root)
+                .inspect_err(|message| emit_shared_log(&app, "stderr", message))?;
+            spawn_preview_process(app, state, project_path, PackageTask::Dev, plan, supervise)
+        }
+        // A plain static site is its own production build.
+        PreviewKind::StaticSite => {
+            start_static_preview(app, state, project_path, live_reload, serve)
+        }
+    }
+}
+
+/// The self-signed certificate HTTPS previews use, created on first call,
+/// so the UI can offer it for trusting before a preview is started.
+#[tauri::command]
+pub fn preview_https_certificate(app: AppHandle) -> Result<tls::CertificateInfo, String> {
+    tls::load_or_create(&tls::certificate_dir(&app)?, &[]).map(|certificate| certificate.info)
+}
+
+/// Requests a static preview has served, for the network inspector. Kept
+/// after the preview stops so a crashed session can still be looked at.
+#[tauri::command]
+pub fn preview_requests(
+    state: tauri::State<PreviewState>,
+    preview_id: String,
+    query: Option<RequestQuery>,
+) -> Result<Vec<RequestRecord>, String> {
+    Ok(state
+        .requests
+        .query(&preview_id, &query.unwrap_or_default()))
+}
+
+/// Stop one preview by id or project path, or every preview when neither is
+/// given.
+#[tauri::command]
+pub fn preview_stop(
+    app: AppHandle,
+    state: tauri::State<PreviewState>,
+    preview_id: Option<String>,
+    project_path: Option<String>,
+) -> Result<Vec<String>, String> {
+    let targets = match (preview_id, project_path) {
+        (Some(id), _) => {
+            if !state.list().iter().any(|info| info.id == id) {
+                return Err(format!("No preview with id {}", id));
+            }
+            vec![id]
+        }
+        (None, Some(project_path)) => {
+            let root = project_root(&project_path)?;
+            state.id_for_root(&root).into_iter().collect()
+        }
+        (None, None) => state.list().into_iter().map(|info| info.id).collect(),
+    };
+
+    for id in &targets {
+        stop_preview(&app, &state, id);
+    }
+    Ok(targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::{PreviewHandle, PreviewState};
+
+    #[test]
+    fn previews_are_tracked_per_project() {
+        let frontend = tempdir().unwrap();
+        let docs = tempdir().unwrap();
+        let state = PreviewState::default();
+
+        let first = state
+            .reserve(frontend.path(), "frontend", "package", "pnpm dev")
+            .unwrap();
+        let second = state
+            .reserve(docs.path(), "docs", "static", "static preview")
+            .unwrap();
+        assert_ne!(first, second);
+
+        let err = state
+            .reserve(frontend.path(), "frontend", "package", "pnpm dev")
+            .unwrap_err();
+        assert!(err.contains("already running"), "{err}");
+
+        state.update(&second, |entry| {
+            entry.port = Some(4173);
+            entry.handle = PreviewHandle::Pending;
+        });
+        let list = state.list();
+        assert_eq!(
+            list.iter().map(|info| info.id.as_str()).collect::<Vec<_>>(),
+            [first.as_str(), second.as_str()]
+        );
+        assert_eq!(list[1].port, Some(4173));
+        assert_eq!(state.id_for_root(docs.path()), Some(second.clone()));
+
+        assert!(state.remove(&first).is_some());
+        assert!(state
+            .reserve(frontend.path(), "frontend", "package", "pnpm dev")
+            .is_ok());
+    }
+}
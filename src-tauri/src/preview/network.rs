@@ -0,0 +1,182 @@
+// src-tauri/src/preview/network.rs
+//
+// Where the static preview listens. A preferred port falls back to the next
+// free one, and the server can optionally bind every interface so the site
+// can be opened from a phone on the same network.
+
+use std::{
+    fs, io,
+    net::{IpAddr, Ipv4Addr, TcpListener},
+    path::{Path, PathBuf},
+};
+
+use super::proxy::ProxyRule;
+use crate::project_files::is_env_file;
+
+/// Ports tried after the preferred one before giving up.
+const PORT_FALLBACK_ATTEMPTS: u16 = 20;
+/// How deep below the served root `.env` files are looked for.
+const ENV_SCAN_DEPTH: usize = 4;
+const ENV_SCAN_SKIP_DIRS: &[&str] = &["node_modules", ".git"];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServeOptions {
+    /// Try this port first; None lets the OS pick one.
+    pub preferred_port: Option<u16>,
+    /// Bind all interfaces instead of loopback only.
+    pub expose_on_lan: bool,
+    /// Paths forwarded to a local backend instead of served from disk.
+    pub proxy: Vec<ProxyRule>,
+    /// Serve over TLS with the machine's self-signed certificate.
+    pub https: bool,
+}
+
+/// Bind the static preview listener. With a preferred port, the next free
+/// ports are tried in turn when it is taken.
+pub fn bind_listener(options: &ServeOptions) -> Result<TcpListener, String> {
+    let host = if options.expose_on_lan {
+        IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+    } else {
+        IpAddr::V4(Ipv4Addr::LOCALHOST)
+    };
+
+    let Some(preferred) = options.preferred_port.filter(|port| *port != 0) else {
+        return TcpListener::bind((host, 0)).map_err(|e| format!("Failed to bind {}: {}", host, e));
+    };
+
+    let last = preferred.saturating_add(PORT_FALLBACK_ATTEMPTS);
+    for port in preferred..=last {
+        match TcpListener::bind((host, port)) {
+            Ok(listener) => return Ok(listener),
+            // Taken, or privileged: move on to the next port.
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::AddrInUse | io::ErrorKind::PermissionDenied
+                ) => {}
+            Err(err) => return Err(format!("Failed to bind {}:{}: {}", host, port, err)),
+        }
+    }
+    Err(format!(
+        "Ports {} to {} are all in use. Choose another preview port.",
+        preferred, last
+    ))
+}
+
+/// Addresses other devices on the network can reach this machine on.
+/// Loopback and link-local addresses are left out; IPv6 is skipped since
+/// phones rarely resolve bare IPv6 URLs.
+pub fn lan_addresses() -> Vec<Ipv4Addr> {
+    let mut addresses: Vec<Ipv4Addr> = if_addrs::get_if_addrs()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|interface| match interface.ip() {
+            IpAddr::V4(ip) if !ip.is_loopback() && !ip.is_link_local() => Some(ip),
+            _ => None,
+        })
+        .collect();
+    addresses.sort();
+    addresses.dedup();
+    addresses
+}
+
+/// `scheme://address:port/` for each LAN address.
+pub fn lan_urls(scheme: &str, port: u16) -> Vec<String> {
+    lan_addresses()
+        .into_iter()
+        .map(|ip| format!("{}://{}:{}/", scheme, ip, port))
+        .collect()
+}
+
+/// `.env` files under `root` that LAN exposure would publish. Only a start
+/// time warning: while shared, the server also refuses to serve any `.env`
+/// file or dot-folder, including ones created later.
+pub fn find_env_files(root: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let mut pending = vec![(root.to_path_buf(), 0)];
+
+    while let Some((dir, depth)) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                if depth < ENV_SCAN_DEPTH && !ENV_SCAN_SKIP_DIRS.contains(&name.as_str()) {
+                    pending.push((entry.path(), depth + 1));
+                }
+            } else if is_env_file(&name) {
+                found.push(entry.path());
+            }
+        }
+    }
+
+    found.sort();
+    found
+}
+
+/// Refuse to expose `root` on the network while it holds `.env` files.
+pub fn check_lan_exposure(root: &Path) -> Result<(), String> {
+    let env_files = find_env_files(root);
+    if env_files.is_empty() {
+        return Ok(());
+    }
+
+    let names: Vec<String> = env_files
+        .iter()
+        .map(|path| {
+            path.strip_prefix(root)
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        })
+        .collect();
+    Err(format!(
+        "Refusing to share this preview on the network: the served folder contains {} ({}). Move or delete them, or preview on this computer only.",
+        if names.len() == 1 { "an .env file" } else { ".env files" },
+        names.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, net::TcpListener};
+
+    use tempfile::tempdir;
+
+    use super::{bind_listener, check_lan_exposure, ServeOptions};
+
+    #[test]
+    fn falls_back_to_the_next_free_port() {
+        let taken = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = taken.local_addr().unwrap().port();
+
+        let listener = bind_listener(&ServeOptions {
+            preferred_port: Some(port),
+            ..ServeOptions::default()
+        })
+        .unwrap();
+        let bound = listener.local_addr().unwrap();
+        assert!(bound.ip().is_loopback());
+        assert!(bound.port() > port, "{bound}");
+    }
+
+    #[test]
+    fn refuses_lan_exposure_with_env_files() {
+        let site = tempdir().unwrap();
+        fs::write(site.path().join(".env.example"), "API_KEY=").unwrap();
+        assert!(check_lan_exposure(site.path()).is_ok());
+
+        fs::create_dir_all(site.path().join("assets/config")).unwrap();
+        fs::write(
+            site.path().join("assets/config/.env.local"),
+            "API_KEY=secret",
+        )
+        .unwrap();
+        let err = check_lan_exposure(site.path()).unwrap_err();
+        assert!(err.contains(".env.local"), "{err}");
+    }
+}
@@ -0,0 +1,335 @@
+// src-tauri/src/preview/proxy.rs
+//
+// Forwarding selected paths of a static preview to a local backend, so a
+// prototype can call `/api/...` on its own origin instead of working around
+// CORS. Only the request head is rewritten; everything after it is relayed
+// byte for byte in both directions. That covers any method, streamed and
+// chunked bodies, and WebSocket upgrades (after `101 Switching Protocols`
+// the same relay simply keeps running as a tunnel).
+
+use std::{
+    io::{self, Write},
+    net::{IpAddr, Shutdown, TcpStream, ToSocketAddrs},
+    thread,
+    time::Duration,
+};
+
+use serde::Deserialize;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Hop-by-hop headers the proxy sets itself.
+const REPLACED_HEADERS: &[&str] = &[
+    "host",
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "x-forwarded-for",
+    "x-forwarded-host",
+    "x-forwarded-proto",
+];
+
+/// A rule as sent by the frontend, e.g.
+/// `{ "path": "/api/*", "target": "http://localhost:8787" }`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyRuleConfig {
+    pub path: String,
+    pub target: String,
+    /// Drop the matched prefix before forwarding (`/api/users` -> `/users`).
+    #[serde(default)]
+    pub strip_prefix: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyRule {
+    /// Path prefix without a trailing slash; empty matches every path.
+    prefix: String,
+    host: String,
+    port: u16,
+    /// Path of the target URL, prepended to forwarded paths.
+    base_path: String,
+    strip_prefix: bool,
+}
+
+impl ProxyRule {
+    pub fn from_config(config: &ProxyRuleConfig) -> Result<Self, String> {
+        let path = config.path.trim();
+        if !path.starts_with('/') {
+            return Err(format!("Proxy path must start with '/': {}", config.path));
+        }
+        let prefix = path.trim_end_matches('*').trim_end_matches('/').to_string();
+
+        let target = config.target.trim();
+        let rest = if let Some(rest) = target.strip_prefix("http://") {
+            rest
+        } else if let Some(rest) = target.strip_prefix("ws://") {
+            rest
+        } else if target.starts_with("https://") || target.starts_with("wss://") {
+            return Err(format!(
+                "Proxy target {} uses TLS, which is not supported. Point the rule at the backend's http:// address.",
+                target
+            ));
+        } else {
+            return Err(format!(
+                "Proxy target must be an http:// URL: {}",
+                config.target
+            ));
+        };
+
+        let (authority, base_path) = match rest.find('/') {
+            Some(at) => (&rest[..at], rest[at..].trim_end_matches('/')),
+            None => (rest, ""),
+        };
+        let (host, port) = split_authority(authority)
+            .ok_or_else(|| format!("Proxy target has an invalid host or port: {}", target))?;
+
+        Ok(Self {
+            prefix,
+            host,
+            port,
+            base_path: base_path.to_string(),
+            strip_prefix: config.strip_prefix,
+        })
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        self.prefix.is_empty()
+            || path == self.prefix
+            || path
+                .strip_prefix(&self.prefix)
+                .is_some_and(|rest| rest.starts_with('/'))
+    }
+
+    fn authority(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    pub fn target_url(&self) -> String {
+        format!("http://{}{}", self.authority(), self.base_path)
+    }
+
+    /// `/api/* -> http://localhost:8787`, for logs.
+    pub fn describe(&self) -> String {
+        format!("{}/* -> {}", self.prefix, self.target_url())
+    }
+
+    /// The request target to send upstream, query string included.
+    fn upstream_target(&self, target: &str) -> String {
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (target, None),
+        };
+        let path = if self.strip_prefix {
+            match &path[self.prefix.len().min(path.len())..] {
+                "" => "/",
+                rest => rest,
+            }
+        } else {
+            path
+        };
+
+        let mut upstream = format!("{}{}", self.base_path, path);
+        if let Some(query) = query {
+            upstream.push('?');
+            upstream.push_str(query);
+        }
+        upstream
+    }
+
+    /// Connect to the target, trying every address `host` resolves to
+    /// (`localhost` may resolve to `::1` while the backend listens on IPv4).
+    pub fn connect(&self) -> io::Result<TcpStream> {
+        let mut last_err = io::Error::new(io::ErrorKind::NotFound, "host did not resolve");
+        for address in (self.host.as_str(), self.port).to_socket_addrs()? {
+            match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+                Ok(stream) => return Ok(stream),
+                Err(err) => last_err = err,
+            }
+        }
+        Err(last_err)
+    }
+
+    /// The request head as the backend should see it: the target rewritten,
+    /// `Host` set to the backend, and `X-Forwarded-*` describing the
+    /// original request (`scheme` and `client` as the browser connected).
+    /// Upgrades keep `Connection: Upgrade`; everything else is sent with
+    /// `Connection: close`, matching the preview server's one request per
+    /// connection.
+    pub fn request_head(
+        &self,
+        method: &str,
+        target: &str,
+        headers: &[(String, String)],
+        scheme: &str,
+        client: Option<IpAddr>,
+    ) -> String {
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str())
+        };
+        let upgrade = header("Connection").is_some_and(|value| {
+            value
+                .split(',')
+                .any(|token| token.trim().eq_ignore_ascii_case("upgrade"))
+        }) && header("Upgrade").is_some();
+
+        let mut head = format!("{} {} HTTP/1.1\r\n", method, self.upstream_target(target));
+        head.push_str(&format!("Host: {}\r\n", self.authority()));
+        for (name, value) in headers {
+            if !REPLACED_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+                head.push_str(&format!("{}: {}\r\n", name, value));
+            }
+        }
+        if let Some(host) = header("Host") {
+            head.push_str(&format!("X-Forwarded-Host: {}\r\n", host));
+        }
+        head.push_str(&format!("X-Forwarded-Proto: {}\r\n", scheme));
+        if let Some(client) = client {
+            head.push_str(&format!("X-Forwarded-For: {}\r\n", client));
+        }
+        head.push_str(if upgrade {
+            "Connection: Upgrade\r\n\r\n"
+        } else {
+            "Connection: close\r\n\r\n"
+        });
+        head
+    }
+}
+
+fn split_authority(authority: &str) -> Option<(String, u16)> {
+    let (host, port) = if let Some(rest) = authority.strip_prefix('[') {
+        let (host, after) = rest.split_once(']')?;
+        (host, after.strip_prefix(':'))
+    } else {
+        match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        }
+    };
+    if host.is_empty() {
+        return None;
+    }
+    let port = match port {
+        Some(port) => port.parse().ok()?,
+        None => 80,
+    };
+    Some((host.to_string(), port))
+}
+
+/// The most specific rule matching `path`.
+pub fn find_rule<'a>(rules: &'a [ProxyRule], path: &str) -> Option<&'a ProxyRule> {
+    rules
+        .iter()
+        .filter(|rule| rule.matches(path))
+        .max_by_key(|rule| rule.prefix.len())
+}
+
+/// Send `head` plus any body bytes already read, then copy both directions
+/// until either side closes.
+pub fn relay(
+    client: TcpStream,
+    mut upstream: TcpStream,
+    head: &str,
+    body_start: &[u8],
+) -> io::Result<()> {
+    // WebSockets and long polls sit idle far longer than a file request.
+    client.set_read_timeout(None)?;
+
+    upstream.write_all(head.as_bytes())?;
+    upstream.write_all(body_start)?;
+    upstream.flush()?;
+
+    let mut client_reader = client.try_clone()?;
+    let mut upstream_writer = upstream.try_clone()?;
+    let uploader = thread::spawn(move || {
+        let _ = io::copy(&mut client_reader, &mut upstream_writer);
+        let _ = upstream_writer.shutdown(Shutdown::Write);
+    });
+
+    let mut client_writer = client.try_clone()?;
+    let downloaded = io::copy(&mut upstream, &mut client_writer);
+
+    // Unblocks the uploader if the browser is still holding its side open.
+    let _ = client.shutdown(Shutdown::Both);
+    let _ = upstream.shutdown(Shutdown::Both);
+    let _ = uploader.join();
+
+    downloaded.map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_rule, ProxyRule, ProxyRuleConfig};
+
+    fn rule(path: &str, target: &str, strip_prefix: bool) -> Result<ProxyRule, String> {
+        ProxyRule::from_config(&ProxyRuleConfig {
+            path: path.to_string(),
+            target: target.to_string(),
+            strip_prefix,
+        })
+    }
+
+    #[test]
+    fn parses_rules_and_rewrites_targets() {
+        let api = rule("/api/*", "http://localhost:8787", false).unwrap();
+        let auth = rule("/api/auth", "http://127.0.0.1:9000/v1/", true).unwrap();
+        let rules = vec![api.clone(), auth.clone()];
+
+        assert_eq!(find_rule(&rules, "/api/items"), Some(&api));
+        assert_eq!(find_rule(&rules, "/api/auth/login"), Some(&auth));
+        assert_eq!(find_rule(&rules, "/apiary"), None);
+        assert_eq!(find_rule(&rules, "/index.html"), None);
+
+        assert_eq!(
+            api.upstream_target("/api/items?page=2"),
+            "/api/items?page=2"
+        );
+        assert_eq!(auth.upstream_target("/api/auth/login"), "/v1/login");
+        assert_eq!(auth.upstream_target("/api/auth?next=/"), "/v1/?next=/");
+        assert_eq!(auth.describe(), "/api/auth/* -> http://127.0.0.1:9000/v1");
+
+        assert!(rule("api", "http://localhost:8787", false).is_err());
+        assert!(rule("/api", "https://localhost:8787", false)
+            .unwrap_err()
+            .contains("TLS"));
+        assert!(rule("/api", "http://localhost:port", false).is_err());
+        assert_eq!(
+            rule("/", "http://[::1]:8787", false).unwrap().target_url(),
+            "http://[::1]:8787"
+        );
+    }
+
+    #[test]
+    fn rewrites_the_request_head() {
+        let api = rule("/api", "http://localhost:8787", false).unwrap();
+        let headers = vec![
+            ("Host".to_string(), "127.0.0.1:5000".to_string()),
+            ("Connection".to_string(), "keep-alive".to_string()),
+            ("Content-Type".to_string(), "application/json".to_string()),
+        ];
+        let client = "192.168.1.30".parse().ok();
+        let head = api.request_head("POST", "/api/items", &headers, "https", client);
+        assert!(head.starts_with("POST /api/items HTTP/1.1\r\nHost: localhost:8787\r\n"));
+        assert!(head.contains("Content-Type: application/json\r\n"));
+        assert!(head.contains("X-Forwarded-Host: 127.0.0.1:5000\r\n"));
+        assert!(head.contains("X-Forwarded-Proto: https\r\n"));
+        assert!(head.contains("X-Forwarded-For: 192.168.1.30\r\n"));
+        assert!(head.ends_with("Connection: close\r\n\r\n"));
+        assert!(!head.contains("keep-alive"));
+
+        let upgrade = vec![
+            ("Connection".to_string(), "keep-alive, Upgrade".to_string()),
+            ("Upgrade".to_string(), "websocket".to_string()),
+        ];
+        let head = api.request_head("GET", "/api/socket", &upgrade, "http", None);
+        assert!(head.contains("Upgrade: websocket\r\n"));
+        assert!(head.ends_with("Connection: Upgrade\r\n\r\n"));
+    }
+}
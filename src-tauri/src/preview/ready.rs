@@ -0,0 +1,243 @@
+// src-tauri/src/preview/ready.rs
+//
+// Working out when a dev server is ready and where. Known output lines
+// (Vite "Local:", Next "ready - started server on", Expo "Waiting on ...")
+// give the URL directly; otherwise the expected port is polled until it
+// accepts connections.
+
+use std::{
+    fs,
+    net::{SocketAddr, TcpStream},
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::ansi::strip_ansi_sequences;
+
+pub const PORT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Expo and first-run Next builds can take well over a minute.
+pub const PORT_POLL_TIMEOUT: Duration = Duration::from_secs(180);
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// Phrases that introduce the address a dev server is serving on.
+const READY_MARKERS: &[&str] = &[
+    "local:",
+    "local ",
+    "ready - started server on",
+    "ready on",
+    "started server on",
+    "waiting on",
+    "listening on",
+    "server running at",
+    "running at",
+    "available on",
+];
+
+const URL_SCHEMES: &[&str] = &["http://", "https://", "exp://"];
+
+/// `(dependency, default port)`, most specific first.
+const FRAMEWORK_PORTS: &[(&str, u16)] = &[
+    ("expo", 8081),
+    ("next", 3000),
+    ("nuxt", 3000),
+    ("@remix-run/dev", 3000),
+    ("react-scripts", 3000),
+    ("astro", 4321),
+    ("@angular/cli", 4200),
+    ("@vue/cli-service", 8080),
+    ("gatsby", 8000),
+    ("@sveltejs/kit", 5173),
+    ("vite", 5173),
+];
+
+/// Wildcard binds are not browsable; point at loopback instead.
+fn browsable(url: &str) -> String {
+    url.replacen("://0.0.0.0", "://localhost", 1)
+        .replacen("://[::]", "://localhost", 1)
+}
+
+fn extract_url(text: &str) -> Option<String> {
+    let start = URL_SCHEMES
+        .iter()
+        .filter_map(|scheme| text.find(scheme))
+        .min()?;
+    let url: String = text[start..]
+        .chars()
+        .take_while(|c| !c.is_whitespace() && !matches!(c, '"' | '\'' | '<' | '>' | ')'))
+        .collect();
+    let url = url.trim_end_matches(['.', ',', ';']);
+    (url.len() > "http://".len()).then(|| browsable(url))
+}
+
+/// `0.0.0.0:3000` after "started server on" in older Next releases.
+fn extract_host_port(text: &str) -> Option<String> {
+    let token = text.split_whitespace().next()?.trim_end_matches(',');
+    let (host, port) = token.rsplit_once(':')?;
+    let port: u16 = port.parse().ok()?;
+    let host = match host {
+        "" | "0.0.0.0" | "[::]" | "::" => "localhost",
+        other => other,
+    };
+    Some(format!("http://{}:{}", host, port))
+}
+
+/// The serving URL announced by a line of dev-server output, if any.
+/// `extra_markers` are lowercase phrases a particular runner uses on top of
+/// the common ones. LAN ("Network:") addresses are ignored in favour of the
+/// local one.
+pub fn detect_dev_server_url(line: &str, extra_markers: &[&str]) -> Option<String> {
+    let clean = strip_ansi_sequences(line);
+    let lower = clean.to_ascii_lowercase();
+    if lower.contains("network:") {
+        return None;
+    }
+
+    let (marker_at, marker) = READY_MARKERS
+        .iter()
+        .chain(extra_markers)
+        .filter_map(|marker| lower.find(marker).map(|at| (at, *marker)))
+        .min_by_key(|(at, _)| *at)?;
+    let rest = &clean[marker_at + marker.len()..];
+
+    extract_url(rest).or_else(|| extract_host_port(rest))
+}
+
+pub fn port_from_url(url: &str) -> Option<u16> {
+    let after_scheme = url.split_once("://").map(|(_, rest)| rest)?;
+    let authority = after_scheme.split('/').next()?;
+    match authority.rsplit_once(':') {
+        Some((_, port)) => port.parse().ok(),
+        None if url.starts_with("https://") => Some(443),
+        None => Some(80),
+    }
+}
+
+fn port_flag(script: &str) -> Option<u16> {
+    let mut tokens = script.split_whitespace();
+    while let Some(token) = tokens.next() {
+        if let Some(value) = token.strip_prefix("--port=") {
+            return value.parse().ok();
+        }
+        if token == "--port" || token == "-p" {
+            return tokens.next().and_then(|value| value.parse().ok());
+        }
+    }
+    None
+}
+
+/// Best guess at the port `pnpm dev` will listen on: an explicit `--port`
+/// in the dev script, else the framework's default.
+pub fn expected_dev_port(project_path: &Path) -> Option<u16> {
+    let text = fs::read_to_string(project_path.join("package.json")).ok()?;
+    let package: serde_json::Value = serde_json::from_str(&text).ok()?;
+
+    if let Some(port) = package
+        .pointer("/scripts/dev")
+        .and_then(|script| script.as_str())
+        .and_then(port_flag)
+    {
+        return Some(port);
+    }
+
+    let has_dependency = |name: &str| {
+        ["dependencies", "devDependencies"].iter().any(|section| {
+            package
+                .get(section)
+                .and_then(|deps| deps.get(name))
+                .is_some()
+        })
+    };
+    FRAMEWORK_PORTS
+        .iter()
+        .find(|(name, _)| has_dependency(name))
+        .map(|(_, port)| *port)
+}
+
+/// Poll `port` on loopback until it accepts a connection, the timeout
+/// passes, or `keep_waiting` returns false.
+pub fn wait_for_port(port: u16, timeout: Duration, mut keep_waiting: impl FnMut() -> bool) -> bool {
+    let deadline = Instant::now() + timeout;
+    let addresses = [
+        SocketAddr::from(([127, 0, 0, 1], port)),
+        SocketAddr::from((std::net::Ipv6Addr::LOCALHOST, port)),
+    ];
+
+    while Instant::now() < deadline && keep_waiting() {
+        if addresses
+            .iter()
+            .any(|address| TcpStream::connect_timeout(address, CONNECT_TIMEOUT).is_ok())
+        {
+            return true;
+        }
+        thread::sleep(PORT_POLL_INTERVAL);
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, net::TcpListener, time::Duration};
+
+    use tempfile::tempdir;
+
+    use super::{detect_dev_server_url, expected_dev_port, port_from_url, wait_for_port};
+
+    #[test]
+    fn detects_urls_from_common_dev_servers() {
+        for (line, expected) in [
+            (
+                "  \u{1b}[32m➜\u{1b}[39m  \u{1b}[1mLocal\u{1b}[22m:   \u{1b}[36mhttp://localhost:\u{1b}[1m5173\u{1b}[22m/\u{1b}[39m",
+                Some("http://localhost:5173/"),
+            ),
+            (
+                "ready - started server on 0.0.0.0:3000, url: http://localhost:3000",
+                Some("http://localhost:3000"),
+            ),
+            ("ready - started server on 0.0.0.0:3001", Some("http://localhost:3001")),
+            ("   - Local:        http://localhost:3000", Some("http://localhost:3000")),
+            (
+                "   - Local:        \u{1b}]8;;http://localhost:3000\u{7}http://localhost:3000\u{1b}]8;;\u{7}",
+                Some("http://localhost:3000"),
+            ),
+            ("› Metro waiting on exp://192.168.1.20:8081", Some("exp://192.168.1.20:8081")),
+            ("› Web is waiting on http://localhost:8081", Some("http://localhost:8081")),
+            ("  ➜  Network: http://192.168.1.20:5173/", None),
+            ("vite v5.0.0 building for development...", None),
+        ] {
+            assert_eq!(detect_dev_server_url(line, &[]).as_deref(), expected, "{line}");
+        }
+        assert_eq!(
+            detect_dev_server_url(" * Running on http://127.0.0.1:5000", &["running on"])
+                .as_deref(),
+            Some("http://127.0.0.1:5000")
+        );
+
+        assert_eq!(port_from_url("http://localhost:5173/"), Some(5173));
+        assert_eq!(port_from_url("https://example.test/app"), Some(443));
+    }
+
+    #[test]
+    fn guesses_the_dev_port_and_polls_until_it_opens() {
+        let project = tempdir().unwrap();
+        fs::write(
+            project.path().join("package.json"),
+            r#"{"scripts":{"dev":"vite --host --port 6100"},"devDependencies":{"vite":"^5"}}"#,
+        )
+        .unwrap();
+        assert_eq!(expected_dev_port(project.path()), Some(6100));
+
+        fs::write(
+            project.path().join("package.json"),
+            r#"{"scripts":{"dev":"next dev"},"dependencies":{"next":"14","react":"18"}}"#,
+        )
+        .unwrap();
+        assert_eq!(expected_dev_port(project.path()), Some(3000));
+
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert!(wait_for_port(port, Duration::from_secs(2), || true));
+        drop(listener);
+        assert!(!wait_for_port(port, Duration::from_secs(2), || false));
+    }
+}
@@ -0,0 +1,205 @@
+// src-tauri/src/preview/request_log.rs
+//
+// Every request a static preview handled: what was asked for, which file
+// answered (or that nothing did), and whether a navigation fell back to
+// `index.html`. Broken asset references show up here as 404s the page
+// itself never reports. Records also go out as events for a live network
+// panel; this store is the backlog behind them.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::log_buffer::now_ms;
+
+/// Requests kept per preview.
+const REQUEST_CAPACITY: usize = 1000;
+/// Previews whose requests are kept; the least recently active goes first.
+const MAX_PREVIEWS: usize = 16;
+const DEFAULT_QUERY_LIMIT: usize = 200;
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestRecord {
+    /// Increases by one per request within a preview; use it to page.
+    pub seq: u64,
+    pub timestamp_ms: u64,
+    /// Address of the browser that sent the request.
+    pub client: Option<String>,
+    pub method: String,
+    /// Request target as sent, query string included.
+    pub path: String,
+    /// None when the response came from a proxy target or is the live
+    /// reload event stream.
+    pub status: Option<u16>,
+    /// File that answered, relative to the served folder.
+    pub file: Option<String>,
+    /// Body bytes sent.
+    pub bytes: u64,
+    pub duration_ms: u64,
+    /// A navigation that matched no file and was answered with `index.html`.
+    pub fallback: bool,
+    /// "file", "proxy", "live-reload" or "invalid".
+    pub handler: &'static str,
+}
+
+impl RequestRecord {
+    /// Error statuses and fallback hits: what a network panel should flag.
+    pub fn is_problem(&self) -> bool {
+        self.fallback || self.status.is_some_and(|status| status >= 400)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestQuery {
+    /// Only requests recorded after this sequence number.
+    pub after_seq: Option<u64>,
+    /// Only error statuses and index.html fallbacks.
+    #[serde(default)]
+    pub problems_only: bool,
+    /// Case-insensitive substring of the path.
+    pub path: Option<String>,
+    pub limit: Option<usize>,
+}
+
+struct PreviewRequests {
+    records: VecDeque<RequestRecord>,
+    next_seq: u64,
+    last_write: u64,
+}
+
+#[derive(Default)]
+pub struct RequestLog {
+    inner: Mutex<RequestLogInner>,
+}
+
+#[derive(Default)]
+struct RequestLogInner {
+    previews: HashMap<String, PreviewRequests>,
+    writes: u64,
+}
+
+impl RequestLog {
+    /// Store `record`, stamping its sequence number and time. Returns the
+    /// stored copy for emitting.
+    pub fn push(&self, preview_id: &str, mut record: RequestRecord) -> RequestRecord {
+        let Ok(mut inner) = self.inner.lock() else {
+            return record;
+        };
+        inner.writes += 1;
+        let write_order = inner.writes;
+
+        if !inner.previews.contains_key(preview_id) && inner.previews.len() >= MAX_PREVIEWS {
+            let oldest = inner
+                .previews
+                .iter()
+                .min_by_key(|(_, requests)| requests.last_write)
+                .map(|(id, _)| id.clone());
+            if let Some(oldest) = oldest {
+                inner.previews.remove(&oldest);
+            }
+        }
+
+        let requests = inner
+            .previews
+            .entry(preview_id.to_string())
+            .or_insert_with(|| PreviewRequests {
+                records: VecDeque::new(),
+                next_seq: 0,
+                last_write: 0,
+            });
+        if requests.records.len() == REQUEST_CAPACITY {
+            requests.records.pop_front();
+        }
+        requests.next_seq += 1;
+        requests.last_write = write_order;
+        record.seq = requests.next_seq;
+        record.timestamp_ms = now_ms();
+        requests.records.push_back(record.clone());
+        record
+    }
+
+    /// Matching requests, oldest first; the newest `limit` when there are
+    /// more.
+    pub fn query(&self, preview_id: &str, query: &RequestQuery) -> Vec<RequestRecord> {
+        let Ok(inner) = self.inner.lock() else {
+            return Vec::new();
+        };
+        let Some(requests) = inner.previews.get(preview_id) else {
+            return Vec::new();
+        };
+
+        let needle = query.path.as_deref().map(str::to_lowercase);
+        let matching: Vec<&RequestRecord> = requests
+            .records
+            .iter()
+            .filter(|record| query.after_seq.is_none_or(|after| record.seq > after))
+            .filter(|record| !query.problems_only || record.is_problem())
+            .filter(|record| {
+                needle
+                    .as_deref()
+                    .is_none_or(|needle| record.path.to_lowercase().contains(needle))
+            })
+            .collect();
+        let limit = query.limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+        let start = matching.len().saturating_sub(limit);
+        matching[start..]
+            .iter()
+            .map(|record| (*record).clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RequestLog, RequestQuery, RequestRecord};
+
+    fn request(path: &str, status: u16, fallback: bool) -> RequestRecord {
+        RequestRecord {
+            method: "GET".to_string(),
+            path: path.to_string(),
+            status: Some(status),
+            fallback,
+            handler: "file",
+            ..RequestRecord::default()
+        }
+    }
+
+    #[test]
+    fn filters_problems_and_pages_by_sequence() {
+        let log = RequestLog::default();
+        log.push("preview-1", request("/", 200, false));
+        log.push("preview-1", request("/assets/app.js", 404, false));
+        log.push("preview-1", request("/settings", 200, true));
+        let last = log.push("preview-1", request("/assets/logo.svg", 304, false));
+        assert_eq!(last.seq, 4);
+
+        let problems = log.query(
+            "preview-1",
+            &RequestQuery {
+                problems_only: true,
+                ..RequestQuery::default()
+            },
+        );
+        assert_eq!(
+            problems.iter().map(|r| r.path.as_str()).collect::<Vec<_>>(),
+            ["/assets/app.js", "/settings"]
+        );
+
+        let assets = log.query(
+            "preview-1",
+            &RequestQuery {
+                after_seq: Some(2),
+                path: Some("ASSETS".to_string()),
+                ..RequestQuery::default()
+            },
+        );
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].seq, 4);
+        assert!(log.query("preview-2", &RequestQuery::default()).is_empty());
+    }
+}
@@ -0,0 +1,654 @@
+// src-tauri/src/preview/runners.rs
+//
+// Dev previews for projects that are not Node packages: Python web apps,
+// Deno, Rust web projects and plain PHP. Each kind is a `PreviewRunner`
+// that recognises its projects and says how to install dependencies, how
+// to start the dev server on a given port, and which output lines announce
+// that it is ready. Supporting another stack means adding a runner to
+// `RUNNERS`.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::package_manager;
+
+/// A program and its arguments, run from the project folder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunnerCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Extra environment, e.g. `PORT` for servers that read it.
+    pub env: Vec<(String, String)>,
+}
+
+impl RunnerCommand {
+    pub fn new(program: impl Into<String>, args: &[&str]) -> Self {
+        Self {
+            program: program.into(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            env: Vec::new(),
+        }
+    }
+
+    fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    fn env(mut self, key: &str, value: impl Into<String>) -> Self {
+        self.env.push((key.to_string(), value.into()));
+        self
+    }
+
+    /// `python -m flask run`, without the interpreter's folder or `.exe`.
+    pub fn label(&self) -> String {
+        let program = Path::new(&self.program)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.program.clone());
+        std::iter::once(program)
+            .chain(self.args.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Error for a program that cannot be found, if this one cannot.
+    pub fn check_available(&self) -> Result<(), String> {
+        let path = Path::new(&self.program);
+        let found = if path.is_absolute() {
+            path.is_file()
+        } else {
+            package_manager::is_on_path(&self.program)
+        };
+        if found {
+            return Ok(());
+        }
+        Err(format!(
+            "{} could not be found. Install it or restart KForge after adding it to your PATH.",
+            self.program
+        ))
+    }
+}
+
+pub struct DevServer {
+    pub command: RunnerCommand,
+    /// Why this command was chosen, for the log.
+    pub reason: String,
+}
+
+pub trait PreviewRunner: Send + Sync {
+    /// Reported by `preview_detect_kind` and as the kind of the dev preview.
+    fn id(&self) -> &'static str;
+    fn name(&self) -> &'static str;
+    /// Why `root` is this kind of project, or None when it is not.
+    fn detect(&self, root: &Path) -> Option<String>;
+    /// Whether this runner wins over a `package.json` in the same folder.
+    fn overrides_package_json(&self) -> bool {
+        false
+    }
+    /// Commands run in order by "Install"; empty when there is nothing to
+    /// install.
+    fn install(&self, root: &Path) -> Vec<RunnerCommand>;
+    /// Port tried first for the dev server; taken ports fall back to the
+    /// next free one.
+    fn default_port(&self, root: &Path) -> u16;
+    fn dev_server(&self, root: &Path, port: u16) -> Result<DevServer, String>;
+    /// Output phrases, besides the common ones, that come right before the
+    /// serving URL.
+    fn ready_markers(&self) -> &'static [&'static str] {
+        &[]
+    }
+}
+
+/// Checked in order.
+pub static RUNNERS: &[&dyn PreviewRunner] =
+    &[&DenoRunner, &PythonRunner, &CargoWebRunner, &PhpRunner];
+
+/// The runner for `root`. Runners that do not override a `package.json`
+/// are only considered when there is none.
+pub fn detect(root: &Path) -> Option<(&'static dyn PreviewRunner, String)> {
+    let has_package_json = root.join("package.json").is_file();
+    RUNNERS
+        .iter()
+        .filter(|runner| !has_package_json || runner.overrides_package_json())
+        .find_map(|runner| runner.detect(root).map(|reason| (*runner, reason)))
+}
+
+fn read_lowercase(path: &Path) -> String {
+    fs::read_to_string(path)
+        .map(|text| text.to_ascii_lowercase())
+        .unwrap_or_default()
+}
+
+fn first_file<'a>(root: &Path, candidates: &[&'a str]) -> Option<&'a str> {
+    candidates
+        .iter()
+        .copied()
+        .find(|candidate| root.join(candidate).is_file())
+}
+
+// Python -------------------------------------------------------------------
+
+const PYTHON_ENTRIES: &[&str] = &["main.py", "app.py", "server.py", "app/main.py"];
+const VENV_DIRS: &[&str] = &[".venv", "venv"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PythonFramework {
+    FastApi,
+    Flask,
+    Plain,
+}
+
+pub struct PythonRunner;
+
+impl PythonRunner {
+    fn framework(root: &Path) -> PythonFramework {
+        let declared = read_lowercase(&root.join("requirements.txt"))
+            + &read_lowercase(&root.join("pyproject.toml"));
+        if declared.contains("fastapi") {
+            PythonFramework::FastApi
+        } else if declared.contains("flask") {
+            PythonFramework::Flask
+        } else {
+            PythonFramework::Plain
+        }
+    }
+
+    /// Interpreter path inside a virtualenv folder.
+    fn venv_interpreter(venv: &Path) -> PathBuf {
+        if cfg!(windows) {
+            venv.join("Scripts").join("python.exe")
+        } else {
+            venv.join("bin").join("python")
+        }
+    }
+
+    fn venv_python(root: &Path) -> Option<PathBuf> {
+        VENV_DIRS
+            .iter()
+            .map(|dir| Self::venv_interpreter(&root.join(dir)))
+            .find(|python| python.is_file())
+    }
+
+    fn system_python() -> &'static str {
+        if cfg!(windows) {
+            "python"
+        } else {
+            "python3"
+        }
+    }
+
+    /// The project's virtualenv interpreter, else the one on PATH.
+    fn python(root: &Path) -> String {
+        Self::venv_python(root)
+            .map(|python| python.to_string_lossy().to_string())
+            .unwrap_or_else(|| Self::system_python().to_string())
+    }
+}
+
+impl PreviewRunner for PythonRunner {
+    fn id(&self) -> &'static str {
+        "python"
+    }
+
+    fn name(&self) -> &'static str {
+        "Python"
+    }
+
+    fn detect(&self, root: &Path) -> Option<String> {
+        first_file(root, &["pyproject.toml", "requirements.txt"])
+            .map(|file| format!("found {}", file))
+    }
+
+    /// Dependencies go into the project's virtualenv, created as `.venv`
+    /// when there is none, never into the system interpreter.
+    fn install(&self, root: &Path) -> Vec<RunnerCommand> {
+        let mut steps = Vec::new();
+        let python = match Self::venv_python(root) {
+            Some(python) => python,
+            None => {
+                steps.push(RunnerCommand::new(
+                    Self::system_python(),
+                    &["-m", "venv", ".venv"],
+                ));
+                Self::venv_interpreter(&root.join(".venv"))
+            }
+        };
+        let python = python.to_string_lossy().to_string();
+
+        if root.join("requirements.txt").is_file() {
+            steps.push(RunnerCommand::new(
+                python,
+                &["-m", "pip", "install", "-r", "requirements.txt"],
+            ));
+        } else {
+            steps.push(RunnerCommand::new(
+                python,
+                &["-m", "pip", "install", "-e", "."],
+            ));
+        }
+        steps
+    }
+
+    fn default_port(&self, root: &Path) -> u16 {
+        match Self::framework(root) {
+            PythonFramework::Flask => 5000,
+            PythonFramework::FastApi | PythonFramework::Plain => 8000,
+        }
+    }
+
+    fn dev_server(&self, root: &Path, port: u16) -> Result<DevServer, String> {
+        let entry = first_file(root, PYTHON_ENTRIES).ok_or_else(|| {
+            format!(
+                "No Python entry point found (looked for {}).",
+                PYTHON_ENTRIES.join(", ")
+            )
+        })?;
+        let module = entry.trim_end_matches(".py").replace('/', ".");
+        let python = Self::python(root);
+        let interpreter = if python == Self::system_python() {
+            format!("{} on PATH (no .venv found)", python)
+        } else {
+            "the project's virtualenv".to_string()
+        };
+        let port = port.to_string();
+
+        let (command, framework) = match Self::framework(root) {
+            PythonFramework::FastApi => (
+                RunnerCommand::new(python, &["-m", "uvicorn"])
+                    .arg(format!("{}:app", module))
+                    .arg("--reload")
+                    .arg("--port")
+                    .arg(&port),
+                "FastAPI",
+            ),
+            PythonFramework::Flask => (
+                RunnerCommand::new(python, &["-m", "flask", "--app"])
+                    .arg(module)
+                    .arg("run")
+                    .arg("--debug")
+                    .arg("--port")
+                    .arg(&port),
+                "Flask",
+            ),
+            PythonFramework::Plain => (RunnerCommand::new(python, &[entry]), "plain Python"),
+        };
+
+        Ok(DevServer {
+            command: command.env("PORT", port),
+            reason: format!("{} app in {}, using {}", framework, entry, interpreter),
+        })
+    }
+
+    fn ready_markers(&self) -> &'static [&'static str] {
+        // Flask: "Running on http://...", Uvicorn: "Uvicorn running on ...".
+        &["running on"]
+    }
+}
+
+// Deno ---------------------------------------------------------------------
+
+const DENO_CONFIGS: &[&str] = &["deno.json", "deno.jsonc"];
+const DENO_ENTRIES: &[&str] = &["main.ts", "main.tsx", "main.js", "server.ts", "mod.ts"];
+const DENO_TASKS: &[&str] = &["dev", "start"];
+
+pub struct DenoRunner;
+
+impl DenoRunner {
+    /// The dev task named in `deno.json`. `.jsonc` files with comments are
+    /// not parsed and fall back to an entry file.
+    fn task(root: &Path) -> Option<&'static str> {
+        let config = DENO_CONFIGS
+            .iter()
+            .filter_map(|file| fs::read_to_string(root.join(file)).ok())
+            .find_map(|text| serde_json::from_str::<serde_json::Value>(&text).ok())?;
+        DENO_TASKS
+            .iter()
+            .copied()
+            .find(|task| config.pointer(&format!("/tasks/{}", task)).is_some())
+    }
+}
+
+impl PreviewRunner for DenoRunner {
+    fn id(&self) -> &'static str {
+        "deno"
+    }
+
+    fn name(&self) -> &'static str {
+        "Deno"
+    }
+
+    fn detect(&self, root: &Path) -> Option<String> {
+        first_file(root, DENO_CONFIGS).map(|file| format!("found {}", file))
+    }
+
+    /// Deno projects often carry a `package.json` for npm packages.
+    fn overrides_package_json(&self) -> bool {
+        true
+    }
+
+    fn install(&self, _root: &Path) -> Vec<RunnerCommand> {
+        vec![RunnerCommand::new("deno", &["install"])]
+    }
+
+    fn default_port(&self, _root: &Path) -> u16 {
+        8000
+    }
+
+    fn dev_server(&self, root: &Path, port: u16) -> Result<DevServer, String> {
+        let (command, reason) = if let Some(task) = Self::task(root) {
+            (
+                RunnerCommand::new("deno", &["task", task]),
+                format!("\"{}\" task in deno.json", task),
+            )
+        } else {
+            let entry = first_file(root, DENO_ENTRIES).ok_or_else(|| {
+                format!(
+                    "deno.json has no dev or start task and no entry file was found (looked for {}).",
+                    DENO_ENTRIES.join(", ")
+                )
+            })?;
+            (
+                RunnerCommand::new(
+                    "deno",
+                    &[
+                        "run",
+                        "--watch",
+                        "--allow-net",
+                        "--allow-read",
+                        "--allow-env",
+                        entry,
+                    ],
+                ),
+                format!("no dev task, running {}", entry),
+            )
+        };
+        Ok(DevServer {
+            command: command.env("PORT", port.to_string()),
+            reason,
+        })
+    }
+}
+
+// Rust ---------------------------------------------------------------------
+
+/// `(crate, default port)` of server frameworks run with `cargo run`.
+const CARGO_WEB_FRAMEWORKS: &[(&str, u16)] = &[
+    ("axum", 3000),
+    ("actix-web", 8080),
+    ("rocket", 8000),
+    ("warp", 3030),
+    ("poem", 3000),
+    ("salvo", 5800),
+    ("tide", 8080),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CargoWebFlavor {
+    /// `cargo leptos watch`.
+    Leptos,
+    /// A client-side WASM app served by `trunk serve`.
+    Trunk,
+    Server(&'static str),
+}
+
+pub struct CargoWebRunner;
+
+impl CargoWebRunner {
+    fn manifest(root: &Path) -> Option<String> {
+        fs::read_to_string(root.join("Cargo.toml")).ok()
+    }
+
+    /// A `name = ...`, `name.workspace = true` or `[dependencies.name]` entry.
+    fn has_dependency(manifest: &str, name: &str) -> bool {
+        manifest.lines().any(|line| {
+            let line = line.trim();
+            line == format!("[dependencies.{}]", name)
+                || line.strip_prefix(name).is_some_and(|rest| {
+                    let rest = rest.trim_start();
+                    rest.starts_with('=') || rest.starts_with(".workspace")
+                })
+        })
+    }
+
+    fn flavor(root: &Path) -> Option<CargoWebFlavor> {
+        let manifest = Self::manifest(root)?;
+        if manifest.contains("[package.metadata.leptos]") {
+            return Some(CargoWebFlavor::Leptos);
+        }
+        if root.join("Trunk.toml").is_file() || root.join("index.html").is_file() {
+            return Some(CargoWebFlavor::Trunk);
+        }
+        CARGO_WEB_FRAMEWORKS
+            .iter()
+            .find(|(name, _)| Self::has_dependency(&manifest, name))
+            .map(|(name, _)| CargoWebFlavor::Server(name))
+    }
+
+    /// Port from `site-addr = "127.0.0.1:3000"` in the Leptos metadata.
+    fn leptos_port(root: &Path) -> Option<u16> {
+        Self::manifest(root)?
+            .lines()
+            .map(str::trim)
+            .find(|line| line.starts_with("site-addr"))?
+            .rsplit(':')
+            .next()?
+            .trim_matches(|c: char| !c.is_ascii_digit())
+            .parse()
+            .ok()
+    }
+}
+
+impl PreviewRunner for CargoWebRunner {
+    fn id(&self) -> &'static str {
+        "cargo"
+    }
+
+    fn name(&self) -> &'static str {
+        "Rust"
+    }
+
+    fn detect(&self, root: &Path) -> Option<String> {
+        let reason = match Self::flavor(root)? {
+            CargoWebFlavor::Leptos => "Leptos project".to_string(),
+            CargoWebFlavor::Trunk => "Cargo.toml with index.html (Trunk)".to_string(),
+            CargoWebFlavor::Server(name) => format!("{} server", name),
+        };
+        Some(reason)
+    }
+
+    fn install(&self, _root: &Path) -> Vec<RunnerCommand> {
+        vec![RunnerCommand::new("cargo", &["fetch"])]
+    }
+
+    fn default_port(&self, root: &Path) -> u16 {
+        match Self::flavor(root) {
+            Some(CargoWebFlavor::Leptos) => Self::leptos_port(root).unwrap_or(3000),
+            Some(CargoWebFlavor::Server(name)) => CARGO_WEB_FRAMEWORKS
+                .iter()
+                .find(|(framework, _)| *framework == name)
+                .map(|(_, port)| *port)
+                .unwrap_or(8080),
+            Some(CargoWebFlavor::Trunk) | None => 8080,
+        }
+    }
+
+    fn dev_server(&self, root: &Path, port: u16) -> Result<DevServer, String> {
+        let flavor = Self::flavor(root).ok_or_else(|| {
+            "Cargo.toml does not look like a web project (no Leptos, Trunk or web server crate)."
+                .to_string()
+        })?;
+        let port = port.to_string();
+        let (command, reason) = match flavor {
+            CargoWebFlavor::Leptos => (
+                RunnerCommand::new("cargo", &["leptos", "watch"])
+                    .env("LEPTOS_SITE_ADDR", format!("127.0.0.1:{}", port)),
+                "Leptos project".to_string(),
+            ),
+            CargoWebFlavor::Trunk => (
+                RunnerCommand::new("trunk", &["serve", "--port"]).arg(&port),
+                "client-side app served by Trunk".to_string(),
+            ),
+            // The port is only a hint: servers that hard-code theirs are
+            // still found through their output.
+            CargoWebFlavor::Server(name) => (
+                RunnerCommand::new("cargo", &["run"]).env("ROCKET_PORT", &port),
+                format!("{} server run with cargo run", name),
+            ),
+        };
+        Ok(DevServer {
+            command: command.env("PORT", port),
+            reason,
+        })
+    }
+
+    fn ready_markers(&self) -> &'static [&'static str] {
+        // Rocket: "Rocket has launched from http://...".
+        &["launched from", "serving at"]
+    }
+}
+
+// PHP ----------------------------------------------------------------------
+
+pub struct PhpRunner;
+
+impl PhpRunner {
+    /// Laravel-style `public/` when it holds the front controller.
+    fn document_root(root: &Path) -> &'static str {
+        if root.join("public/index.php").is_file() {
+            "public"
+        } else {
+            "."
+        }
+    }
+}
+
+impl PreviewRunner for PhpRunner {
+    fn id(&self) -> &'static str {
+        "php"
+    }
+
+    fn name(&self) -> &'static str {
+        "PHP"
+    }
+
+    fn detect(&self, root: &Path) -> Option<String> {
+        first_file(root, &["index.php", "public/index.php", "composer.json"])
+            .map(|file| format!("found {}", file))
+    }
+
+    fn install(&self, root: &Path) -> Vec<RunnerCommand> {
+        if root.join("composer.json").is_file() {
+            vec![RunnerCommand::new("composer", &["install"])]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn default_port(&self, _root: &Path) -> u16 {
+        8000
+    }
+
+    fn dev_server(&self, root: &Path, port: u16) -> Result<DevServer, String> {
+        let document_root = Self::document_root(root);
+        Ok(DevServer {
+            command: RunnerCommand::new("php", &["-S"])
+                .arg(format!("127.0.0.1:{}", port))
+                .arg("-t")
+                .arg(document_root),
+            reason: format!("built-in server for {}", document_root),
+        })
+    }
+
+    fn ready_markers(&self) -> &'static [&'static str] {
+        // "PHP 8.3.0 Development Server (http://127.0.0.1:8000) started".
+        &["development server ("]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::tempdir;
+
+    use super::{detect, CargoWebRunner, PhpRunner, PreviewRunner, PythonRunner};
+
+    #[test]
+    fn detects_project_kinds() {
+        let project = tempdir().unwrap();
+        assert!(detect(project.path()).is_none());
+
+        fs::write(project.path().join("requirements.txt"), "Flask==3.0\n").unwrap();
+        assert_eq!(detect(project.path()).unwrap().0.id(), "python");
+
+        // A package.json makes it a Node project, unless Deno is configured.
+        fs::write(project.path().join("package.json"), "{}").unwrap();
+        assert!(detect(project.path()).is_none());
+        fs::write(project.path().join("deno.json"), "{\"tasks\":{}}").unwrap();
+        assert_eq!(detect(project.path()).unwrap().0.id(), "deno");
+
+        let cli = tempdir().unwrap();
+        fs::write(
+            cli.path().join("Cargo.toml"),
+            "[package]\nname = \"tool\"\n\n[dependencies]\nclap = \"4\"\n",
+        )
+        .unwrap();
+        assert!(detect(cli.path()).is_none());
+
+        let server = tempdir().unwrap();
+        fs::write(
+            server.path().join("Cargo.toml"),
+            "[package]\nname = \"api\"\n\n[dependencies]\naxum = { version = \"0.7\" }\n",
+        )
+        .unwrap();
+        let (runner, reason) = detect(server.path()).unwrap();
+        assert_eq!((runner.id(), reason.as_str()), ("cargo", "axum server"));
+        assert_eq!(CargoWebRunner.default_port(server.path()), 3000);
+    }
+
+    #[test]
+    fn builds_dev_server_commands() {
+        let fastapi = tempdir().unwrap();
+        fs::write(
+            fastapi.path().join("pyproject.toml"),
+            "[project]\ndependencies = [\"fastapi\", \"uvicorn\"]\n",
+        )
+        .unwrap();
+        assert!(PythonRunner.dev_server(fastapi.path(), 8000).is_err());
+        fs::create_dir_all(fastapi.path().join("app")).unwrap();
+        fs::write(fastapi.path().join("app/main.py"), "app = None\n").unwrap();
+
+        let server = PythonRunner.dev_server(fastapi.path(), 8001).unwrap();
+        assert!(server
+            .command
+            .label()
+            .ends_with("-m uvicorn app.main:app --reload --port 8001"));
+        assert!(server
+            .command
+            .env
+            .contains(&("PORT".to_string(), "8001".to_string())));
+
+        // Without a virtualenv, install creates one first.
+        let steps = PythonRunner.install(fastapi.path());
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].args, ["-m", "venv", ".venv"]);
+        assert!(steps[1].program.contains(".venv"));
+        assert_eq!(steps[1].args, ["-m", "pip", "install", "-e", "."]);
+
+        let php = tempdir().unwrap();
+        fs::create_dir_all(php.path().join("public")).unwrap();
+        fs::write(php.path().join("public/index.php"), "<?php echo 1;").unwrap();
+        assert!(PhpRunner.install(php.path()).is_empty());
+        assert_eq!(
+            PhpRunner
+                .dev_server(php.path(), 8080)
+                .unwrap()
+                .command
+                .label(),
+            "php -S 127.0.0.1:8080 -t public"
+        );
+    }
+}
@@ -0,0 +1,1031 @@
+// src-tauri/src/preview/static_server.rs
+//
+// Request handling for the static preview server: one request per
+// connection. Supports conditional requests (ETag / If-None-Match), single
+// byte ranges for media seeking, gzip/brotli for text assets, and streams
+// files from disk instead of loading them whole. Paths matching a proxy rule
+// are handed to `proxy` instead. Each request is described by a
+// `RequestRecord` for the preview's request log.
+
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom, Write},
+    net::{IpAddr, TcpStream},
+    path::{Component, Path, PathBuf},
+    time::{Duration, Instant, UNIX_EPOCH},
+};
+
+use flate2::{write::GzEncoder, Compression};
+
+use super::live_reload::{self, LiveReload, LIVE_RELOAD_PATH};
+use super::mime::{self, content_type_for, is_compressible};
+use super::proxy::{self, ProxyRule};
+use super::request_log::RequestRecord;
+use crate::project_files::is_env_file;
+
+const MAX_REQUEST_HEAD_BYTES: usize = 64 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Text assets between these sizes are compressed in memory; anything else
+/// is streamed as-is.
+const MIN_COMPRESS_BYTES: u64 = 1024;
+const MAX_COMPRESS_BYTES: u64 = 8 * 1024 * 1024;
+const BROTLI_QUALITY: u32 = 5;
+const BROTLI_WINDOW: u32 = 22;
+
+struct Request {
+    method: String,
+    target: String,
+    headers: Vec<(String, String)>,
+    /// Bytes that arrived after the head (the start of a body).
+    body_start: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+enum RequestError {
+    TooLarge,
+    Malformed,
+    Io(io::Error),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Encoding {
+    Brotli,
+    Gzip,
+}
+
+impl Encoding {
+    fn token(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum RangeRequest {
+    /// Inclusive byte range within the file.
+    Satisfiable {
+        start: u64,
+        end: u64,
+    },
+    Unsatisfiable,
+    /// Absent, multi-range or unparseable: serve the whole file.
+    Ignored,
+}
+
+enum Body {
+    Bytes(Vec<u8>),
+    File { file: File, start: u64, len: u64 },
+}
+
+/// Read the request head, which may arrive across several reads.
+fn read_request(stream: &mut TcpStream) -> Result<Option<Request>, RequestError> {
+    let mut head = Vec::new();
+    let mut chunk = [0_u8; 4096];
+
+    let end = loop {
+        let read = stream.read(&mut chunk).map_err(RequestError::Io)?;
+        if read == 0 {
+            if head.is_empty() {
+                return Ok(None);
+            }
+            return Err(RequestError::Malformed);
+        }
+        head.extend_from_slice(&chunk[..read]);
+
+        if let Some(end) = find_head_end(&head) {
+            break end;
+        }
+        if head.len() > MAX_REQUEST_HEAD_BYTES {
+            return Err(RequestError::TooLarge);
+        }
+    };
+
+    let body_offset = if head[end..].starts_with(b"\r\n\r\n") {
+        end + 4
+    } else {
+        end + 2
+    };
+    let text = String::from_utf8_lossy(&head[..end]);
+    let mut lines = text.lines();
+    let mut parts = lines.next().unwrap_or("").split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(RequestError::Malformed);
+    };
+
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+
+    Ok(Some(Request {
+        method: method.to_string(),
+        target: target.to_string(),
+        headers,
+        body_start: head[body_offset..].to_vec(),
+    }))
+}
+
+fn find_head_end(buffer: &[u8]) -> Option<usize> {
+    buffer
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .or_else(|| buffer.windows(2).position(|window| window == b"\n\n"))
+}
+
+fn decode_url_path(path: &str) -> String {
+    let mut out = Vec::with_capacity(path.len());
+    let bytes = path.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let h1 = bytes[i + 1];
+            let h2 = bytes[i + 2];
+
+            let v1 = (h1 as char).to_digit(16);
+            let v2 = (h2 as char).to_digit(16);
+
+            if let (Some(a), Some(b)) = (v1, v2) {
+                out.push(((a * 16 + b) as u8) as char);
+                i += 3;
+                continue;
+            }
+        }
+
+        if bytes[i] == b'+' {
+            out.push(' ');
+        } else {
+            out.push(bytes[i] as char);
+        }
+
+        i += 1;
+    }
+
+    out.into_iter().collect()
+}
+
+fn safe_join(root: &Path, request_path: &str) -> PathBuf {
+    let clean = request_path.trim_start_matches('/');
+    let decoded = decode_url_path(clean);
+
+    let mut joined = PathBuf::from(root);
+    for component in Path::new(&decoded).components() {
+        match component {
+            Component::Normal(part) => joined.push(part),
+            Component::CurDir => {}
+            Component::RootDir | Component::ParentDir | Component::Prefix(_) => {}
+        }
+    }
+
+    joined
+}
+
+/// Brotli when offered, else gzip. Codings listed with `q=0` are refused.
+fn preferred_encoding(accept_encoding: &str) -> Option<Encoding> {
+    let mut brotli = false;
+    let mut gzip = false;
+
+    for offer in accept_encoding.split(',') {
+        let mut params = offer.split(';');
+        let name = params.next().unwrap_or("").trim().to_ascii_lowercase();
+        let refused = params.any(|param| {
+            param
+                .trim()
+                .strip_prefix("q=")
+                .and_then(|q| q.trim().parse::<f32>().ok())
+                .is_some_and(|q| q <= 0.0)
+        });
+        if refused {
+            continue;
+        }
+        match name.as_str() {
+            "br" => brotli = true,
+            "gzip" | "x-gzip" => gzip = true,
+            _ => {}
+        }
+    }
+
+    if brotli {
+        Some(Encoding::Brotli)
+    } else if gzip {
+        Some(Encoding::Gzip)
+    } else {
+        None
+    }
+}
+
+fn compress(data: &[u8], encoding: Encoding) -> io::Result<Vec<u8>> {
+    match encoding {
+        Encoding::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data)?;
+            encoder.finish()
+        }
+        Encoding::Brotli => {
+            let mut encoder =
+                brotli::CompressorWriter::new(Vec::new(), 4096, BROTLI_QUALITY, BROTLI_WINDOW);
+            encoder.write_all(data)?;
+            encoder.flush()?;
+            Ok(encoder.into_inner())
+        }
+    }
+}
+
+/// `bytes=0-99`, `bytes=100-` or `bytes=-100` against a file of `len` bytes.
+fn parse_range(header: &str, len: u64) -> RangeRequest {
+    let Some(spec) = header.trim().strip_prefix("bytes=") else {
+        return RangeRequest::Ignored;
+    };
+    if spec.contains(',') {
+        return RangeRequest::Ignored;
+    }
+    let Some((first, last)) = spec.trim().split_once('-') else {
+        return RangeRequest::Ignored;
+    };
+
+    let (start, end) = match (first.trim(), last.trim()) {
+        ("", "") => return RangeRequest::Ignored,
+        ("", suffix) => match suffix.parse::<u64>() {
+            Ok(0) => return RangeRequest::Unsatisfiable,
+            Ok(suffix) => (len.saturating_sub(suffix), len.saturating_sub(1)),
+            Err(_) => return RangeRequest::Ignored,
+        },
+        (start, "") => match start.parse::<u64>() {
+            Ok(start) => (start, len.saturating_sub(1)),
+            Err(_) => return RangeRequest::Ignored,
+        },
+        (start, end) => match (start.parse::<u64>(), end.parse::<u64>()) {
+            (Ok(start), Ok(end)) if start <= end => (start, end.min(len.saturating_sub(1))),
+            _ => return RangeRequest::Ignored,
+        },
+    };
+
+    if len == 0 || start >= len {
+        return RangeRequest::Unsatisfiable;
+    }
+    RangeRequest::Satisfiable { start, end }
+}
+
+/// True when any entity tag in an If-None-Match list matches (weak comparison).
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let bare = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    if_none_match.trim() == "*" || if_none_match.split(',').any(|tag| bare(tag) == bare(etag))
+}
+
+fn write_head(stream: &mut impl Write, status: &str, headers: &[(&str, String)]) -> io::Result<()> {
+    let mut head = format!("HTTP/1.1 {}\r\n", status);
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("Connection: close\r\n\r\n");
+    stream.write_all(head.as_bytes())
+}
+
+fn write_simple(
+    stream: &mut impl Write,
+    status: &str,
+    extra: &[(&str, String)],
+    body: &[u8],
+) -> io::Result<()> {
+    let mut headers = vec![
+        ("Content-Type", "text/plain; charset=utf-8".to_string()),
+        ("Content-Length", body.len().to_string()),
+        ("Cache-Control", "no-store".to_string()),
+    ];
+    headers.extend(extra.iter().cloned());
+    write_head(stream, status, &headers)?;
+    stream.write_all(body)?;
+    stream.flush()
+}
+
+/// `write_simple`, noting the status and body size in `record`.
+fn reply(
+    stream: &mut impl Write,
+    record: &mut RequestRecord,
+    status: &str,
+    extra: &[(&str, String)],
+    body: &[u8],
+) -> io::Result<()> {
+    record.status = status_code(status);
+    record.bytes = body.len() as u64;
+    write_simple(stream, status, extra, body)
+}
+
+fn status_code(status: &str) -> Option<u16> {
+    status.split(' ').next()?.parse().ok()
+}
+
+/// Whether a missing path should fall back to `index.html` for client-side
+/// routing. Asset requests (scripts, images, source maps, ...) never do, so a
+/// broken reference shows up as a real 404.
+fn is_navigation(request: &Request, target: &str) -> bool {
+    let name = target.rsplit('/').next().unwrap_or("");
+    let extension = name.rsplit_once('.').map(|(_, ext)| ext).unwrap_or("");
+    if let Some(content_type) = mime::content_type_for_extension(extension) {
+        if !content_type.starts_with("text/html") {
+            return false;
+        }
+    }
+
+    if let Some(mode) = request.header("Sec-Fetch-Mode") {
+        return mode.eq_ignore_ascii_case("navigate");
+    }
+    match request.header("Accept") {
+        Some(accept) => accept.contains("text/html"),
+        None => true,
+    }
+}
+
+/// `.env` files and anything under a dot-folder or dot-file (`.git/config`,
+/// `.npmrc`), which previews shared on the network never serve.
+/// `.well-known` is public by design.
+fn is_private(root: &Path, path: &Path) -> bool {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative.components().any(|component| {
+        let name = component.as_os_str().to_string_lossy();
+        (name.starts_with('.') && name != ".well-known") || is_env_file(&name)
+    })
+}
+
+/// The file answering `target`, and whether it is the `index.html` fallback.
+/// With `hide_private`, private files answer as if they did not exist.
+fn resolve_file(
+    root: &Path,
+    target: &str,
+    navigation: bool,
+    hide_private: bool,
+) -> Option<(PathBuf, bool)> {
+    let mut file_path = if target == "/" || target.is_empty() {
+        root.join("index.html")
+    } else {
+        safe_join(root, target)
+    };
+
+    if file_path.is_dir() {
+        file_path = file_path.join("index.html");
+    }
+
+    if hide_private && is_private(root, &file_path) {
+        return None;
+    }
+    if file_path.is_file() {
+        return Some((file_path, false));
+    }
+    if !navigation {
+        return None;
+    }
+
+    let fallback = root.join("index.html");
+    fallback.is_file().then_some((fallback, true))
+}
+
+fn forward(
+    mut stream: TcpStream,
+    client: Client,
+    rule: &ProxyRule,
+    request: &Request,
+    record: &mut RequestRecord,
+) -> io::Result<()> {
+    record.handler = "proxy";
+    let upstream = match rule.connect() {
+        Ok(upstream) => upstream,
+        Err(err) => {
+            let message = format!(
+                "Proxy target {} is not reachable: {}",
+                rule.target_url(),
+                err
+            );
+            return reply(
+                &mut stream,
+                record,
+                "502 Bad Gateway",
+                &[],
+                message.as_bytes(),
+            );
+        }
+    };
+
+    let head = rule.request_head(
+        &request.method,
+        &request.target,
+        &request.headers,
+        client.scheme(),
+        client.address,
+    );
+    proxy::relay(stream, upstream, &head, &request.body_start)
+}
+
+/// Where a connection came from, as the preview's listener accepted it.
+/// Behind the HTTPS relay the stream itself only shows a loopback peer.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Client {
+    pub address: Option<IpAddr>,
+    pub https: bool,
+}
+
+impl Client {
+    fn scheme(&self) -> &'static str {
+        if self.https {
+            "https"
+        } else {
+            "http"
+        }
+    }
+}
+
+/// Serve one connection, then pass what happened to `on_request` (also
+/// when writing the response failed part way). `shared_on_lan` hides
+/// `.env` files and dot-folders.
+pub fn handle_connection(
+    stream: TcpStream,
+    client: Client,
+    root: &Path,
+    live_reload: Option<&LiveReload>,
+    proxy_rules: &[ProxyRule],
+    shared_on_lan: bool,
+    on_request: impl FnOnce(RequestRecord),
+) -> io::Result<()> {
+    let started = Instant::now();
+    let mut record = RequestRecord::default();
+    let result = respond(
+        stream,
+        client,
+        root,
+        live_reload,
+        proxy_rules,
+        shared_on_lan,
+        &mut record,
+    );
+
+    // Nothing was asked before the client went away.
+    if !record.handler.is_empty() {
+        record.duration_ms = started.elapsed().as_millis() as u64;
+        on_request(record);
+    }
+    result
+}
+
+fn respond(
+    mut stream: TcpStream,
+    client: Client,
+    root: &Path,
+    live_reload: Option<&LiveReload>,
+    proxy_rules: &[ProxyRule],
+    shared_on_lan: bool,
+    record: &mut RequestRecord,
+) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    record.client = client.address.map(|address| address.to_string());
+
+    let request = match read_request(&mut stream) {
+        Ok(Some(request)) => request,
+        Ok(None) => return Ok(()),
+        Err(RequestError::TooLarge) => {
+            record.handler = "invalid";
+            return reply(
+                &mut stream,
+                record,
+                "431 Request Header Fields Too Large",
+                &[],
+                b"Request Header Fields Too Large",
+            );
+        }
+        Err(RequestError::Malformed) => {
+            record.handler = "invalid";
+            return reply(&mut stream, record, "400 Bad Request", &[], b"Bad Request");
+        }
+        Err(RequestError::Io(err)) => return Err(err),
+    };
+
+    let method = request.method.as_str();
+    let target = request.target.split('?').next().unwrap_or("/");
+    record.method = request.method.clone();
+    record.path = request.target.clone();
+    record.handler = "file";
+
+    if target == LIVE_RELOAD_PATH && method == "GET" {
+        if let Some(live_reload) = live_reload {
+            record.handler = "live-reload";
+            return live_reload.attach(stream);
+        }
+    }
+
+    if let Some(rule) = proxy::find_rule(proxy_rules, target) {
+        return forward(stream, client, rule, &request, record);
+    }
+
+    if method != "GET" && method != "HEAD" {
+        return reply(
+            &mut stream,
+            record,
+            "405 Method Not Allowed",
+            &[("Allow", "GET, HEAD".to_string())],
+            b"Method Not Allowed",
+        );
+    }
+
+    let navigation = is_navigation(&request, target);
+    let Some((file_path, fallback)) = resolve_file(root, target, navigation, shared_on_lan) else {
+        return reply(&mut stream, record, "404 Not Found", &[], b"Not Found");
+    };
+    record.fallback = fallback;
+    record.file = Some(
+        file_path
+            .strip_prefix(root)
+            .unwrap_or(&file_path)
+            .to_string_lossy()
+            .replace('\\', "/"),
+    );
+
+    let metadata = fs::metadata(&file_path)?;
+    let len = metadata.len();
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_nanos())
+        .unwrap_or(0);
+
+    let content_type = content_type_for(&file_path);
+    let inject = live_reload.is_some() && content_type.starts_with("text/html");
+    let compressible = is_compressible(content_type);
+    let wants_range = request.header("Range").is_some();
+
+    let encoding =
+        if compressible && !wants_range && (MIN_COMPRESS_BYTES..=MAX_COMPRESS_BYTES).contains(&len)
+        {
+            request
+                .header("Accept-Encoding")
+                .and_then(preferred_encoding)
+        } else {
+            None
+        };
+
+    // Each representation (injected, compressed) gets its own tag.
+    let etag = format!(
+        "\"{:x}-{:x}{}{}\"",
+        len,
+        modified,
+        if inject { "-lr" } else { "" },
+        encoding
+            .map(|e| format!("-{}", e.token()))
+            .unwrap_or_default()
+    );
+
+    let mut headers = vec![
+        ("Content-Type", content_type.to_string()),
+        // Revalidate every time so edits show up, but let unchanged files 304.
+        ("Cache-Control", "no-cache".to_string()),
+        ("ETag", etag.clone()),
+    ];
+    if compressible {
+        headers.push(("Vary", "Accept-Encoding".to_string()));
+    }
+    if !inject && encoding.is_none() {
+        headers.push(("Accept-Ranges", "bytes".to_string()));
+    }
+
+    if request
+        .header("If-None-Match")
+        .is_some_and(|value| etag_matches(value, &etag))
+    {
+        record.status = Some(304);
+        write_head(&mut stream, "304 Not Modified", &headers)?;
+        return stream.flush();
+    }
+
+    let mut status = "200 OK";
+    let body = if inject || encoding.is_some() {
+        let mut data = fs::read(&file_path)?;
+        if inject {
+            data = live_reload::inject_script(&data);
+        }
+        if let Some(encoding) = encoding {
+            data = compress(&data, encoding)?;
+            headers.push(("Content-Encoding", encoding.token().to_string()));
+        }
+        Body::Bytes(data)
+    } else {
+        let range_applies = request
+            .header("If-Range")
+            .is_none_or(|if_range| if_range.trim() == etag);
+        let range = match request.header("Range") {
+            Some(range) if range_applies => parse_range(range, len),
+            _ => RangeRequest::Ignored,
+        };
+
+        match range {
+            RangeRequest::Satisfiable { start, end } => {
+                status = "206 Partial Content";
+                headers.push(("Content-Range", format!("bytes {}-{}/{}", start, end, len)));
+                Body::File {
+                    file: File::open(&file_path)?,
+                    start,
+                    len: end - start + 1,
+                }
+            }
+            RangeRequest::Unsatisfiable => {
+                return reply(
+                    &mut stream,
+                    record,
+                    "416 Range Not Satisfiable",
+                    &[("Content-Range", format!("bytes */{}", len))],
+                    b"Range Not Satisfiable",
+                );
+            }
+            RangeRequest::Ignored => Body::File {
+                file: File::open(&file_path)?,
+                start: 0,
+                len,
+            },
+        }
+    };
+
+    let body_len = match &body {
+        Body::Bytes(data) => data.len() as u64,
+        Body::File { len, .. } => *len,
+    };
+    headers.push(("Content-Length", body_len.to_string()));
+    record.status = status_code(status);
+    write_head(&mut stream, status, &headers)?;
+
+    if method == "GET" {
+        record.bytes = body_len;
+        match body {
+            Body::Bytes(data) => stream.write_all(&data)?,
+            Body::File {
+                mut file,
+                start,
+                len,
+            } => {
+                file.seek(SeekFrom::Start(start))?;
+                io::copy(&mut file.take(len), &mut stream)?;
+            }
+        }
+    }
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        thread,
+        time::Duration,
+    };
+
+    use tempfile::tempdir;
+
+    use super::super::proxy::{ProxyRule, ProxyRuleConfig};
+    use super::{etag_matches, handle_connection, parse_range, preferred_encoding, resolve_file};
+    use super::{Client, Encoding, RangeRequest};
+
+    #[test]
+    fn parses_single_byte_ranges() {
+        assert_eq!(
+            parse_range("bytes=0-99", 1000),
+            RangeRequest::Satisfiable { start: 0, end: 99 }
+        );
+        assert_eq!(
+            parse_range("bytes=900-", 1000),
+            RangeRequest::Satisfiable {
+                start: 900,
+                end: 999
+            }
+        );
+        assert_eq!(
+            parse_range("bytes=-100", 1000),
+            RangeRequest::Satisfiable {
+                start: 900,
+                end: 999
+            }
+        );
+        assert_eq!(
+            parse_range("bytes=500-5000", 1000),
+            RangeRequest::Satisfiable {
+                start: 500,
+                end: 999
+            }
+        );
+        assert_eq!(
+            parse_range("bytes=1000-", 1000),
+            RangeRequest::Unsatisfiable
+        );
+        assert_eq!(parse_range("bytes=0-1,5-9", 1000), RangeRequest::Ignored);
+        assert_eq!(parse_range("items=0-1", 1000), RangeRequest::Ignored);
+    }
+
+    #[test]
+    fn negotiates_encodings_and_matches_etags() {
+        assert_eq!(
+            preferred_encoding("gzip, deflate, br"),
+            Some(Encoding::Brotli)
+        );
+        assert_eq!(preferred_encoding("gzip, br;q=0"), Some(Encoding::Gzip));
+        assert_eq!(preferred_encoding("identity"), None);
+
+        assert!(etag_matches("\"a\", W/\"b\"", "\"b\""));
+        assert!(etag_matches("*", "\"b\""));
+        assert!(!etag_matches("\"a\"", "\"b\""));
+    }
+
+    #[test]
+    fn hides_env_files_and_dot_folders_when_shared_on_lan() {
+        let site = tempdir().unwrap();
+        let root = site.path();
+        fs::write(root.join("index.html"), "<p>home</p>").unwrap();
+        fs::create_dir_all(root.join("a/b/c/d/e")).unwrap();
+        fs::write(root.join("a/b/c/d/e/.env.local"), "KEY=secret").unwrap();
+        fs::write(root.join("a/prod.env"), "KEY=secret").unwrap();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::write(root.join(".git/config"), "[remote]").unwrap();
+        fs::create_dir_all(root.join(".well-known")).unwrap();
+        fs::write(root.join(".well-known/security.txt"), "Contact: x").unwrap();
+
+        for target in [
+            "/a/b/c/d/e/.env.local",
+            "/a/prod.env",
+            "/.git/config",
+            "/%2Egit/config",
+        ] {
+            assert!(resolve_file(root, target, true, true).is_none(), "{target}");
+            assert!(
+                resolve_file(root, target, false, false).is_some(),
+                "{target}"
+            );
+        }
+        assert!(resolve_file(root, "/.well-known/security.txt", false, true).is_some());
+        assert!(resolve_file(root, "/", true, true).is_some());
+    }
+
+    fn exchange_bytes(root: &std::path::Path, request_parts: &[&str]) -> Vec<u8> {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let address = listener.local_addr().unwrap();
+        let root = root.to_path_buf();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            handle_connection(stream, Client::default(), &root, None, &[], false, |_| {}).unwrap();
+        });
+
+        let mut client = TcpStream::connect(address).unwrap();
+        for part in request_parts {
+            client.write_all(part.as_bytes()).unwrap();
+            client.flush().unwrap();
+            thread::sleep(Duration::from_millis(20));
+        }
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).unwrap();
+        server.join().unwrap();
+        response
+    }
+
+    fn exchange(root: &std::path::Path, request_parts: &[&str]) -> String {
+        String::from_utf8_lossy(&exchange_bytes(root, request_parts)).to_string()
+    }
+
+    #[test]
+    fn serves_ranges_from_heads_split_across_reads_and_revalidates() {
+        let site = tempdir().unwrap();
+        fs::write(site.path().join("index.html"), "<p>home</p>").unwrap();
+        fs::write(site.path().join("clip.mp4"), b"0123456789").unwrap();
+
+        let response = exchange(
+            site.path(),
+            &[
+                "GET /clip.mp4 HTTP/1.1\r\nHost: x\r\n",
+                "Range: bytes=2-5\r\n\r\n",
+            ],
+        );
+        assert!(response.starts_with("HTTP/1.1 206 Partial Content\r\n"));
+        assert!(response.contains("Content-Range: bytes 2-5/10\r\n"));
+        assert!(response.ends_with("\r\n\r\n2345"));
+
+        let etag = response
+            .lines()
+            .find_map(|line| line.strip_prefix("ETag: "))
+            .unwrap()
+            .to_string();
+        let revalidated = exchange(
+            site.path(),
+            &[&format!(
+                "GET /clip.mp4 HTTP/1.1\r\nIf-None-Match: {}\r\n\r\n",
+                etag
+            )],
+        );
+        assert!(revalidated.starts_with("HTTP/1.1 304 Not Modified\r\n"));
+        assert!(revalidated.ends_with("\r\n\r\n"));
+    }
+
+    #[test]
+    fn compresses_text_assets_for_clients_that_accept_it() {
+        let site = tempdir().unwrap();
+        let css = "body { color: red; }\n".repeat(200);
+        fs::write(site.path().join("site.css"), &css).unwrap();
+
+        let response = exchange_bytes(
+            site.path(),
+            &["GET /site.css HTTP/1.1\r\nAccept-Encoding: gzip\r\n\r\n"],
+        );
+        let split = response.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        let head = String::from_utf8_lossy(&response[..split]).to_string();
+        assert!(head.contains("Content-Encoding: gzip"));
+        assert!(head.contains("Vary: Accept-Encoding"));
+
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(&response[split + 4..])
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, css);
+    }
+
+    #[test]
+    fn falls_back_to_index_only_for_navigation_requests() {
+        let site = tempdir().unwrap();
+        fs::write(site.path().join("index.html"), "<p>app shell</p>").unwrap();
+
+        let route = exchange(
+            site.path(),
+            &["GET /dashboard/settings HTTP/1.1\r\nAccept: text/html,*/*\r\n\r\n"],
+        );
+        assert!(route.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(route.ends_with("<p>app shell</p>"));
+
+        let script = exchange(
+            site.path(),
+            &["GET /assets/missing.js HTTP/1.1\r\nAccept: */*\r\n\r\n"],
+        );
+        assert!(script.starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+        // Typing an asset URL into the address bar still gets a real 404.
+        let typed = exchange(
+            site.path(),
+            &["GET /app.js.map HTTP/1.1\r\nSec-Fetch-Mode: navigate\r\n\r\n"],
+        );
+        assert!(typed.starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+        let fetch = exchange(
+            site.path(),
+            &["GET /api/users HTTP/1.1\r\nSec-Fetch-Mode: cors\r\nAccept: text/html\r\n\r\n"],
+        );
+        assert!(fetch.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+
+    fn read_head(stream: &mut TcpStream) -> String {
+        let mut head = Vec::new();
+        let mut byte = [0_u8; 1];
+        while !head.ends_with(b"\r\n\r\n") {
+            stream.read_exact(&mut byte).unwrap();
+            head.push(byte[0]);
+        }
+        String::from_utf8(head).unwrap()
+    }
+
+    #[test]
+    fn proxies_requests_and_websocket_upgrades() {
+        let backend = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let backend_port = backend.local_addr().unwrap().port();
+        let upstream = thread::spawn(move || {
+            // A POST with a body, answered with what arrived.
+            let (mut stream, _) = backend.accept().unwrap();
+            let head = read_head(&mut stream);
+            let mut body = [0_u8; 7];
+            stream.read_exact(&mut body).unwrap();
+            let echo = format!(
+                "{}|{}",
+                head.lines().next().unwrap(),
+                String::from_utf8_lossy(&body)
+            );
+            assert!(head.contains(&format!("Host: 127.0.0.1:{}\r\n", backend_port)));
+            write!(
+                stream,
+                "HTTP/1.1 201 Created\r\nContent-Length: {}\r\n\r\n{}",
+                echo.len(),
+                echo
+            )
+            .unwrap();
+            drop(stream);
+
+            // A WebSocket upgrade that then exchanges raw frames.
+            let (mut stream, _) = backend.accept().unwrap();
+            let head = read_head(&mut stream);
+            assert!(head.contains("Connection: Upgrade\r\n"), "{head}");
+            stream
+                .write_all(b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\r\n")
+                .unwrap();
+            let mut frame = [0_u8; 4];
+            stream.read_exact(&mut frame).unwrap();
+            assert_eq!(&frame, b"ping");
+            stream.write_all(b"pong").unwrap();
+        });
+
+        let site = tempdir().unwrap();
+        let rules = vec![ProxyRule::from_config(&ProxyRuleConfig {
+            path: "/api/*".to_string(),
+            target: format!("http://127.0.0.1:{}", backend_port),
+            strip_prefix: false,
+        })
+        .unwrap()];
+        let preview = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let address = preview.local_addr().unwrap();
+        let root = site.path().to_path_buf();
+        let server = thread::spawn(move || {
+            for _ in 0..2 {
+                let (stream, _) = preview.accept().unwrap();
+                handle_connection(
+                    stream,
+                    Client::default(),
+                    &root,
+                    None,
+                    &rules,
+                    false,
+                    |_| {},
+                )
+                .unwrap();
+            }
+        });
+
+        let mut client = TcpStream::connect(address).unwrap();
+        client
+            .write_all(b"POST /api/items?x=1 HTTP/1.1\r\nHost: preview\r\nContent-Length: 7\r\n\r\n{\"a\":1}")
+            .unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert!(
+            response.starts_with("HTTP/1.1 201 Created\r\n"),
+            "{response}"
+        );
+        assert!(response.ends_with("POST /api/items?x=1 HTTP/1.1|{\"a\":1}"));
+
+        let mut socket = TcpStream::connect(address).unwrap();
+        socket
+            .write_all(b"GET /api/socket HTTP/1.1\r\nHost: preview\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\r\n")
+            .unwrap();
+        assert!(read_head(&mut socket).starts_with("HTTP/1.1 101 "));
+        socket.write_all(b"ping").unwrap();
+        let mut reply = [0_u8; 4];
+        socket.read_exact(&mut reply).unwrap();
+        assert_eq!(&reply, b"pong");
+        drop(socket);
+
+        upstream.join().unwrap();
+        server.join().unwrap();
+
+        let closed_port = TcpListener::bind(("127.0.0.1", 0))
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let unreachable = vec![ProxyRule::from_config(&ProxyRuleConfig {
+            path: "/api".to_string(),
+            target: format!("http://127.0.0.1:{}", closed_port),
+            strip_prefix: false,
+        })
+        .unwrap()];
+        let preview = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let address = preview.local_addr().unwrap();
+        let root = site.path().to_path_buf();
+        let server = thread::spawn(move || {
+            let (stream, _) = preview.accept().unwrap();
+            let mut recorded = None;
+            handle_connection(
+                stream,
+                Client::default(),
+                &root,
+                None,
+                &unreachable,
+                false,
+                |record| recorded = Some(record),
+            )
+            .unwrap();
+            recorded.unwrap()
+        });
+        let mut client = TcpStream::connect(address).unwrap();
+        client
+            .write_all(b"DELETE /api/items/1 HTTP/1.1\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert!(
+            response.starts_with("HTTP/1.1 502 Bad Gateway\r\n"),
+            "{response}"
+        );
+        let record = server.join().unwrap();
+        assert_eq!(record.method, "DELETE");
+        assert_eq!(record.path, "/api/items/1");
+        assert_eq!(record.handler, "proxy");
+        assert_eq!(record.status, Some(502));
+    }
+}
@@ -0,0 +1,485 @@
+// src-tauri/src/preview/supervisor.rs
+//
+// Optional supervision for dev servers. An unexpected exit is followed by a
+// restart after an exponential backoff; a server that stops answering its
+// URL is restarted the same way. Once the restart budget is spent the
+// preview is given up with a crash-loop report: the recent exits and the
+// last stderr lines of the final run.
+
+use std::{
+    collections::VecDeque,
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::{
+    emit_log, emit_status, finish_preview, ready, start_process, CapturedOutput, Launch,
+    PreviewHandle, PreviewState,
+};
+use crate::{ansi::strip_ansi_sequences, process_tree::STOP_GRACE_PERIOD};
+
+const DEFAULT_MAX_RESTARTS: u32 = 5;
+const DEFAULT_HEALTH_CHECK_SECS: u64 = 10;
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// A run at least this long starts the restart budget afresh.
+const STABLE_UPTIME: Duration = Duration::from_secs(120);
+/// Consecutive failed health checks before the server is restarted.
+const UNHEALTHY_AFTER: u32 = 3;
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(5);
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(200);
+const CRASH_REPORT_STDERR_LINES: usize = 40;
+const MAX_EXIT_HISTORY: usize = 10;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SupervisorConfig {
+    /// Restarts allowed before giving up (default 5).
+    pub max_restarts: Option<u32>,
+    /// Seconds between health checks; 0 turns them off (default 10).
+    pub health_check_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExitRecord {
+    pub exit_code: Option<i32>,
+    /// "crashed" or "unhealthy".
+    pub reason: &'static str,
+    pub uptime_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashLoopReport {
+    pub summary: String,
+    pub restarts: u32,
+    pub exits: Vec<ExitRecord>,
+    pub last_stderr: Vec<String>,
+}
+
+pub struct Supervisor {
+    max_restarts: u32,
+    health_interval: Option<Duration>,
+    restarts: AtomicU32,
+    /// Set when the health check killed the server, so its exit counts as a
+    /// failure whatever the exit code.
+    unhealthy_kill: AtomicBool,
+    exits: Mutex<VecDeque<ExitRecord>>,
+    stderr: CapturedOutput,
+}
+
+impl Supervisor {
+    pub fn new(config: &SupervisorConfig) -> Self {
+        let health_secs = config
+            .health_check_secs
+            .unwrap_or(DEFAULT_HEALTH_CHECK_SECS);
+        Self {
+            max_restarts: config.max_restarts.unwrap_or(DEFAULT_MAX_RESTARTS),
+            health_interval: (health_secs > 0).then(|| Duration::from_secs(health_secs)),
+            restarts: AtomicU32::new(0),
+            unhealthy_kill: AtomicBool::new(false),
+            exits: Mutex::new(VecDeque::new()),
+            stderr: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    pub fn max_restarts(&self) -> u32 {
+        self.max_restarts
+    }
+
+    pub fn stderr(&self) -> CapturedOutput {
+        Arc::clone(&self.stderr)
+    }
+
+    pub fn clear_stderr(&self) {
+        if let Ok(mut stderr) = self.stderr.lock() {
+            stderr.clear();
+        }
+    }
+
+    pub fn take_unhealthy(&self) -> bool {
+        self.unhealthy_kill.swap(false, Ordering::SeqCst)
+    }
+
+    fn record_exit(&self, exit: ExitRecord) {
+        if let Ok(mut exits) = self.exits.lock() {
+            if exits.len() == MAX_EXIT_HISTORY {
+                exits.pop_front();
+            }
+            exits.push_back(exit);
+        }
+    }
+
+    fn crash_report(&self, restarts: u32) -> CrashLoopReport {
+        let exits: Vec<ExitRecord> = self
+            .exits
+            .lock()
+            .map(|exits| exits.iter().cloned().collect())
+            .unwrap_or_default();
+        let last_stderr: Vec<String> = self
+            .stderr
+            .lock()
+            .map(|stderr| {
+                let start = stderr.len().saturating_sub(CRASH_REPORT_STDERR_LINES);
+                stderr
+                    .iter()
+                    .skip(start)
+                    .map(|line| strip_ansi_sequences(line))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let last = match exits.last() {
+            Some(exit) if exit.reason == "unhealthy" => "it stopped responding".to_string(),
+            Some(ExitRecord {
+                exit_code: Some(code),
+                ..
+            }) => format!("last exit code {}", code),
+            _ => "it was killed".to_string(),
+        };
+        CrashLoopReport {
+            summary: format!(
+                "Dev server kept crashing; gave up after {} restart{} ({}).",
+                restarts,
+                if restarts == 1 { "" } else { "s" },
+                last
+            ),
+            restarts,
+            exits,
+            last_stderr,
+        }
+    }
+}
+
+/// Delay before restart number `attempt` (1-based): 1s, 2s, 4s, ... 30s.
+pub fn backoff(attempt: u32) -> Duration {
+    let doubled = INITIAL_BACKOFF.saturating_mul(1 << attempt.saturating_sub(1).min(16));
+    doubled.min(MAX_BACKOFF)
+}
+
+fn parse_status_line(line: &str) -> Option<u16> {
+    let mut parts = line.split_whitespace();
+    let version = parts.next()?;
+    if !version.starts_with("HTTP/") {
+        return None;
+    }
+    parts.next()?.parse().ok()
+}
+
+/// Probe `url` with a plain GET. Any HTTP response counts as alive (a 500
+/// from a compile error is still a running server). For https only the
+/// connection itself is checked, so the status is None.
+pub fn probe(url: &str) -> Result<Option<u16>, String> {
+    let (scheme, rest) = url
+        .split_once("://")
+        .ok_or_else(|| format!("Not a URL: {}", url))?;
+    let (authority, path) = match rest.find('/') {
+        Some(at) => (&rest[..at], &rest[at..]),
+        None => (rest, "/"),
+    };
+    let port = ready::port_from_url(url).ok_or_else(|| format!("No port in {}", url))?;
+    let host = match authority.rsplit_once(':') {
+        Some((host, _)) => host,
+        None => authority,
+    }
+    .trim_start_matches('[')
+    .trim_end_matches(']');
+
+    let addresses = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Could not resolve {}: {}", host, e))?;
+    let mut last_err = format!("Could not resolve {}", host);
+    let mut connected = None;
+    for address in addresses {
+        match TcpStream::connect_timeout(&address, HEALTH_TIMEOUT) {
+            Ok(stream) => {
+                connected = Some(stream);
+                break;
+            }
+            Err(err) => last_err = format!("Connection to {} failed: {}", authority, err),
+        }
+    }
+    let mut stream = connected.ok_or(last_err)?;
+    if scheme != "http" {
+        return Ok(None);
+    }
+
+    let io_err = |e: std::io::Error| format!("Health check of {} failed: {}", url, e);
+    stream
+        .set_read_timeout(Some(HEALTH_TIMEOUT))
+        .map_err(io_err)?;
+    stream
+        .set_write_timeout(Some(HEALTH_TIMEOUT))
+        .map_err(io_err)?;
+    write!(
+        stream,
+        "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: KForge-health-check\r\nAccept: */*\r\nConnection: close\r\n\r\n",
+        path, authority
+    )
+    .map_err(io_err)?;
+
+    let mut response = Vec::new();
+    let mut chunk = [0_u8; 256];
+    while !response.contains(&b'\n') && response.len() < 1024 {
+        let read = stream.read(&mut chunk).map_err(io_err)?;
+        if read == 0 {
+            break;
+        }
+        response.extend_from_slice(&chunk[..read]);
+    }
+    let text = String::from_utf8_lossy(&response);
+    parse_status_line(text.lines().next().unwrap_or(""))
+        .map(Some)
+        .ok_or_else(|| format!("{} did not answer with HTTP", url))
+}
+
+fn emit_health(app: &AppHandle, preview_id: &str, url: &str, result: &Result<Option<u16>, String>) {
+    let payload = serde_json::json!({
+      "previewId": preview_id,
+      "url": url,
+      "healthy": result.is_ok(),
+      "status": result.as_ref().ok().copied().flatten(),
+      "error": result.as_ref().err()
+    });
+    let _ = app.emit(&format!("kforge://preview/{}/health", preview_id), &payload);
+    let _ = app.emit("kforge://preview/health", payload);
+}
+
+/// Check the preview's URL every interval while it is running. Health
+/// events go out when the state changes; after `UNHEALTHY_AFTER` failures
+/// in a row the server is killed and the waiter restarts it.
+pub fn watch_health(app: AppHandle, preview_id: String, supervisor: Arc<Supervisor>) {
+    let Some(interval) = supervisor.health_interval else {
+        return;
+    };
+
+    thread::spawn(move || {
+        let mut failures = 0;
+        let mut last_healthy: Option<bool> = None;
+
+        loop {
+            thread::sleep(interval);
+            let Some(info) = app.state::<PreviewState>().update(&preview_id, |_| {}) else {
+                return;
+            };
+            let url = match info.url {
+                Some(url) if info.status == "running" && url.starts_with("http") => url,
+                // Starting, restarting or not serving a browsable URL.
+                _ => {
+                    failures = 0;
+                    last_healthy = None;
+                    continue;
+                }
+            };
+
+            let result = probe(&url);
+            if last_healthy != Some(result.is_ok()) {
+                emit_health(&app, &preview_id, &url, &result);
+                last_healthy = Some(result.is_ok());
+            }
+            let Err(err) = result else {
+                failures = 0;
+                continue;
+            };
+
+            failures += 1;
+            emit_log(
+                &app,
+                &preview_id,
+                "stderr",
+                &format!(
+                    "Health check failed ({}/{}): {}",
+                    failures, UNHEALTHY_AFTER, err
+                ),
+            );
+            if failures < UNHEALTHY_AFTER {
+                continue;
+            }
+
+            failures = 0;
+            last_healthy = None;
+            let mut tree = None;
+            app.state::<PreviewState>().update(&preview_id, |entry| {
+                if let PreviewHandle::Child { tree: child } = &entry.handle {
+                    tree = Some(Arc::clone(child));
+                }
+            });
+            if let Some(tree) = tree {
+                emit_log(
+                    &app,
+                    &preview_id,
+                    "status",
+                    "Dev server is not responding; restarting it.",
+                );
+                supervisor.unhealthy_kill.store(true, Ordering::SeqCst);
+                tree.terminate(STOP_GRACE_PERIOD);
+            }
+        }
+    });
+}
+
+fn report_crash_loop(app: &AppHandle, preview_id: &str, report: &CrashLoopReport) {
+    emit_log(app, preview_id, "stderr", &format!("❌ {}", report.summary));
+    let payload = serde_json::json!({
+      "previewId": preview_id,
+      "report": report
+    });
+    let _ = app.emit(
+        &format!("kforge://preview/{}/crash-loop", preview_id),
+        &payload,
+    );
+    let _ = app.emit("kforge://preview/crash-loop", payload);
+}
+
+/// Called from the waiter after an unexpected exit: restart after a backoff,
+/// or give up with a crash-loop report once the budget is spent. Returns
+/// early if the preview is stopped while waiting.
+pub fn restart_after_exit(
+    launch: Launch,
+    exit_code: Option<i32>,
+    uptime: Duration,
+    unhealthy: bool,
+) {
+    let app = &launch.app;
+    let preview_id = &launch.preview_id;
+    let Some(supervisor) = launch.supervisor.clone() else {
+        finish_preview(app, preview_id);
+        return;
+    };
+
+    if uptime >= STABLE_UPTIME {
+        supervisor.restarts.store(0, Ordering::SeqCst);
+    }
+    supervisor.record_exit(ExitRecord {
+        exit_code,
+        reason: if unhealthy { "unhealthy" } else { "crashed" },
+        uptime_ms: uptime.as_millis() as u64,
+    });
+
+    let attempt = supervisor.restarts.fetch_add(1, Ordering::SeqCst) + 1;
+    if attempt > supervisor.max_restarts {
+        report_crash_loop(app, preview_id, &supervisor.crash_report(attempt - 1));
+        finish_preview(app, preview_id);
+        return;
+    }
+
+    let still_running = app
+        .state::<PreviewState>()
+        .update(preview_id, |entry| {
+            entry.url = None;
+            entry.port = None;
+            entry.restarts = attempt;
+            entry.handle = PreviewHandle::Pending;
+        })
+        .is_some();
+    if !still_running {
+        return;
+    }
+
+    let delay = backoff(attempt);
+    emit_status(app, preview_id, "restarting");
+    emit_log(
+        app,
+        preview_id,
+        "status",
+        &format!(
+            "Restarting in {}s (attempt {} of {})…",
+            delay.as_secs(),
+            attempt,
+            supervisor.max_restarts
+        ),
+    );
+
+    let deadline = Instant::now() + delay;
+    loop {
+        let stopped = app
+            .state::<PreviewState>()
+            .update(preview_id, |_| {})
+            .is_none();
+        if stopped {
+            return;
+        }
+        if Instant::now() >= deadline {
+            break;
+        }
+        thread::sleep(STOP_POLL_INTERVAL);
+    }
+
+    if let Err(err) = start_process(&launch) {
+        emit_log(app, preview_id, "stderr", &err);
+        finish_preview(app, preview_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+        time::Duration,
+    };
+
+    use super::{backoff, probe, Supervisor, SupervisorConfig};
+
+    #[test]
+    fn backs_off_exponentially_up_to_a_cap() {
+        assert_eq!(backoff(1), Duration::from_secs(1));
+        assert_eq!(backoff(3), Duration::from_secs(4));
+        assert_eq!(backoff(6), Duration::from_secs(30));
+        assert_eq!(backoff(40), Duration::from_secs(30));
+
+        let supervisor = Supervisor::new(&SupervisorConfig {
+            max_restarts: Some(2),
+            health_check_secs: Some(0),
+        });
+        assert_eq!(supervisor.max_restarts(), 2);
+        assert!(supervisor.health_interval.is_none());
+        supervisor
+            .stderr()
+            .lock()
+            .unwrap()
+            .push_back("\u{1b}[31mError: listen EADDRINUSE\u{1b}[39m".to_string());
+        let report = supervisor.crash_report(2);
+        assert_eq!(report.last_stderr, vec!["Error: listen EADDRINUSE"]);
+        assert!(report.summary.contains("2 restarts"), "{}", report.summary);
+    }
+
+    #[test]
+    fn probes_urls_over_http() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0_u8; 512];
+            let read = stream.read(&mut request).unwrap();
+            assert!(String::from_utf8_lossy(&request[..read]).starts_with("GET /app HTTP/1.1\r\n"));
+            stream
+                .write_all(b"HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+        });
+
+        assert_eq!(
+            probe(&format!("http://127.0.0.1:{}/app", port)),
+            Ok(Some(500))
+        );
+        server.join().unwrap();
+
+        let closed = TcpListener::bind(("127.0.0.1", 0))
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        assert!(probe(&format!("http://127.0.0.1:{}/", closed)).is_err());
+    }
+}
@@ -0,0 +1,431 @@
+// src-tauri/src/preview/tls.rs
+//
+// HTTPS for the static preview. Service workers, WebAuthn, camera access
+// and secure cookies need a secure origin, which a LAN address over plain
+// HTTP is not. KForge keeps one self-signed certificate per machine in its
+// data folder; users trust it once and every preview reuses it.
+//
+// TLS is terminated per connection and the decrypted bytes are handed to
+// the ordinary request handler over a loopback socket, so file serving,
+// live reload and proxying work the same over both schemes.
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{self, Read, Write},
+    net::{Ipv4Addr, Shutdown, TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use rcgen::{CertificateParams, DnType, ExtendedKeyUsagePurpose, KeyPair};
+use rustls::{
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    ServerConfig, ServerConnection,
+};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::secret_vault::write_private_file;
+
+const CERT_DIR: &str = "preview-tls";
+const CERT_FILE: &str = "kforge-preview.pem";
+const KEY_FILE: &str = "kforge-preview-key.pem";
+const META_FILE: &str = "kforge-preview.json";
+/// Hosts every certificate covers; LAN addresses are added as needed.
+const DEFAULT_HOSTS: &[&str] = &["localhost", "127.0.0.1", "::1"];
+/// Apple platforms reject server certificates valid for longer.
+const VALIDITY: Duration = Duration::from_secs(825 * 24 * 60 * 60);
+const RENEW_BEFORE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificateInfo {
+    /// PEM file to import into the OS or browser trust store.
+    pub cert_path: String,
+    pub hosts: Vec<String>,
+    /// Unix seconds.
+    pub expires_at: u64,
+}
+
+pub struct LocalCertificate {
+    pub info: CertificateInfo,
+    /// True when this call generated a new certificate, which has to be
+    /// trusted again.
+    pub created: bool,
+    pub config: Arc<ServerConfig>,
+}
+
+pub fn certificate_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_local_data_dir()
+        .map(|dir| dir.join(CERT_DIR))
+        .map_err(|e| format!("Could not resolve the app data folder: {e}"))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn read_cached(dir: &Path) -> Option<(CertificateInfo, Vec<u8>, Vec<u8>)> {
+    let meta = fs::read_to_string(dir.join(META_FILE)).ok()?;
+    let info: CertificateInfo = serde_json::from_str(&meta).ok()?;
+    let cert = fs::read(dir.join(CERT_FILE)).ok()?;
+    let key = fs::read(dir.join(KEY_FILE)).ok()?;
+    Some((info, cert, key))
+}
+
+/// The cached certificate when it covers `hosts` and is not about to
+/// expire; otherwise a new one for those hosts plus everything the old one
+/// covered, so addresses that were trusted before keep working.
+pub fn load_or_create(dir: &Path, hosts: &[String]) -> Result<LocalCertificate, String> {
+    let mut wanted: BTreeSet<String> = DEFAULT_HOSTS.iter().map(|h| h.to_string()).collect();
+    wanted.extend(hosts.iter().cloned());
+
+    let cached = read_cached(dir);
+    if let Some((info, cert, key)) = &cached {
+        let covered = wanted.iter().all(|host| info.hosts.contains(host));
+        let fresh = info.expires_at > now_secs() + RENEW_BEFORE.as_secs();
+        if covered && fresh {
+            if let Ok(config) = server_config(cert, key) {
+                return Ok(LocalCertificate {
+                    info: info.clone(),
+                    created: false,
+                    config,
+                });
+            }
+        }
+    }
+    if let Some((info, _, _)) = cached {
+        wanted.extend(info.hosts);
+    }
+
+    let hosts: Vec<String> = wanted.into_iter().collect();
+    let (cert, key, expires_at) = generate(&hosts)?;
+
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let info = CertificateInfo {
+        cert_path: dir.join(CERT_FILE).to_string_lossy().to_string(),
+        hosts,
+        expires_at,
+    };
+    let meta = serde_json::to_string_pretty(&info).map_err(|e| e.to_string())?;
+    // The key must stay private to the user: anyone holding it can
+    // impersonate every preview that trusts the certificate.
+    write_private_file(&dir.join(KEY_FILE), key.as_bytes())?;
+    for (file, contents) in [(CERT_FILE, cert.as_bytes()), (META_FILE, meta.as_bytes())] {
+        let path = dir.join(file);
+        fs::write(&path, contents)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+
+    Ok(LocalCertificate {
+        info,
+        created: true,
+        config: server_config(cert.as_bytes(), key.as_bytes())?,
+    })
+}
+
+/// PEM certificate, PEM key and expiry for a certificate covering `hosts`.
+fn generate(hosts: &[String]) -> Result<(String, String, u64), String> {
+    let cert_err = |e: rcgen::Error| format!("Failed to create the HTTPS certificate: {}", e);
+    let mut params = CertificateParams::new(hosts.to_vec()).map_err(cert_err)?;
+    params
+        .distinguished_name
+        .push(DnType::CommonName, "KForge local preview");
+    params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+
+    // Backdated a day so a slightly wrong clock does not reject it.
+    let now = now_secs();
+    let epoch = rcgen::date_time_ymd(1970, 1, 1);
+    params.not_before = epoch + Duration::from_secs(now.saturating_sub(24 * 60 * 60));
+    let expires_at = now + VALIDITY.as_secs();
+    params.not_after = epoch + Duration::from_secs(expires_at);
+
+    let key = KeyPair::generate().map_err(cert_err)?;
+    let cert = params.self_signed(&key).map_err(cert_err)?;
+    Ok((cert.pem(), key.serialize_pem(), expires_at))
+}
+
+fn server_config(cert_pem: &[u8], key_pem: &[u8]) -> Result<Arc<ServerConfig>, String> {
+    let certs = CertificateDer::pem_slice_iter(cert_pem)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Invalid HTTPS certificate: {}", e))?;
+    let key = PrivateKeyDer::from_pem_slice(key_pem)
+        .map_err(|e| format!("Invalid HTTPS certificate key: {}", e))?;
+
+    let mut config =
+        ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|e| format!("TLS setup failed: {}", e))?
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .map_err(|e| format!("TLS setup failed: {}", e))?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(Arc::new(config))
+}
+
+/// A connected pair of loopback sockets.
+fn loopback_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    let client = TcpStream::connect(listener.local_addr()?)?;
+    let expected = client.local_addr()?;
+    loop {
+        let (server, peer) = listener.accept()?;
+        if peer == expected {
+            return Ok((client, server));
+        }
+    }
+}
+
+/// Take over an accepted TLS connection. Returns the plaintext end for the
+/// request handler; two threads shuttle bytes between the two, one per
+/// direction, until either side closes.
+pub fn terminate(stream: TcpStream, config: Arc<ServerConfig>) -> io::Result<TcpStream> {
+    let mut connection = ServerConnection::new(config).map_err(io::Error::other)?;
+    // Plaintext written before the handshake finishes waits in rustls;
+    // afterwards every write is encrypted and sent right away.
+    connection.set_buffer_limit(None);
+    let connection = Arc::new(Mutex::new(connection));
+    let (inner, plain) = loopback_pair()?;
+
+    let incoming = Arc::clone(&connection);
+    let (tls_in, tls_out) = (stream.try_clone()?, stream.try_clone()?);
+    let plain_in = inner.try_clone()?;
+    thread::spawn(move || {
+        let result = client_to_plain(&incoming, tls_in, tls_out, &plain_in);
+        // Unblocks a handler still waiting for a request; it then closes
+        // its end, which ends the other direction.
+        let how = if result.is_ok() {
+            Shutdown::Write
+        } else {
+            Shutdown::Both
+        };
+        let _ = plain_in.shutdown(how);
+    });
+    thread::spawn(move || {
+        let _ = plain_to_client(&connection, &inner, stream);
+    });
+    Ok(plain)
+}
+
+fn lock(connection: &Mutex<ServerConnection>) -> io::Result<MutexGuard<'_, ServerConnection>> {
+    connection
+        .lock()
+        .map_err(|_| io::Error::other("TLS connection lock poisoned"))
+}
+
+/// Send whatever rustls has queued for the browser. Called with the
+/// connection locked, so records from both directions go out in order.
+fn send_tls(connection: &mut ServerConnection, mut socket: &TcpStream) -> io::Result<()> {
+    while connection.wants_write() {
+        connection.write_tls(&mut socket)?;
+    }
+    Ok(())
+}
+
+/// Decrypt what the browser sends and pass it to the handler. The socket is
+/// read without holding the lock, so responses keep flowing while the
+/// browser is quiet.
+fn client_to_plain(
+    connection: &Mutex<ServerConnection>,
+    mut tls_in: TcpStream,
+    tls_out: TcpStream,
+    mut plain: &TcpStream,
+) -> io::Result<()> {
+    let mut buffer = vec![0_u8; 16 * 1024];
+    let mut plaintext = Vec::new();
+
+    loop {
+        let read = tls_in.read(&mut buffer)?;
+        // Browsers often close without a close_notify.
+        if read == 0 {
+            return Ok(());
+        }
+
+        let mut session = lock(connection)?;
+        let mut received = &buffer[..read];
+        let mut closed = false;
+        while !received.is_empty() {
+            session.read_tls(&mut received)?;
+            let state = match session.process_new_packets() {
+                Ok(state) => state,
+                Err(err) => {
+                    // Sends the alert rustls queued for the error.
+                    let _ = send_tls(&mut session, &tls_out);
+                    return Err(io::Error::other(err));
+                }
+            };
+            let start = plaintext.len();
+            plaintext.resize(start + state.plaintext_bytes_to_read(), 0);
+            session.reader().read_exact(&mut plaintext[start..])?;
+            closed |= state.peer_has_closed();
+        }
+        send_tls(&mut session, &tls_out)?;
+        drop(session);
+
+        plain.write_all(&plaintext)?;
+        plaintext.clear();
+        if closed {
+            return Ok(());
+        }
+    }
+}
+
+/// Encrypt the handler's output and send it to the browser; close the TLS
+/// session once the handler is done.
+fn plain_to_client(
+    connection: &Mutex<ServerConnection>,
+    mut plain: &TcpStream,
+    tls_out: TcpStream,
+) -> io::Result<()> {
+    let mut buffer = vec![0_u8; 16 * 1024];
+    let result = loop {
+        let read = match plain.read(&mut buffer) {
+            Ok(read) => read,
+            Err(err) => break Err(err),
+        };
+        let sent = lock(connection).and_then(|mut session| {
+            if read == 0 {
+                session.send_close_notify();
+            } else {
+                session.writer().write_all(&buffer[..read])?;
+            }
+            send_tls(&mut session, &tls_out)
+        });
+        if read == 0 || sent.is_err() {
+            break sent;
+        }
+    };
+
+    // Also ends the other direction's blocking read.
+    let _ = tls_out.shutdown(Shutdown::Both);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        io::{Read, Write},
+        net::{SocketAddr, TcpListener, TcpStream},
+        sync::Arc,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use rustls::{
+        pki_types::{pem::PemObject, CertificateDer, ServerName},
+        ClientConfig, ClientConnection, RootCertStore, StreamOwned,
+    };
+    use tempfile::tempdir;
+
+    use super::{load_or_create, terminate, KEY_FILE};
+
+    #[test]
+    fn caches_the_certificate_until_a_new_host_is_needed() {
+        let dir = tempdir().unwrap();
+        let first = load_or_create(dir.path(), &[]).unwrap();
+        assert!(first.created);
+        assert!(first.info.hosts.contains(&"localhost".to_string()));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let key = fs::metadata(dir.path().join(KEY_FILE)).unwrap();
+            assert_eq!(key.permissions().mode() & 0o777, 0o600);
+        }
+        let pem = fs::read(&first.info.cert_path).unwrap();
+
+        let again = load_or_create(dir.path(), &["localhost".to_string()]).unwrap();
+        assert!(!again.created);
+        assert_eq!(fs::read(&again.info.cert_path).unwrap(), pem);
+
+        let lan = load_or_create(dir.path(), &["192.168.1.20".to_string()]).unwrap();
+        assert!(lan.created);
+        assert!(lan.info.hosts.contains(&"192.168.1.20".to_string()));
+        assert!(lan.info.hosts.contains(&"::1".to_string()));
+    }
+
+    #[test]
+    fn terminates_tls_into_a_plain_stream() {
+        let dir = tempdir().unwrap();
+        let certificate = load_or_create(dir.path(), &[]).unwrap();
+
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let address = listener.local_addr().unwrap();
+        let config = Arc::clone(&certificate.config);
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut plain = terminate(stream, config).unwrap();
+            let mut request = [0_u8; 4];
+            plain.read_exact(&mut request).unwrap();
+            assert_eq!(&request, b"ping");
+            plain.write_all(b"pong").unwrap();
+        });
+
+        let mut client = connect(&certificate.info.cert_path, address);
+        client.write_all(b"ping").unwrap();
+        client.flush().unwrap();
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).unwrap();
+        assert_eq!(response, b"pong");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn streams_large_responses_without_waiting_on_the_browser() {
+        const SIZE: usize = 8 * 1024 * 1024;
+        let dir = tempdir().unwrap();
+        let certificate = load_or_create(dir.path(), &[]).unwrap();
+
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let address = listener.local_addr().unwrap();
+        let config = Arc::clone(&certificate.config);
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut plain = terminate(stream, config).unwrap();
+            let mut request = [0_u8; 3];
+            plain.read_exact(&mut request).unwrap();
+            plain.write_all(&vec![b'x'; SIZE]).unwrap();
+        });
+
+        let started = Instant::now();
+        let mut client = connect(&certificate.info.cert_path, address);
+        client.write_all(b"get").unwrap();
+        client.flush().unwrap();
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).unwrap();
+        server.join().unwrap();
+
+        assert_eq!(response.len(), SIZE);
+        assert!(
+            started.elapsed() < Duration::from_secs(5),
+            "took {:?}",
+            started.elapsed()
+        );
+    }
+
+    fn connect(cert_path: &str, address: SocketAddr) -> StreamOwned<ClientConnection, TcpStream> {
+        let mut roots = RootCertStore::empty();
+        for cert in CertificateDer::pem_file_iter(cert_path).unwrap() {
+            roots.add(cert.unwrap()).unwrap();
+        }
+        let client_config =
+            ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .unwrap()
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+        let connection = ClientConnection::new(
+            Arc::new(client_config),
+            ServerName::try_from("localhost").unwrap(),
+        )
+        .unwrap();
+        StreamOwned::new(connection, TcpStream::connect(address).unwrap())
+    }
+}
@@ -0,0 +1,299 @@
+// src-tauri/src/process_tree.rs
+//
+// Stopping a spawned command together with everything it started. Package
+// managers and shells fork the real server (Vite, Next, Node), so signalling
+// only the direct child leaves grandchildren holding the port.
+//
+// Unix: children get their own process group and the whole group is
+// signalled. Windows: children are assigned to a job object that is
+// terminated as a unit (and killed if KForge itself exits).
+
+use std::{
+    process::{Child, Command},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How long a tree gets to exit after the graceful signal before it is
+/// force killed.
+pub const STOP_GRACE_PERIOD: Duration = Duration::from_secs(5);
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopOutcome {
+    /// Everything exited after the graceful request.
+    Exited,
+    /// The grace period ran out and the tree was force killed.
+    Killed,
+}
+
+/// Put the command in its own process group so it can be signalled as one.
+/// Call before `spawn`.
+pub fn configure(command: &mut Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+
+    #[cfg(not(unix))]
+    let _ = command;
+}
+
+pub struct ProcessTree {
+    pid: u32,
+    #[cfg(windows)]
+    job: Option<windows::Job>,
+}
+
+impl ProcessTree {
+    /// Track a child spawned from a command passed through [`configure`].
+    pub fn attach(child: &Child) -> Self {
+        Self {
+            pid: child.id(),
+            #[cfg(windows)]
+            job: windows::Job::for_child(child),
+        }
+    }
+
+    /// Track a process that already leads its own process group, such as a
+    /// child spawned into a pseudo-terminal (which makes it session leader).
+    pub fn attach_leader(pid: u32) -> Self {
+        Self {
+            pid,
+            #[cfg(windows)]
+            job: None,
+        }
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Ask the tree to exit, then force kill whatever is left after `grace`.
+    /// Blocks for up to `grace`.
+    pub fn terminate(&self, grace: Duration) -> StopOutcome {
+        self.request_exit();
+
+        let deadline = Instant::now() + grace;
+        while Instant::now() < deadline {
+            if !self.is_alive() {
+                return StopOutcome::Exited;
+            }
+            thread::sleep(EXIT_POLL_INTERVAL);
+        }
+
+        if !self.is_alive() {
+            return StopOutcome::Exited;
+        }
+        self.kill();
+        StopOutcome::Killed
+    }
+
+    #[cfg(unix)]
+    fn signal_group(&self, signal: libc::c_int) -> bool {
+        let Ok(pgid) = libc::pid_t::try_from(self.pid) else {
+            return false;
+        };
+        // A negative pid addresses the whole process group.
+        unsafe { libc::kill(-pgid, signal) == 0 }
+    }
+
+    #[cfg(unix)]
+    fn request_exit(&self) {
+        self.signal_group(libc::SIGTERM);
+    }
+
+    /// Whether any process in the tree is still running.
+    #[cfg(unix)]
+    pub fn is_alive(&self) -> bool {
+        self.signal_group(0)
+    }
+
+    #[cfg(unix)]
+    fn kill(&self) {
+        self.signal_group(libc::SIGKILL);
+    }
+
+    #[cfg(windows)]
+    fn request_exit(&self) {
+        use std::os::windows::process::CommandExt;
+
+        // Without /F taskkill asks windowed processes to close; console
+        // servers usually ignore it and are handled by the job below.
+        let _ = Command::new("taskkill")
+            .args(["/PID", &self.pid.to_string(), "/T"])
+            .creation_flags(windows::CREATE_NO_WINDOW)
+            .status();
+    }
+
+    /// Whether any process in the tree is still running.
+    #[cfg(windows)]
+    pub fn is_alive(&self) -> bool {
+        match &self.job {
+            Some(job) => job.active_processes() > 0,
+            None => windows::pid_is_running(self.pid),
+        }
+    }
+
+    #[cfg(windows)]
+    fn kill(&self) {
+        use std::os::windows::process::CommandExt;
+
+        match &self.job {
+            Some(job) => job.terminate(),
+            None => {
+                let _ = Command::new("taskkill")
+                    .args(["/PID", &self.pid.to_string(), "/T", "/F"])
+                    .creation_flags(windows::CREATE_NO_WINDOW)
+                    .status();
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::{
+        mem,
+        os::windows::{io::AsRawHandle, process::CommandExt},
+        process::{Child, Command},
+        ptr,
+    };
+
+    use windows_sys::Win32::{
+        Foundation::{CloseHandle, HANDLE},
+        System::JobObjects::{
+            AssignProcessToJobObject, CreateJobObjectW, JobObjectBasicAccountingInformation,
+            JobObjectExtendedLimitInformation, QueryInformationJobObject, SetInformationJobObject,
+            TerminateJobObject, JOBOBJECT_BASIC_ACCOUNTING_INFORMATION,
+            JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+        },
+    };
+
+    pub const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    pub struct Job(HANDLE);
+
+    // The handle is only used through thread-safe Win32 job APIs.
+    unsafe impl Send for Job {}
+    unsafe impl Sync for Job {}
+
+    impl Job {
+        /// A kill-on-close job holding `child`, or None if the job could not
+        /// be set up (the tree is then stopped with taskkill instead).
+        pub fn for_child(child: &Child) -> Option<Self> {
+            unsafe {
+                let handle = CreateJobObjectW(ptr::null(), ptr::null());
+                if handle.is_null() {
+                    return None;
+                }
+                let job = Job(handle);
+
+                let mut limits: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = mem::zeroed();
+                limits.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+                let configured = SetInformationJobObject(
+                    job.0,
+                    JobObjectExtendedLimitInformation,
+                    &limits as *const _ as *const _,
+                    mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+                );
+                if configured == 0 {
+                    return None;
+                }
+
+                if AssignProcessToJobObject(job.0, child.as_raw_handle() as HANDLE) == 0 {
+                    return None;
+                }
+                Some(job)
+            }
+        }
+
+        pub fn active_processes(&self) -> u32 {
+            unsafe {
+                let mut info: JOBOBJECT_BASIC_ACCOUNTING_INFORMATION = mem::zeroed();
+                let ok = QueryInformationJobObject(
+                    self.0,
+                    JobObjectBasicAccountingInformation,
+                    &mut info as *mut _ as *mut _,
+                    mem::size_of::<JOBOBJECT_BASIC_ACCOUNTING_INFORMATION>() as u32,
+                    ptr::null_mut(),
+                );
+                if ok == 0 {
+                    0
+                } else {
+                    info.ActiveProcesses
+                }
+            }
+        }
+
+        pub fn terminate(&self) {
+            unsafe {
+                TerminateJobObject(self.0, 1);
+            }
+        }
+    }
+
+    impl Drop for Job {
+        fn drop(&mut self) {
+            unsafe {
+                CloseHandle(self.0);
+            }
+        }
+    }
+
+    pub fn pid_is_running(pid: u32) -> bool {
+        Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()))
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{
+        process::{Command, Stdio},
+        thread,
+        time::Duration,
+    };
+
+    use super::{configure, ProcessTree, StopOutcome};
+
+    #[test]
+    fn terminating_the_tree_reaches_grandchildren() {
+        // The shell forks a grandchild that would outlive a plain kill.
+        let mut command = Command::new("sh");
+        command
+            .args(["-c", "sleep 30 & wait"])
+            .stdout(Stdio::null());
+        configure(&mut command);
+        let mut child = command.spawn().unwrap();
+        let tree = ProcessTree::attach(&child);
+        thread::sleep(Duration::from_millis(200));
+
+        let waiter = thread::spawn(move || child.wait());
+        assert_eq!(tree.terminate(Duration::from_secs(5)), StopOutcome::Exited);
+        waiter.join().unwrap().unwrap();
+        assert!(!tree.is_alive());
+    }
+
+    #[test]
+    fn escalates_to_kill_when_the_tree_ignores_term() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "trap '' TERM; sleep 30 & wait"]);
+        configure(&mut command);
+        let mut child = command.spawn().unwrap();
+        let tree = ProcessTree::attach(&child);
+        thread::sleep(Duration::from_millis(200));
+
+        let waiter = thread::spawn(move || child.wait());
+        assert_eq!(
+            tree.terminate(Duration::from_millis(300)),
+            StopOutcome::Killed
+        );
+        waiter.join().unwrap().unwrap();
+    }
+}
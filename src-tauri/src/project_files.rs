@@ -0,0 +1,15 @@
+// src-tauri/src/project_files.rs
+//
+// Rules about files inside a user's project that more than one feature
+// needs, kept apart from any single feature's module.
+
+/// `.env`, `.env.local`, `production.env` and so on: files that hold
+/// secrets by convention. Documented templates (`.env.example`) are fine.
+pub fn is_env_file(name: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    let is_env = lower == ".env" || lower.starts_with(".env.") || lower.ends_with(".env");
+    let is_template = [".example", ".sample", ".template"]
+        .iter()
+        .any(|suffix| lower.ends_with(suffix));
+    is_env && !is_template
+}
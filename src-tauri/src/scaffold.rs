@@ -14,6 +14,8 @@ use serde::Serialize;
 use serde_json::Value;
 use tauri::Emitter;
 
+use crate::package_manager::{PackageManager, DEFAULT_PACKAGE_MANAGER};
+
 const PREVIEW_LOG_EVENT: &str = "kforge://preview/log";
 const PREVIEW_STATUS_EVENT: &str = "kforge://preview/status";
 
@@ -110,15 +112,51 @@ fn ensure_expo_dev_script(project_path: &str) -> Result<(), String> {
 
     Ok(())
 }
+
+fn resolve_package_manager(package_manager: Option<&str>) -> Result<PackageManager, String> {
+    match package_manager
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+    {
+        None => Ok(DEFAULT_PACKAGE_MANAGER),
+        Some(value) => PackageManager::parse(value).ok_or_else(|| {
+            format!(
+                "Unsupported package manager: {}. Use npm, yarn, pnpm or bun.",
+                value
+            )
+        }),
+    }
+}
+
 fn run_scaffold_blocking(
     window: tauri::Window,
     parent_path: String,
     app_name: String,
-    command_label: &'static str,
-    command_args: &'static [&'static str],
+    package_manager: Option<String>,
+    create_package: &'static str,
+    create_args: &'static [&'static str],
     failure_label: &'static str,
 ) -> Result<String, String> {
     let (parent_path, _app_name) = validate_scaffold_inputs(&parent_path, &app_name)?;
+    let manager = resolve_package_manager(package_manager.as_deref())?;
+
+    if !manager.is_installed() {
+        let reason = if package_manager
+            .as_deref()
+            .is_some_and(|v| !v.trim().is_empty())
+        {
+            "picked for the new app"
+        } else {
+            "KForge's default"
+        };
+        let message = manager.not_installed_message(reason);
+        emit_preview_log(&window, "stderr", message.clone());
+        return Err(message);
+    }
+
+    // Windows spawn fix: the program already carries the .cmd suffix.
+    let (program, args) = manager.exec(create_package, create_args);
+    let command_label = format!("{} {}", program.trim_end_matches(".cmd"), args.join(" "));
 
     emit_preview_status(&window, "scaffold:starting");
     emit_preview_log(
@@ -127,19 +165,18 @@ fn run_scaffold_blocking(
         format!("scaffold: running {}", command_label),
     );
 
-    // Windows spawn fix
-    let pnpm = if cfg!(windows) { "pnpm.cmd" } else { "pnpm" };
-
-    let mut child = background_command(pnpm)
+    let mut child = background_command(program)
         .current_dir(&parent_path)
-        .args(command_args)
+        .args(&args)
         .env("CI", "1")
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .spawn()
         .map_err(|e| {
             format!(
-                "Failed to spawn pnpm. Is pnpm installed and on PATH? ({})",
+                "Failed to spawn {}. Is {} installed and on PATH? ({})",
+                program,
+                manager.name(),
                 e
             )
         })?;
@@ -171,7 +208,7 @@ fn run_scaffold_blocking(
 
     let status = child
         .wait()
-        .map_err(|e| format!("Failed waiting for pnpm process: {}", e))?;
+        .map_err(|e| format!("Failed waiting for {} process: {}", manager.name(), e))?;
 
     let _ = out_handle.join();
     let _ = err_handle.join();
@@ -335,21 +372,16 @@ pub async fn scaffold_vite_react(
     window: tauri::Window,
     parent_path: String,
     app_name: String,
+    package_manager: Option<String>,
 ) -> Result<String, String> {
     tauri::async_runtime::spawn_blocking(move || {
         run_scaffold_blocking(
             window,
             parent_path,
             app_name,
-            "pnpm dlx create-vite@latest . --template react --no-interactive",
-            &[
-                "dlx",
-                "create-vite@latest",
-                ".",
-                "--template",
-                "react",
-                "--no-interactive",
-            ],
+            package_manager,
+            "create-vite@latest",
+            &[".", "--template", "react", "--no-interactive"],
             "Vite",
         )
     })
@@ -361,14 +393,16 @@ pub async fn scaffold_expo_react_native(
     window: tauri::Window,
     parent_path: String,
     app_name: String,
+    package_manager: Option<String>,
 ) -> Result<String, String> {
     tauri::async_runtime::spawn_blocking(move || {
         let generated_path = run_scaffold_blocking(
             window.clone(),
             parent_path,
             app_name,
-            "pnpm dlx create-expo-app@latest . --template blank",
-            &["dlx", "create-expo-app@latest", ".", "--template", "blank"],
+            package_manager,
+            "create-expo-app@latest",
+            &[".", "--template", "blank"],
             "Expo React Native",
         )?;
 
@@ -390,14 +424,16 @@ pub async fn scaffold_nextjs(
     window: tauri::Window,
     parent_path: String,
     app_name: String,
+    package_manager: Option<String>,
 ) -> Result<String, String> {
     tauri::async_runtime::spawn_blocking(move || {
         run_scaffold_blocking(
             window,
             parent_path,
             app_name,
-            "pnpm create next-app@latest . --yes",
-            &["create", "next-app@latest", ".", "--yes"],
+            package_manager,
+            "create-next-app@latest",
+            &[".", "--yes"],
             "Next.js",
         )
     })
@@ -0,0 +1,491 @@
+// src-tauri/src/secret_vault.rs
+//
+// Passphrase-encrypted file vault for secrets.
+//
+// The OS credential store stays the preferred backend. This vault is only
+// written when the keyring backend reports that no storage is reachable
+// (e.g. a Linux session without a Secret Service provider on D-Bus), so keys
+// survive restarts instead of living only in the session cache.
+//
+// File format: one JSON envelope holding the PBKDF2 salt, the AEAD nonce and
+// the ChaCha20-Poly1305 ciphertext of a JSON map "service/account" -> secret.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Write,
+    num::NonZeroU32,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN},
+    pbkdf2,
+    rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::temp_file;
+
+pub const VAULT_FILE_NAME: &str = "secrets.vault";
+
+const VAULT_PURPOSE: &str = "kforge-secret-vault";
+const ENVELOPE_VERSION: u32 = 1;
+const KDF_NAME: &str = "pbkdf2-hmac-sha256";
+const PBKDF2_ITERATIONS: u32 = 600_000;
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+const MIN_PASSPHRASE_CHARS: usize = 8;
+
+static VAULT: OnceLock<Mutex<Option<UnlockedVault>>> = OnceLock::new();
+/// Vault file for this install, known before the first unlock so removals
+/// can tell "nothing stored" from "stored but locked".
+static VAULT_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+fn vault() -> &'static Mutex<Option<UnlockedVault>> {
+    VAULT.get_or_init(|| Mutex::new(None))
+}
+
+/// Encrypted payload as written to disk. `purpose` is bound into the AEAD tag
+/// so an envelope produced for one use cannot be opened as another.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SealedEnvelope {
+    pub version: u32,
+    pub purpose: String,
+    pub kdf: String,
+    pub iterations: u32,
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct VaultStatus {
+    pub path: String,
+    pub exists: bool,
+    pub unlocked: bool,
+}
+
+struct UnlockedVault {
+    path: PathBuf,
+    key: [u8; KEY_LEN],
+    salt: Vec<u8>,
+    iterations: u32,
+    entries: BTreeMap<String, String>,
+}
+
+/// True when the keyring error means "no credential store is reachable",
+/// as opposed to a missing entry or a bad value.
+pub fn is_keyring_unavailable(error: &keyring::Error) -> bool {
+    matches!(
+        error,
+        keyring::Error::NoStorageAccess(_) | keyring::Error::PlatformFailure(_)
+    )
+}
+
+pub fn default_vault_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_local_data_dir()
+        .map(|dir| dir.join(VAULT_FILE_NAME))
+        .map_err(|e| format!("Could not resolve the app data folder: {e}"))
+}
+
+/// Remember where the vault lives. Called once during setup.
+pub fn init(app: &AppHandle) {
+    if let Ok(path) = default_vault_path(app) {
+        let _ = VAULT_PATH.set(path);
+    }
+}
+
+pub fn status(path: &Path) -> Result<VaultStatus, String> {
+    let guard = vault()
+        .lock()
+        .map_err(|_| "Secret vault lock poisoned".to_string())?;
+
+    Ok(VaultStatus {
+        path: path.to_string_lossy().to_string(),
+        exists: path.is_file(),
+        unlocked: guard.as_ref().is_some_and(|v| v.path == path),
+    })
+}
+
+/// Unlock the vault at `path`, creating an empty one protected by
+/// `passphrase` when no file exists yet.
+pub fn unlock(path: &Path, passphrase: &str) -> Result<(), String> {
+    VAULT_PATH.get_or_init(|| path.to_path_buf());
+    let unlocked = if path.is_file() {
+        UnlockedVault::open(path, passphrase)?
+    } else {
+        UnlockedVault::create(path, passphrase, PBKDF2_ITERATIONS)?
+    };
+
+    let mut guard = vault()
+        .lock()
+        .map_err(|_| "Secret vault lock poisoned".to_string())?;
+    *guard = Some(unlocked);
+    Ok(())
+}
+
+pub fn lock() -> Result<(), String> {
+    let mut guard = vault()
+        .lock()
+        .map_err(|_| "Secret vault lock poisoned".to_string())?;
+    *guard = None;
+    Ok(())
+}
+
+/// Read a secret. A locked vault behaves like an empty one.
+pub fn get(service: &str, account: &str) -> Result<Option<String>, String> {
+    let guard = vault()
+        .lock()
+        .map_err(|_| "Secret vault lock poisoned".to_string())?;
+    Ok(guard
+        .as_ref()
+        .and_then(|v| v.entries.get(&entry_id(service, account)).cloned()))
+}
+
+/// Store a secret. Returns false when the vault is locked and nothing was
+/// written.
+pub fn set(service: &str, account: &str, secret: &str) -> Result<bool, String> {
+    let mut guard = vault()
+        .lock()
+        .map_err(|_| "Secret vault lock poisoned".to_string())?;
+    match guard.as_mut() {
+        Some(v) => {
+            v.set(entry_id(service, account), secret.to_string())?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Delete a secret. Fails while a vault file exists but is locked, since
+/// the encrypted copy would otherwise come back at the next unlock.
+pub fn remove(service: &str, account: &str) -> Result<(), String> {
+    let mut guard = vault()
+        .lock()
+        .map_err(|_| "Secret vault lock poisoned".to_string())?;
+    match guard.as_mut() {
+        Some(v) => v.remove(&entry_id(service, account)),
+        None if VAULT_PATH.get().is_some_and(|path| path.is_file()) => {
+            Err("the vault is locked; unlock it first so the stored copy is removed too".into())
+        }
+        None => Ok(()),
+    }
+}
+
+fn entry_id(service: &str, account: &str) -> String {
+    format!("{service}/{account}")
+}
+
+impl UnlockedVault {
+    fn create(path: &Path, passphrase: &str, iterations: u32) -> Result<Self, String> {
+        validate_passphrase(passphrase)?;
+        let salt = random_bytes(SALT_LEN)?;
+        let vault = Self {
+            path: path.to_path_buf(),
+            key: derive_key(passphrase, &salt, iterations)?,
+            salt,
+            iterations,
+            entries: BTreeMap::new(),
+        };
+        vault.persist()?;
+        Ok(vault)
+    }
+
+    fn open(path: &Path, passphrase: &str) -> Result<Self, String> {
+        let raw =
+            fs::read_to_string(path).map_err(|e| format!("Secret vault could not be read: {e}"))?;
+        let envelope: SealedEnvelope = serde_json::from_str(&raw)
+            .map_err(|_| "Secret vault file is damaged or not a KForge vault".to_string())?;
+        check_envelope(&envelope, VAULT_PURPOSE)?;
+
+        let salt = decode(&envelope.salt)?;
+        let key = derive_key(passphrase, &salt, envelope.iterations)?;
+        let plaintext = open_with_key(&key, &envelope)?;
+        let entries = serde_json::from_slice(&plaintext)
+            .map_err(|_| "Secret vault contents are damaged".to_string())?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            key,
+            salt,
+            iterations: envelope.iterations,
+            entries,
+        })
+    }
+
+    fn set(&mut self, id: String, secret: String) -> Result<(), String> {
+        let previous = self.entries.insert(id.clone(), secret);
+        if let Err(error) = self.persist() {
+            // Keep memory and disk in agreement if the write fails.
+            match previous {
+                Some(value) => self.entries.insert(id, value),
+                None => self.entries.remove(&id),
+            };
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, id: &str) -> Result<(), String> {
+        if self.entries.remove(id).is_none() {
+            return Ok(());
+        }
+        self.persist()
+    }
+
+    fn persist(&self) -> Result<(), String> {
+        let plaintext = serde_json::to_vec(&self.entries)
+            .map_err(|e| format!("Secret vault could not be encoded: {e}"))?;
+        let envelope = seal_with_key(
+            &self.key,
+            &self.salt,
+            self.iterations,
+            VAULT_PURPOSE,
+            &plaintext,
+        )?;
+        let encoded = serde_json::to_vec_pretty(&envelope)
+            .map_err(|e| format!("Secret vault could not be encoded: {e}"))?;
+        write_private_file(&self.path, &encoded)
+    }
+}
+
+/// Encrypt `plaintext` under a key derived from `passphrase`.
+pub fn seal(passphrase: &str, purpose: &str, plaintext: &[u8]) -> Result<SealedEnvelope, String> {
+    validate_passphrase(passphrase)?;
+    let salt = random_bytes(SALT_LEN)?;
+    let key = derive_key(passphrase, &salt, PBKDF2_ITERATIONS)?;
+    seal_with_key(&key, &salt, PBKDF2_ITERATIONS, purpose, plaintext)
+}
+
+/// Decrypt an envelope produced by [`seal`] for the same `purpose`.
+pub fn open(passphrase: &str, purpose: &str, envelope: &SealedEnvelope) -> Result<Vec<u8>, String> {
+    check_envelope(envelope, purpose)?;
+    let salt = decode(&envelope.salt)?;
+    let key = derive_key(passphrase, &salt, envelope.iterations)?;
+    open_with_key(&key, envelope)
+}
+
+fn check_envelope(envelope: &SealedEnvelope, purpose: &str) -> Result<(), String> {
+    if envelope.version != ENVELOPE_VERSION || envelope.kdf != KDF_NAME {
+        return Err("Encrypted data uses an unsupported format version".into());
+    }
+    if envelope.purpose != purpose {
+        return Err("Encrypted data was not created for this purpose".into());
+    }
+    if envelope.iterations == 0 {
+        return Err("Encrypted data has invalid key-derivation settings".into());
+    }
+    Ok(())
+}
+
+fn validate_passphrase(passphrase: &str) -> Result<(), String> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_CHARS {
+        return Err(format!(
+            "Passphrase must be at least {MIN_PASSPHRASE_CHARS} characters"
+        ));
+    }
+    Ok(())
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> Result<[u8; KEY_LEN], String> {
+    let iterations = NonZeroU32::new(iterations)
+        .ok_or_else(|| "Key-derivation iterations must be positive".to_string())?;
+    let mut key = [0_u8; KEY_LEN];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        iterations,
+        salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+    Ok(key)
+}
+
+fn seal_with_key(
+    key: &[u8; KEY_LEN],
+    salt: &[u8],
+    iterations: u32,
+    purpose: &str,
+    plaintext: &[u8],
+) -> Result<SealedEnvelope, String> {
+    let nonce_bytes = random_bytes(NONCE_LEN)?;
+    let nonce = Nonce::try_assume_unique_for_key(&nonce_bytes)
+        .map_err(|_| "Encryption nonce could not be created".to_string())?;
+
+    let mut in_out = plaintext.to_vec();
+    aead_key(key)?
+        .seal_in_place_append_tag(nonce, Aad::from(purpose.as_bytes()), &mut in_out)
+        .map_err(|_| "Encryption failed".to_string())?;
+
+    Ok(SealedEnvelope {
+        version: ENVELOPE_VERSION,
+        purpose: purpose.to_string(),
+        kdf: KDF_NAME.to_string(),
+        iterations,
+        salt: BASE64.encode(salt),
+        nonce: BASE64.encode(&nonce_bytes),
+        ciphertext: BASE64.encode(&in_out),
+    })
+}
+
+fn open_with_key(key: &[u8; KEY_LEN], envelope: &SealedEnvelope) -> Result<Vec<u8>, String> {
+    let nonce = Nonce::try_assume_unique_for_key(&decode(&envelope.nonce)?)
+        .map_err(|_| "Encrypted data has an invalid nonce".to_string())?;
+    let mut in_out = decode(&envelope.ciphertext)?;
+
+    let plaintext = aead_key(key)?
+        .open_in_place(nonce, Aad::from(envelope.purpose.as_bytes()), &mut in_out)
+        .map_err(|_| "Incorrect passphrase or tampered data".to_string())?;
+    Ok(plaintext.to_vec())
+}
+
+fn aead_key(key: &[u8; KEY_LEN]) -> Result<LessSafeKey, String> {
+    UnboundKey::new(&CHACHA20_POLY1305, key)
+        .map(LessSafeKey::new)
+        .map_err(|_| "Encryption key could not be created".to_string())
+}
+
+fn random_bytes(len: usize) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![0_u8; len];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| "System random generator unavailable".to_string())?;
+    Ok(bytes)
+}
+
+fn decode(value: &str) -> Result<Vec<u8>, String> {
+    BASE64
+        .decode(value)
+        .map_err(|_| "Encrypted data is not valid base64".to_string())
+}
+
+/// Write via a sibling temp file and rename, so a crash never leaves a
+/// half-written vault. On Unix the file is readable by the owner only.
+pub fn write_private_file(path: &Path, contents: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Could not create {}: {e}", parent.display()))?;
+    }
+
+    let tmp = temp_file::sibling(path);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options
+        .open(&tmp)
+        .map_err(|e| format!("Could not write {}: {e}", tmp.display()))?;
+    file.write_all(contents)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Could not write {}: {e}", tmp.display()))?;
+    drop(file);
+
+    fs::rename(&tmp, path).map_err(|e| format!("Could not replace {}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get, lock, open, remove, seal, set, unlock, write_private_file, UnlockedVault};
+
+    const TEST_ITERATIONS: u32 = 1_000;
+
+    #[test]
+    fn vault_round_trips_entries_across_unlocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested/secrets.vault");
+
+        let mut vault =
+            UnlockedVault::create(&path, "correct horse battery", TEST_ITERATIONS).unwrap();
+        vault
+            .set("com.kforge.kforge/provider_openai".into(), "sk-test".into())
+            .unwrap();
+
+        let on_disk = std::fs::read_to_string(&path).unwrap();
+        assert!(!on_disk.contains("sk-test"));
+
+        let reopened = UnlockedVault::open(&path, "correct horse battery").unwrap();
+        assert_eq!(
+            reopened
+                .entries
+                .get("com.kforge.kforge/provider_openai")
+                .map(String::as_str),
+            Some("sk-test")
+        );
+    }
+
+    #[test]
+    fn vault_rejects_wrong_passphrase() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secrets.vault");
+        UnlockedVault::create(&path, "correct horse battery", TEST_ITERATIONS).unwrap();
+
+        let error = UnlockedVault::open(&path, "wrong horse battery")
+            .err()
+            .unwrap();
+        assert!(error.contains("Incorrect passphrase"));
+    }
+
+    #[test]
+    fn short_passphrases_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secrets.vault");
+        assert!(UnlockedVault::create(&path, "short", TEST_ITERATIONS).is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn removing_while_locked_fails_instead_of_keeping_the_stored_copy() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secrets.vault");
+        unlock(&path, "correct horse battery").unwrap();
+        assert!(set("test-service", "provider_openai", "sk-test").unwrap());
+        lock().unwrap();
+
+        let error = remove("test-service", "provider_openai").unwrap_err();
+        assert!(error.contains("unlock it first"), "{error}");
+
+        unlock(&path, "correct horse battery").unwrap();
+        remove("test-service", "provider_openai").unwrap();
+        lock().unwrap();
+        unlock(&path, "correct horse battery").unwrap();
+        assert_eq!(get("test-service", "provider_openai").unwrap(), None);
+        lock().unwrap();
+    }
+
+    #[test]
+    fn sealed_envelopes_are_bound_to_their_purpose() {
+        let envelope = seal("correct horse battery", "purpose-a", b"payload").unwrap();
+        assert_eq!(
+            open("correct horse battery", "purpose-a", &envelope).unwrap(),
+            b"payload"
+        );
+        assert!(open("correct horse battery", "purpose-b", &envelope).is_err());
+    }
+
+    #[test]
+    fn private_writes_leave_sibling_tmp_files_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keys");
+        std::fs::write(dir.path().join("keys.tmp"), "unrelated").unwrap();
+
+        write_private_file(&path, b"first").unwrap();
+        write_private_file(&path, b"second").unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("keys.tmp")).unwrap(),
+            "unrelated"
+        );
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+}
@@ -299,37 +299,9 @@ fn detect_framework(
 }
 
 fn detect_package_manager(declared: Option<&str>, root_files: &BTreeSet<String>) -> String {
-    let managers = [
-        ("pnpm-lock.yaml", "pnpm"),
-        ("package-lock.json", "npm"),
-        ("yarn.lock", "yarn"),
-        ("bun.lock", "bun"),
-        ("bun.lockb", "bun"),
-    ]
-    .into_iter()
-    .filter(|(file, _)| root_files.contains(*file))
-    .map(|(_, manager)| manager)
-    .collect::<BTreeSet<_>>();
-
-    if managers.len() == 1 {
-        return managers.iter().next().unwrap_or(&"unknown").to_string();
-    }
-    if managers.len() > 1 {
-        return "unknown".into();
-    }
-    let declared_manager = declared
-        .unwrap_or_default()
-        .trim()
-        .to_ascii_lowercase()
-        .split('@')
-        .next()
-        .unwrap_or_default()
-        .to_string();
-    if ["pnpm", "npm", "yarn", "bun"].contains(&declared_manager.as_str()) {
-        declared_manager
-    } else {
-        "unknown".into()
-    }
+    crate::package_manager::detect_package_manager(declared, root_files)
+        .map(|manager| manager.name().to_string())
+        .unwrap_or_else(|| "unknown".into())
 }
 
 fn collect_source_paths(root: &Path, warnings: &mut Vec<String>) -> Result<Vec<String>, String> {
@@ -520,7 +492,7 @@ fn classify_source_file(
     }
 }
 
-fn read_bounded_text(path: &Path, max_bytes: u64) -> Result<String, String> {
+pub(crate) fn read_bounded_text(path: &Path, max_bytes: u64) -> Result<String, String> {
     let metadata = fs::metadata(path).map_err(|_| "file is unavailable".to_string())?;
     if !metadata.is_file() || metadata.len() > max_bytes {
         return Err("file exceeded the read-only inspection size limit".into());
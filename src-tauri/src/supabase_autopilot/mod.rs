@@ -1,4 +1,4 @@
-mod inspection;
+pub(crate) mod inspection;
 mod mcp;
 pub(crate) mod mutation;
 mod oauth;
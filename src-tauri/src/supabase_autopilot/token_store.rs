@@ -1,6 +1,8 @@
 use async_trait::async_trait;
 use rmcp::transport::auth::{AuthError, CredentialStore, StoredCredentials};
 
+use crate::secret_vault;
+
 const KEYRING_SERVICE: &str = "com.kforge.kforge.supabase-autopilot";
 const READ_ONLY_KEYRING_ACCOUNT: &str = "hosted-mcp-oauth";
 const DATABASE_WRITE_KEYRING_ACCOUNT: &str = "hosted-mcp-oauth-database-write";
@@ -10,17 +12,34 @@ fn entry(account: &str) -> Result<keyring::Entry, AuthError> {
         .map_err(|error| AuthError::InternalError(format!("credential store unavailable: {error}")))
 }
 
+fn vault_error(error: String) -> AuthError {
+    AuthError::InternalError(format!("encrypted credential vault failed: {error}"))
+}
+
 async fn load_credentials(
     account: &'static str,
 ) -> Result<Option<StoredCredentials>, AuthError> {
-    tokio::task::spawn_blocking(move || match entry(account)?.get_password() {
-        Ok(serialized) => serde_json::from_str(&serialized)
+    tokio::task::spawn_blocking(move || {
+        let serialized = match entry(account)?.get_password() {
+            Ok(serialized) => serialized,
+            Err(error)
+                if matches!(error, keyring::Error::NoEntry)
+                    || secret_vault::is_keyring_unavailable(&error) =>
+            {
+                match secret_vault::get(KEYRING_SERVICE, account).map_err(vault_error)? {
+                    Some(serialized) => serialized,
+                    None => return Ok(None),
+                }
+            }
+            Err(error) => {
+                return Err(AuthError::InternalError(format!(
+                    "credential store read failed: {error}"
+                )))
+            }
+        };
+        serde_json::from_str(&serialized)
             .map(Some)
-            .map_err(|_| AuthError::InternalError("stored OAuth credentials are invalid".into())),
-        Err(keyring::Error::NoEntry) => Ok(None),
-        Err(error) => Err(AuthError::InternalError(format!(
-            "credential store read failed: {error}"
-        ))),
+            .map_err(|_| AuthError::InternalError("stored OAuth credentials are invalid".into()))
     })
     .await
     .map_err(|_| AuthError::InternalError("credential store task failed".into()))?
@@ -33,24 +52,42 @@ async fn save_credentials(
     let serialized = serde_json::to_string(&credentials)
         .map_err(|_| AuthError::InternalError("OAuth credentials could not be encoded".into()))?;
 
-    tokio::task::spawn_blocking(move || {
-        entry(account)?.set_password(&serialized).map_err(|error| {
-            AuthError::InternalError(format!("credential store write failed: {error}"))
-        })
+    tokio::task::spawn_blocking(move || match entry(account)?.set_password(&serialized) {
+        Ok(()) => Ok(()),
+        Err(error) if secret_vault::is_keyring_unavailable(&error) => {
+            if secret_vault::set(KEYRING_SERVICE, account, &serialized).map_err(vault_error)? {
+                Ok(())
+            } else {
+                Err(AuthError::InternalError(format!(
+                    "credential store unavailable ({error}); unlock the encrypted key vault to stay signed in"
+                )))
+            }
+        }
+        Err(error) => Err(AuthError::InternalError(format!(
+            "credential store write failed: {error}"
+        ))),
     })
     .await
     .map_err(|_| AuthError::InternalError("credential store task failed".into()))?
 }
 
 async fn clear_credentials(account: &'static str) -> Result<(), AuthError> {
-    tokio::task::spawn_blocking(move || match entry(account)?.delete_credential() {
+    tokio::task::spawn_blocking(move || {
+        secret_vault::remove(KEYRING_SERVICE, account).map_err(vault_error)?;
+        clear_keyring_credentials(account)
+    })
+    .await
+    .map_err(|_| AuthError::InternalError("credential store task failed".into()))?
+}
+
+fn clear_keyring_credentials(account: &str) -> Result<(), AuthError> {
+    match entry(account)?.delete_credential() {
         Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(error) if secret_vault::is_keyring_unavailable(&error) => Ok(()),
         Err(error) => Err(AuthError::InternalError(format!(
             "credential store clear failed: {error}"
         ))),
-    })
-    .await
-    .map_err(|_| AuthError::InternalError("credential store task failed".into()))?
+    }
 }
 
 #[derive(Clone, Debug, Default)]
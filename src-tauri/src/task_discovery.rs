@@ -0,0 +1,624 @@
+// src-tauri/src/task_discovery.rs
+//
+// Tasks a project already defines, so the command box can offer them
+// instead of having them typed by hand: package.json scripts (of the root
+// and of every workspace package), Makefile targets, justfile recipes and
+// Cargo aliases. Each task carries the command line and folder it runs with
+// and can be started by id through the command runner.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::command_runner::{self, CommandSessionInfo};
+use crate::package_manager::{self, PackageManager};
+use crate::terminal::TerminalSize;
+
+const MAKEFILES: &[&str] = &["GNUmakefile", "makefile", "Makefile"];
+const JUSTFILES: &[&str] = &["justfile", "Justfile", ".justfile"];
+const CARGO_CONFIGS: &[&str] = &[".cargo/config.toml", ".cargo/config"];
+/// How deep a `**` workspace pattern looks for packages.
+const MAX_WORKSPACE_DEPTH: usize = 4;
+const SKIPPED_DIRS: &[&str] = &["node_modules", ".git", "target", "dist"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TaskSource {
+    PackageScript,
+    Make,
+    Just,
+    CargoAlias,
+}
+
+impl TaskSource {
+    fn id(self) -> &'static str {
+        match self {
+            Self::PackageScript => "script",
+            Self::Make => "make",
+            Self::Just => "just",
+            Self::CargoAlias => "cargo",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectTask {
+    /// `source:name` for the project root, `source:dir#name` for a
+    /// workspace package.
+    pub id: String,
+    pub source: TaskSource,
+    pub name: String,
+    /// Folder the task runs in, relative to the project ("" for the root).
+    pub dir: String,
+    /// Workspace package name, for scripts of a workspace package.
+    pub package: Option<String>,
+    /// Command line the runner executes, package manager prefix included.
+    pub command: String,
+    /// What the script runs, the recipe's or target's doc comment, or the
+    /// alias expansion.
+    pub detail: Option<String>,
+}
+
+impl ProjectTask {
+    fn new(source: TaskSource, dir: &str, name: &str, command: String) -> Self {
+        let id = if dir.is_empty() {
+            format!("{}:{}", source.id(), name)
+        } else {
+            format!("{}:{}#{}", source.id(), dir, name)
+        };
+        Self {
+            id,
+            source,
+            name: name.to_string(),
+            dir: dir.to_string(),
+            package: None,
+            command,
+            detail: None,
+        }
+    }
+}
+
+/// Quote `value` for the shell when it has anything beyond plain name
+/// characters: single quotes for sh, so `$` and backticks stay literal, and
+/// double quotes for cmd.
+fn shell_word(value: &str) -> String {
+    let plain = value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || ":_-./@+=".contains(c));
+    if plain && !value.is_empty() {
+        value.to_string()
+    } else if cfg!(windows) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+fn read_json(path: &Path) -> Option<serde_json::Value> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+fn package_scripts(
+    root: &Path,
+    dir: &str,
+    manager: PackageManager,
+    workspace_package: bool,
+) -> Vec<ProjectTask> {
+    let Some(package) = read_json(&root.join(dir).join("package.json")) else {
+        return Vec::new();
+    };
+    let name = package
+        .get("name")
+        .and_then(|value| value.as_str())
+        .map(str::to_string);
+    let Some(scripts) = package.get("scripts").and_then(|value| value.as_object()) else {
+        return Vec::new();
+    };
+
+    scripts
+        .iter()
+        .map(|(script, body)| {
+            let mut args = vec![manager.name().to_string()];
+            args.extend(manager.run_args(script).iter().map(|arg| shell_word(arg)));
+            let mut task = ProjectTask::new(TaskSource::PackageScript, dir, script, args.join(" "));
+            task.detail = body.as_str().map(str::to_string);
+            if workspace_package {
+                task.package = name.clone();
+            }
+            task
+        })
+        .collect()
+}
+
+/// Workspace patterns from package.json (`workspaces` as a list or as
+/// `{ packages }`) and pnpm-workspace.yaml.
+fn workspace_patterns(root: &Path) -> Vec<String> {
+    let mut patterns = Vec::new();
+    if let Some(package) = read_json(&root.join("package.json")) {
+        let workspaces = package.get("workspaces");
+        let list = workspaces
+            .and_then(|value| value.as_array())
+            .or_else(|| workspaces?.get("packages")?.as_array());
+        patterns.extend(
+            list.into_iter()
+                .flatten()
+                .filter_map(|value| value.as_str().map(str::to_string)),
+        );
+    }
+
+    // Only the `packages:` list matters, so a line reader does instead of
+    // a YAML parser.
+    if let Ok(text) = fs::read_to_string(root.join("pnpm-workspace.yaml")) {
+        let mut in_packages = false;
+        for line in text.lines() {
+            let trimmed = line.trim();
+            if !line.starts_with([' ', '\t', '-']) && !trimmed.is_empty() {
+                in_packages = trimmed.starts_with("packages:");
+                continue;
+            }
+            if let Some(item) = trimmed.strip_prefix('-').filter(|_| in_packages) {
+                let item = item.split(" #").next().unwrap_or(item).trim();
+                patterns.push(item.trim_matches(['"', '\'']).to_string());
+            }
+        }
+    }
+    patterns
+}
+
+fn matches_segment(pattern: &str, name: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let (Some(first), Some(last)) = (parts.first(), parts.last()) else {
+        return false;
+    };
+    if parts.len() == 1 {
+        return pattern == name;
+    }
+    if !name.starts_with(first) || name.len() < first.len() + last.len() || !name.ends_with(last) {
+        return false;
+    }
+
+    let mut rest = &name[first.len()..name.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+fn child_dirs(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| !SKIPPED_DIRS.contains(&name))
+        })
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+fn expand_pattern(root: &Path, pattern: &str) -> Vec<PathBuf> {
+    let mut current = vec![root.to_path_buf()];
+    for segment in pattern.trim_start_matches("./").split('/') {
+        let mut next = Vec::new();
+        match segment {
+            "" | "." => continue,
+            "**" => {
+                let mut level = current.clone();
+                next.extend(level.iter().cloned());
+                for _ in 0..MAX_WORKSPACE_DEPTH {
+                    level = level.iter().flat_map(|dir| child_dirs(dir)).collect();
+                    next.extend(level.iter().cloned());
+                }
+            }
+            segment if segment.contains('*') => {
+                for dir in &current {
+                    next.extend(child_dirs(dir).into_iter().filter(|child| {
+                        child
+                            .file_name()
+                            .and_then(|name| name.to_str())
+                            .is_some_and(|name| matches_segment(segment, name))
+                    }));
+                }
+            }
+            segment => next.extend(
+                current
+                    .iter()
+                    .map(|dir| dir.join(segment))
+                    .filter(|dir| dir.is_dir()),
+            ),
+        }
+        current = next;
+    }
+    current
+}
+
+/// Workspace package folders relative to `root`, sorted, without the root.
+fn workspace_dirs(root: &Path) -> Vec<String> {
+    let mut included = BTreeMap::new();
+    let patterns = workspace_patterns(root);
+    for pattern in patterns.iter().filter(|pattern| !pattern.starts_with('!')) {
+        for dir in expand_pattern(root, pattern) {
+            if dir.join("package.json").is_file() {
+                if let Ok(relative) = dir.strip_prefix(root) {
+                    let relative = relative.to_string_lossy().replace('\\', "/");
+                    if !relative.is_empty() {
+                        included.insert(relative, ());
+                    }
+                }
+            }
+        }
+    }
+    for pattern in patterns
+        .iter()
+        .filter_map(|pattern| pattern.strip_prefix('!'))
+    {
+        for dir in expand_pattern(root, pattern) {
+            if let Ok(relative) = dir.strip_prefix(root) {
+                included.remove(&relative.to_string_lossy().replace('\\', "/"));
+            }
+        }
+    }
+    included.into_keys().collect()
+}
+
+/// `(target, doc)` pairs of a Makefile: explicit targets only, without
+/// special (`.PHONY`), pattern (`%.o`) and variable-built ones. The doc is
+/// a `## text` after the prerequisites or a comment line just above.
+fn make_targets(text: &str) -> Vec<(String, Option<String>)> {
+    let mut targets = Vec::new();
+    let mut comment: Option<String> = None;
+
+    for line in text.lines() {
+        if line.starts_with(['\t', ' ']) || line.trim().is_empty() {
+            comment = None;
+            continue;
+        }
+        if let Some(text) = line.strip_prefix('#') {
+            comment = Some(text.trim_start_matches('#').trim().to_string());
+            continue;
+        }
+
+        let doc = comment.take();
+        let Some((head, rest)) = line.split_once(':') else {
+            continue;
+        };
+        // `VAR := x`, `VAR ::= x` and `VAR :::= x` are assignments.
+        if rest.trim_start_matches(':').starts_with('=') || head.contains(['=', '$', '%']) {
+            continue;
+        }
+        let inline_doc = rest.split_once("##").map(|(_, doc)| doc.trim().to_string());
+        let doc = inline_doc.or(doc).filter(|doc| !doc.is_empty());
+        for target in head.split_whitespace() {
+            if !target.starts_with('.') && !targets.iter().any(|(t, _)| t == target) {
+                targets.push((target.to_string(), doc.clone()));
+            }
+        }
+    }
+    targets
+}
+
+/// `(recipe, doc)` pairs of a justfile, without private recipes (`_name`
+/// or `[private]`). Parameters without a default are listed in the doc,
+/// since the recipe cannot run without them.
+fn just_recipes(text: &str) -> Vec<(String, Option<String>)> {
+    let mut recipes = Vec::new();
+    let mut comment: Option<String> = None;
+    let mut private = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if line.starts_with([' ', '\t']) || trimmed.is_empty() {
+            comment = None;
+            private = false;
+            continue;
+        }
+        if let Some(text) = trimmed.strip_prefix('#') {
+            if !text.starts_with('!') {
+                comment = Some(text.trim().to_string());
+            }
+            continue;
+        }
+        if trimmed.starts_with('[') {
+            private |= trimmed.contains("private");
+            continue;
+        }
+
+        let doc = comment.take();
+        let hidden = std::mem::take(&mut private);
+        let first_word = trimmed.split_whitespace().next().unwrap_or_default();
+        if matches!(
+            first_word,
+            "alias" | "set" | "export" | "import" | "mod" | "import?" | "mod?"
+        ) {
+            continue;
+        }
+        let Some((header, rest)) = trimmed.split_once(':') else {
+            continue;
+        };
+        if rest.starts_with('=') {
+            continue;
+        }
+
+        let mut words = header.split_whitespace();
+        let Some(name) = words.next().map(|name| name.trim_start_matches('@')) else {
+            continue;
+        };
+        let valid = name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid || name.is_empty() || name.starts_with('_') || hidden {
+            continue;
+        }
+
+        let required: Vec<&str> = words
+            .filter(|param| !param.contains('=') && !param.starts_with('*'))
+            .map(|param| param.trim_start_matches(['+', '$']))
+            .collect();
+        let doc = match (doc.filter(|doc| !doc.is_empty()), required.is_empty()) {
+            (doc, true) => doc,
+            (Some(doc), false) => Some(format!("{} (needs {})", doc, required.join(", "))),
+            (None, false) => Some(format!("Needs {}", required.join(", "))),
+        };
+        recipes.push((name.to_string(), doc));
+    }
+    recipes
+}
+
+/// `(alias, expansion)` pairs from the `[alias]` table of a Cargo config.
+fn cargo_aliases(text: &str) -> Vec<(String, String)> {
+    let Ok(config) = text.parse::<toml::Table>() else {
+        return Vec::new();
+    };
+    let Some(aliases) = config.get("alias").and_then(|value| value.as_table()) else {
+        return Vec::new();
+    };
+    aliases
+        .iter()
+        .filter_map(|(name, value)| {
+            let expansion = match value {
+                toml::Value::String(text) => text.clone(),
+                toml::Value::Array(items) => items
+                    .iter()
+                    .filter_map(|item| item.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                _ => return None,
+            };
+            Some((name.clone(), expansion))
+        })
+        .collect()
+}
+
+fn first_file(root: &Path, names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .find_map(|name| fs::read_to_string(root.join(name)).ok())
+}
+
+/// Every task the project defines, package scripts first (root, then each
+/// workspace package), then Make, just and Cargo.
+pub fn discover(root: &Path) -> Vec<ProjectTask> {
+    let manager = package_manager::for_project(root).manager;
+    let mut tasks = package_scripts(root, "", manager, false);
+    for dir in workspace_dirs(root) {
+        tasks.extend(package_scripts(root, &dir, manager, true));
+    }
+
+    // make looks for the same file names in the same order on its own.
+    if let Some(text) = first_file(root, MAKEFILES) {
+        tasks.extend(make_targets(&text).into_iter().map(|(target, doc)| {
+            let mut task = ProjectTask::new(
+                TaskSource::Make,
+                "",
+                &target,
+                format!("make {}", shell_word(&target)),
+            );
+            task.detail = doc;
+            task
+        }));
+    }
+
+    if let Some(text) = first_file(root, JUSTFILES) {
+        tasks.extend(just_recipes(&text).into_iter().map(|(recipe, doc)| {
+            let mut task =
+                ProjectTask::new(TaskSource::Just, "", &recipe, format!("just {}", recipe));
+            task.detail = doc;
+            task
+        }));
+    }
+
+    if let Some(text) = first_file(root, CARGO_CONFIGS) {
+        tasks.extend(cargo_aliases(&text).into_iter().map(|(alias, expansion)| {
+            let mut task = ProjectTask::new(
+                TaskSource::CargoAlias,
+                "",
+                &alias,
+                format!("cargo {}", shell_word(&alias)),
+            );
+            task.detail = Some(expansion);
+            task
+        }));
+    }
+
+    tasks
+}
+
+fn project_root(project_path: &str) -> Result<PathBuf, String> {
+    let root = PathBuf::from(project_path.trim());
+    if project_path.trim().is_empty() || !root.is_dir() {
+        return Err(format!("Project folder not found: {}", project_path));
+    }
+    Ok(root)
+}
+
+#[tauri::command]
+pub fn task_discover(project_path: String) -> Result<Vec<ProjectTask>, String> {
+    Ok(discover(&project_root(&project_path)?))
+}
+
+/// Start a discovered task as a command session, in its own folder and
+/// named after it. Takes the same `terminal` and `confirmation_token` as
+/// `command_run`.
+#[tauri::command]
+pub fn task_run(
+    app: AppHandle,
+    project_path: String,
+    task_id: String,
+    terminal: Option<TerminalSize>,
+    confirmation_token: Option<String>,
+) -> Result<CommandSessionInfo, String> {
+    let root = project_root(&project_path)?;
+    let task = discover(&root)
+        .into_iter()
+        .find(|task| task.id == task_id)
+        .ok_or_else(|| format!("No task with id {} in this project", task_id))?;
+
+    command_runner::start_session(
+        &app,
+        &task.command,
+        root.join(&task.dir).to_string_lossy().into_owned(),
+        Some(task.name),
+        terminal,
+        confirmation_token.as_deref(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::tempdir;
+
+    use super::{discover, just_recipes, make_targets, shell_word};
+
+    #[test]
+    fn parses_make_targets_and_just_recipes() {
+        let makefile = "\
+CC := gcc
+LD ::= ld
+AR:::=ar
+.PHONY: build test
+# Build everything
+build: deps
+\t$(CC) main.c
+test lint: build ## Run checks
+%.o: %.c
+\t$(CC) -c $<
+$(OUT): build
+";
+        assert_eq!(
+            make_targets(makefile),
+            vec![
+                ("build".to_string(), Some("Build everything".to_string())),
+                ("test".to_string(), Some("Run checks".to_string())),
+                ("lint".to_string(), Some("Run checks".to_string())),
+            ]
+        );
+
+        let justfile = "\
+set dotenv-load
+version := \"1.0\"
+alias b := build
+
+# Compile the app
+build:
+    cargo build
+
+[private]
+helper:
+    echo hidden
+
+_internal:
+    echo hidden
+
+deploy env target=\"web\" *flags: build
+    ./deploy.sh {{env}}
+";
+        assert_eq!(
+            just_recipes(justfile),
+            vec![
+                ("build".to_string(), Some("Compile the app".to_string())),
+                ("deploy".to_string(), Some("Needs env".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn quotes_words_that_are_not_plain_names() {
+        assert_eq!(shell_word("build:web"), "build:web");
+        if cfg!(windows) {
+            assert_eq!(shell_word("say \"hi\""), "\"say \"\"hi\"\"\"");
+        } else {
+            assert_eq!(shell_word("echo $HOME"), "'echo $HOME'");
+            assert_eq!(shell_word("it's"), "'it'\\''s'");
+        }
+    }
+
+    #[test]
+    fn discovers_workspace_scripts_with_the_project_package_manager() {
+        let project = tempdir().unwrap();
+        let root = project.path();
+        fs::write(
+            root.join("package.json"),
+            r#"{"workspaces":["packages/*"],"scripts":{"dev:web":"vite"}}"#,
+        )
+        .unwrap();
+        fs::write(root.join("pnpm-lock.yaml"), "").unwrap();
+        fs::write(
+            root.join("pnpm-workspace.yaml"),
+            "packages:\n  - 'apps/**'\n  - '!apps/legacy'\ncatalog:\n  - nope\n",
+        )
+        .unwrap();
+        for (dir, name) in [
+            ("packages/ui", "@acme/ui"),
+            ("apps/site/web", "site"),
+            ("apps/legacy", "legacy"),
+        ] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(
+                root.join(dir).join("package.json"),
+                format!(r#"{{"name":"{name}","scripts":{{"build":"tsc"}}}}"#),
+            )
+            .unwrap();
+        }
+        fs::create_dir_all(root.join(".cargo")).unwrap();
+        fs::write(
+            root.join(".cargo/config.toml"),
+            "[alias]\nxtask = \"run -p xtask --\"\nci = [\"test\", \"--workspace\"]\n",
+        )
+        .unwrap();
+
+        let tasks = discover(root);
+        let summary: Vec<(&str, &str, &str)> = tasks
+            .iter()
+            .map(|task| (task.id.as_str(), task.dir.as_str(), task.command.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("script:dev:web", "", "pnpm dev:web"),
+                ("script:apps/site/web#build", "apps/site/web", "pnpm build"),
+                ("script:packages/ui#build", "packages/ui", "pnpm build"),
+                ("cargo:ci", "", "cargo ci"),
+                ("cargo:xtask", "", "cargo xtask"),
+            ]
+        );
+        assert_eq!(tasks[2].package.as_deref(), Some("@acme/ui"));
+        assert_eq!(tasks[3].detail.as_deref(), Some("test --workspace"));
+    }
+}
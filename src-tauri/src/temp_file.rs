@@ -0,0 +1,25 @@
+// src-tauri/src/temp_file.rs
+//
+// Names for the temp files that atomic writes stage next to their target
+// before renaming it into place.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+static NEXT: AtomicU64 = AtomicU64::new(0);
+
+/// A hidden temp path beside `path`, unique per process and call, so it
+/// never lands on a real sibling (`keys.tmp`) or on another writer's file.
+pub fn sibling(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(
+        ".{name}.{}.{}.tmp",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ))
+}
@@ -0,0 +1,331 @@
+// src-tauri/src/terminal.rs
+//
+// Pseudo-terminals for command runner sessions. Interactive tools
+// (`npx create-*` prompts, `gh auth login`, `supabase login`) check whether
+// they talk to a terminal and hang or drop their colors on plain pipes.
+// Here they get a real one: output is passed on raw, escape sequences
+// intact, for a terminal emulator in the frontend to render, and input and
+// window size come back from it.
+
+use std::{
+    io::{Read, Write},
+    sync::Mutex,
+};
+
+use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct TerminalSize {
+    pub cols: u16,
+    pub rows: u16,
+}
+
+impl TerminalSize {
+    fn pty_size(self) -> PtySize {
+        PtySize {
+            rows: self.rows.max(1),
+            cols: self.cols.max(1),
+            pixel_width: 0,
+            pixel_height: 0,
+        }
+    }
+}
+
+/// The controlling side of a session's pseudo-terminal.
+pub struct Terminal {
+    master: Mutex<Box<dyn MasterPty + Send>>,
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl Terminal {
+    /// Send keystrokes or pasted text to the program.
+    pub fn write(&self, data: &[u8]) -> Result<(), String> {
+        let mut writer = self
+            .writer
+            .lock()
+            .map_err(|_| "Terminal lock failed".to_string())?;
+        writer
+            .write_all(data)
+            .and_then(|_| writer.flush())
+            .map_err(|e| format!("Failed to write to terminal: {}", e))
+    }
+
+    pub fn resize(&self, size: TerminalSize) -> Result<(), String> {
+        self.master
+            .lock()
+            .map_err(|_| "Terminal lock failed".to_string())?
+            .resize(size.pty_size())
+            .map_err(|e| format!("Failed to resize terminal: {}", e))
+    }
+}
+
+/// Expand `%NAME%` references the way cmd does on its command line, since
+/// text that comes out of a variable is not expanded a second time. Unknown
+/// names are left as written.
+#[cfg(target_os = "windows")]
+fn expand_variables(command: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut expanded = String::with_capacity(command.len());
+    let mut rest = command;
+    while let Some(start) = rest.find('%') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after
+            .find('%')
+            .filter(|&end| end > 0)
+            .and_then(|end| lookup(&after[..end]).map(|value| (value, end)));
+        match value {
+            Some((value, end)) => {
+                expanded.push_str(&value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                expanded.push('%');
+                rest = after;
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// A command started in a fresh pseudo-terminal.
+pub struct TerminalProcess {
+    pub terminal: Terminal,
+    pub child: Box<dyn Child + Send + Sync>,
+    pub output: Box<dyn Read + Send>,
+}
+
+/// Run `command` through the platform shell inside a new pseudo-terminal.
+pub fn spawn(command: &str, cwd: &str, size: TerminalSize) -> Result<TerminalProcess, String> {
+    let pair = native_pty_system()
+        .openpty(size.pty_size())
+        .map_err(|e| format!("Failed to open a terminal: {}", e))?;
+
+    // ConPTY quotes every argument with backslash escapes, which cmd does
+    // not understand. The command goes in a variable that cmd expands while
+    // reading its own command line, so it parses the text as typed, just as
+    // the piped runner passes it with `raw_arg`.
+    #[cfg(target_os = "windows")]
+    let mut shell = {
+        let mut shell = CommandBuilder::new("cmd");
+        shell.args(["/D", "/S", "/C", "%KFORGE_COMMAND%"]);
+        shell.env(
+            "KFORGE_COMMAND",
+            expand_variables(command, |name| std::env::var(name).ok()),
+        );
+        shell
+    };
+
+    #[cfg(not(target_os = "windows"))]
+    let mut shell = {
+        let mut shell = CommandBuilder::new("sh");
+        shell.args(["-lc", command]);
+        shell
+    };
+
+    shell.cwd(cwd);
+    shell.env("TERM", "xterm-256color");
+    shell.env("COLORTERM", "truecolor");
+
+    let child = pair
+        .slave
+        .spawn_command(shell)
+        .map_err(|e| format!("Failed to start: {}", e))?;
+    // Only the child may hold the terminal's slave side, or reading the
+    // output never reaches its end.
+    drop(pair.slave);
+
+    let output = pair
+        .master
+        .try_clone_reader()
+        .map_err(|e| format!("Failed to read from terminal: {}", e))?;
+    let writer = pair
+        .master
+        .take_writer()
+        .map_err(|e| format!("Failed to write to terminal: {}", e))?;
+
+    Ok(TerminalProcess {
+        terminal: Terminal {
+            master: Mutex::new(pair.master),
+            writer: Mutex::new(writer),
+        },
+        child,
+        output,
+    })
+}
+
+/// Turns raw terminal reads into text without splitting a multi-byte
+/// character that straddles two reads.
+#[derive(Default)]
+pub struct Utf8Decoder {
+    pending: Vec<u8>,
+}
+
+impl Utf8Decoder {
+    pub fn decode(&mut self, bytes: &[u8]) -> String {
+        self.pending.extend_from_slice(bytes);
+        let mut text = String::with_capacity(self.pending.len());
+        let mut rest = self.pending.as_slice();
+
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    text.push_str(valid);
+                    rest = &[];
+                    break;
+                }
+                Err(err) => {
+                    let (valid, after) = rest.split_at(err.valid_up_to());
+                    // Checked by `from_utf8` above.
+                    text.push_str(std::str::from_utf8(valid).unwrap_or_default());
+                    match err.error_len() {
+                        Some(invalid) => {
+                            text.push(char::REPLACEMENT_CHARACTER);
+                            rest = &after[invalid..];
+                        }
+                        // A character cut off by the end of this read.
+                        None => {
+                            rest = after;
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.pending = rest.to_vec();
+        text
+    }
+}
+
+/// Splits terminal output into log lines. A carriage return without a
+/// newline redraws the line (progress bars), so only the last redraw of a
+/// line is kept.
+#[derive(Default)]
+pub struct LineSplitter {
+    partial: String,
+}
+
+impl LineSplitter {
+    pub fn push(&mut self, text: &str) -> Vec<String> {
+        self.partial.push_str(text);
+        let mut lines = Vec::new();
+        while let Some(end) = self.partial.find('\n') {
+            let line: String = self.partial.drain(..=end).collect();
+            lines.push(last_redraw(&line[..end]).to_string());
+        }
+        lines
+    }
+
+    /// Whatever is left once the output has ended.
+    pub fn finish(&mut self) -> Option<String> {
+        let rest = std::mem::take(&mut self.partial);
+        let line = last_redraw(&rest);
+        (!line.is_empty()).then(|| line.to_string())
+    }
+}
+
+fn last_redraw(line: &str) -> &str {
+    let line = line.trim_end_matches('\r');
+    line.rsplit('\r').next().unwrap_or(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineSplitter, Utf8Decoder};
+
+    #[test]
+    fn decodes_characters_split_across_reads_and_keeps_last_redraw() {
+        let mut decoder = Utf8Decoder::default();
+        let bytes = "✓ done\r\n".as_bytes();
+        assert_eq!(decoder.decode(&bytes[..1]), "");
+        assert_eq!(decoder.decode(&bytes[1..]), "✓ done\r\n");
+        assert_eq!(decoder.decode(&[b'a', 0xff, b'b']), "a\u{fffd}b");
+
+        let mut lines = LineSplitter::default();
+        assert_eq!(
+            lines.push("10%\r50%\r100%\r\nnext"),
+            vec!["100%".to_string()]
+        );
+        assert!(lines.push(" line").is_empty());
+        assert_eq!(lines.finish(), Some("next line".to_string()));
+        assert_eq!(lines.finish(), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_commands_in_a_terminal_with_escape_sequences_intact() {
+        use super::{spawn, TerminalSize};
+        use std::io::Read;
+
+        let mut process = spawn(
+            "test -t 1 && printf '\\033[31mtty\\033[0m'; read answer; echo \"got $answer\"",
+            "/",
+            TerminalSize {
+                cols: 100,
+                rows: 30,
+            },
+        )
+        .unwrap();
+        process.terminal.write(b"yes\n").unwrap();
+        process
+            .terminal
+            .resize(TerminalSize {
+                cols: 120,
+                rows: 40,
+            })
+            .unwrap();
+        let status = process.child.wait().unwrap();
+        assert!(status.success());
+
+        let mut output = Vec::new();
+        let mut buffer = [0u8; 1024];
+        while let Ok(read) = process.output.read(&mut buffer) {
+            if read == 0 {
+                break;
+            }
+            output.extend_from_slice(&buffer[..read]);
+        }
+        let output = String::from_utf8_lossy(&output);
+        assert!(output.contains("\u{1b}[31mtty\u{1b}[0m"), "{output}");
+        assert!(output.contains("got yes"), "{output}");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn passes_quoted_arguments_to_cmd_unchanged() {
+        use super::{expand_variables, spawn, TerminalSize};
+        use std::io::Read;
+
+        let lookup = |name: &str| (name == "APP").then(|| "demo".to_string());
+        assert_eq!(
+            expand_variables("echo %APP% 50%% %MISSING%", lookup),
+            "echo demo 50%% %MISSING%"
+        );
+
+        let mut process = spawn(
+            "echo \"my app\" & echo %COMSPEC%",
+            "C:\\",
+            TerminalSize {
+                cols: 100,
+                rows: 30,
+            },
+        )
+        .unwrap();
+        assert!(process.child.wait().unwrap().success());
+
+        let mut output = Vec::new();
+        let mut buffer = [0u8; 1024];
+        while let Ok(read) = process.output.read(&mut buffer) {
+            if read == 0 {
+                break;
+            }
+            output.extend_from_slice(&buffer[..read]);
+        }
+        let output = String::from_utf8_lossy(&output);
+        assert!(output.contains("\"my app\""), "{output}");
+        assert!(!output.contains("\\\"my app"), "{output}");
+        assert!(output.to_lowercase().contains("cmd.exe"), "{output}");
+    }
+}
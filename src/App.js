@@ -2479,6 +2479,9 @@ export default function App() {
       const ep = (endpoints[aiProvider] || "").trim();
       if (ep) req.endpoint = ep;
 
+      // Lets the backend pick this project's credential profile, if assigned.
+      if (projectPath) req.project_path = projectPath;
+
       return { ...req, ...override };
     },
     [
@@ -1,7 +1,8 @@
-import React, { useEffect, useMemo, useRef, useState } from "react";
+import React, { useCallback, useEffect, useMemo, useRef, useState } from "react";
 import {
   appendCommandLog,
   clearCommandLogBuffer,
+  commandCheck,
   commandRun,
   commandStop,
   getCommandLogBuffer,
@@ -163,12 +164,43 @@ export default function CommandRunnerPanel({
 
   const endRef = useRef(null);
   const inputRef = useRef(null);
+  // Session started from this panel; other sessions' events are ignored.
+  const sessionRef = useRef(null);
+  // Events that arrive while a run is pending and its session id unknown.
+  const pendingEventsRef = useRef(null);
 
   useEffect(() => {
     setLogs(getCommandLogBuffer());
     setStatus(getCommandStatusValue());
   }, [projectPath]);
 
+  const showLog = useCallback((payload) => {
+    appendCommandLog({
+      kind: "stdout",
+      line: String(payload?.line ?? ""),
+      ts: Date.now(),
+    });
+    setLogs(getCommandLogBuffer());
+  }, []);
+
+  const showStatus = useCallback((payload) => {
+    const nextStatus = String(payload?.status ?? "idle");
+    setCommandStatusValue(nextStatus);
+    setStatus(nextStatus);
+  }, []);
+
+  // Events without a session id, or from another session, never reach this
+  // panel. While a run is pending they are held until its id is known.
+  const routeEvent = useCallback((show, payload) => {
+    const sessionId = payload?.sessionId;
+    if (!sessionId) return;
+    if (pendingEventsRef.current) {
+      pendingEventsRef.current.push({ show, payload });
+      return;
+    }
+    if (sessionId === sessionRef.current) show(payload);
+  }, []);
+
   useEffect(() => {
     let unLog;
     let unStatus;
@@ -176,22 +208,7 @@ export default function CommandRunnerPanel({
 
     (async () => {
       const logUnlisten = await onCommandLog((payload) => {
-        const line =
-          typeof payload === "string"
-            ? payload
-            : String(payload?.line ?? payload ?? "");
-
-        const entry = {
-          kind: "stdout",
-          line,
-          ts: Date.now(),
-        };
-
-        appendCommandLog(entry);
-
-        if (!cancelled) {
-          setLogs(getCommandLogBuffer());
-        }
+        if (!cancelled) routeEvent(showLog, payload);
       });
 
       if (cancelled) {
@@ -201,16 +218,7 @@ export default function CommandRunnerPanel({
       }
 
       const statusUnlisten = await onCommandStatus((payload) => {
-        const nextStatus =
-          typeof payload === "string"
-            ? payload
-            : String(payload?.status ?? "idle");
-
-        setCommandStatusValue(nextStatus);
-
-        if (!cancelled) {
-          setStatus(nextStatus);
-        }
+        if (!cancelled) routeEvent(showStatus, payload);
       });
 
       if (cancelled) {
@@ -237,7 +245,7 @@ export default function CommandRunnerPanel({
         });
       }
     };
-  }, []);
+  }, [routeEvent, showLog, showStatus]);
 
   useEffect(() => {
     endRef.current?.scrollIntoView({
@@ -282,8 +290,39 @@ export default function CommandRunnerPanel({
     appendCommandLog(entry);
     setLogs(getCommandLogBuffer());
 
+    sessionRef.current = null;
+
     try {
-      await commandRun(trimmed, projectPath);
+      const check = await commandCheck(trimmed, projectPath);
+      if (check?.auditError) {
+        appendCommandLog({ kind: "stderr", line: check.auditError, ts: Date.now() });
+        setLogs(getCommandLogBuffer());
+      }
+      if (check?.decision === "deny") {
+        throw new Error(`Blocked by the command policy: ${check.reasons.join("; ")}`);
+      }
+
+      let confirmationToken;
+      if (check?.decision === "confirm") {
+        const confirmed = window.confirm(
+          `This command can destroy data:\n\n${check.reasons.join("\n")}\n\nRun it anyway?`,
+        );
+        if (!confirmed) {
+          appendCommandLog({ kind: "status", line: "Command cancelled.", ts: Date.now() });
+          setLogs(getCommandLogBuffer());
+          return;
+        }
+        confirmationToken = check.confirmationToken;
+      }
+
+      pendingEventsRef.current = [];
+      const session = await commandRun(trimmed, projectPath, { confirmationToken });
+      sessionRef.current = session?.id ?? null;
+      const pending = pendingEventsRef.current || [];
+      pendingEventsRef.current = null;
+      pending
+        .filter(({ payload }) => payload.sessionId === sessionRef.current)
+        .forEach(({ show, payload }) => show(payload));
       setCommand("");
       setSelectedCommand(null);
       inputRef.current?.focus();
@@ -296,12 +335,14 @@ export default function CommandRunnerPanel({
 
       appendCommandLog(errorEntry);
       setLogs(getCommandLogBuffer());
+    } finally {
+      pendingEventsRef.current = null;
     }
   }
 
   async function handleStop() {
     try {
-      await commandStop();
+      if (sessionRef.current) await commandStop(sessionRef.current);
       inputRef.current?.focus();
     } catch (e) {
       const errorEntry = {
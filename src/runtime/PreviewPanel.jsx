@@ -16,6 +16,7 @@ import {
   previewDetectTemplates,
   previewGetStatus,
   previewInstall,
+  previewList,
   previewStart,
   previewStop,
   setPreviewStatusValue,
@@ -135,6 +136,11 @@ export default function PreviewPanel({ projectPath, onCopyTextChange }) {
   const endRef = useRef(null);
 
   const lastLogKeyRef = useRef("");
+  // Previews of other projects run side by side; only this project's
+  // preview (and scaffold messages, which carry no preview id) is shown.
+  const previewIdRef = useRef(null);
+  // Log events that arrive while a start is pending and its id unknown.
+  const pendingLogsRef = useRef(null);
 
   const [activeScaffold, setActiveScaffold] = useState("");
   const [generateMenuOpen, setGenerateMenuOpen] = useState(false);
@@ -206,14 +212,40 @@ export default function PreviewPanel({ projectPath, onCopyTextChange }) {
     };
   }, [refreshProjectShape]);
 
+  const showLog = useCallback(({ kind, line }) => {
+    const raw = String(line ?? "");
+    const text = raw.replace(/\x1b\[[0-9;]*m/g, "");
+    if (CLI_HINT_RE.some((r) => r.test(text))) {
+      return;
+    }
+
+    const key = `${kind}|${text}`;
+    if (key === lastLogKeyRef.current) return;
+    lastLogKeyRef.current = key;
+
+    const entry = { kind, line: text, ts: Date.now() };
+    appendPreviewLog(entry);
+    setLogs(getPreviewLogBuffer());
+  }, []);
+
   useEffect(() => {
     let unLog;
     let unStatus;
     let cancelled = false;
+    previewIdRef.current = null;
+    pendingLogsRef.current = null;
 
     (async () => {
       try {
-        const currentStatus = await previewGetStatus();
+        const [currentStatus, previews] = await Promise.all([
+          projectPath ? previewGetStatus(projectPath) : "idle",
+          previewList(),
+        ]);
+        const current = (previews || []).find(
+          (preview) => preview.projectPath === projectPath,
+        );
+        if (!cancelled) previewIdRef.current = current?.id ?? null;
+
         const nextStatus = currentStatus || "idle";
         setPreviewStatusValue(nextStatus);
         if (!cancelled) setStatus(nextStatus);
@@ -222,20 +254,15 @@ export default function PreviewPanel({ projectPath, onCopyTextChange }) {
         if (!cancelled) setStatus("idle");
       }
 
-      const logUnlisten = await onPreviewLog(({ kind, line }) => {
-        const raw = String(line ?? "");
-        const text = raw.replace(/\x1b\[[0-9;]*m/g, "");
-        if (CLI_HINT_RE.some((r) => r.test(text))) {
+      const logUnlisten = await onPreviewLog((payload) => {
+        const previewId = payload?.previewId;
+        if (previewId && previewId !== previewIdRef.current) {
+          if (!previewIdRef.current && pendingLogsRef.current) {
+            pendingLogsRef.current.push(payload);
+          }
           return;
         }
-
-        const key = `${kind}|${text}`;
-        if (key === lastLogKeyRef.current) return;
-        lastLogKeyRef.current = key;
-
-        const entry = { kind, line: text, ts: Date.now() };
-        appendPreviewLog(entry);
-        setLogs(getPreviewLogBuffer());
+        showLog(payload);
       });
 
       if (cancelled) {
@@ -244,9 +271,19 @@ export default function PreviewPanel({ projectPath, onCopyTextChange }) {
         unLog = logUnlisten;
       }
 
-      const statusUnlisten = await onPreviewStatus(({ status }) => {
-        const nextStatus = String(status || "idle");
+      const statusUnlisten = await onPreviewStatus((payload) => {
         if (cancelled) return;
+        const previewId = payload?.previewId;
+        if (
+          previewId &&
+          !previewIdRef.current &&
+          payload?.preview?.projectPath === projectPath
+        ) {
+          previewIdRef.current = previewId;
+        }
+        if (previewId && previewId !== previewIdRef.current) return;
+
+        const nextStatus = String(payload?.status || "idle");
 
         setPreviewStatusValue(nextStatus);
         setStatus(nextStatus);
@@ -286,7 +323,7 @@ export default function PreviewPanel({ projectPath, onCopyTextChange }) {
         });
       }
     };
-  }, [refreshProjectShape]);
+  }, [projectPath, refreshProjectShape, showLog]);
 
   useEffect(() => {
     endRef.current?.scrollIntoView({
@@ -381,6 +418,24 @@ export default function PreviewPanel({ projectPath, onCopyTextChange }) {
     await invoke("open_url", { url: previewUrl });
   }
 
+  // Runs `start` (install or preview) and follows the preview it creates,
+  // including the log lines sent before its id came back.
+  async function trackPreview(start) {
+    previewIdRef.current = null;
+    pendingLogsRef.current = [];
+    try {
+      const info = await start();
+      previewIdRef.current = info?.id ?? previewIdRef.current;
+      const pending = pendingLogsRef.current || [];
+      pendingLogsRef.current = null;
+      pending
+        .filter((payload) => payload.previewId === previewIdRef.current)
+        .forEach(showLog);
+    } finally {
+      pendingLogsRef.current = null;
+    }
+  }
+
   async function handlePreview() {
     if (disabled || !isRunnerIdle) return;
 
@@ -389,7 +444,7 @@ export default function PreviewPanel({ projectPath, onCopyTextChange }) {
       return;
     }
 
-    await previewStart(targetPath);
+    await trackPreview(() => previewStart(targetPath));
   }
 
   async function handleGenerateTemplate(template) {
@@ -527,7 +582,7 @@ export default function PreviewPanel({ projectPath, onCopyTextChange }) {
             <button
               className="px-3 py-1.5 rounded-lg bg-zinc-800 hover:bg-zinc-700 text-zinc-100 text-sm disabled:opacity-40"
               disabled={disabled || !isRunnerIdle}
-              onClick={() => previewInstall(targetPath)}
+              onClick={() => trackPreview(() => previewInstall(targetPath))}
               title="Run install for projects that need dependencies"
             >
               Install
@@ -554,7 +609,7 @@ export default function PreviewPanel({ projectPath, onCopyTextChange }) {
             disabled={
               status === "idle" || String(status).startsWith("scaffold:")
             }
-            onClick={() => previewStop()}
+            onClick={() => previewStop({ projectPath: targetPath })}
             title="Stop the running preview process"
           >
             Stop
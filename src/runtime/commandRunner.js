@@ -28,17 +28,126 @@ export function onCommandLog(cb) {
   return listen("kforge://command/log", (event) => cb(event.payload));
 }
 
+// Backlog kept in Rust for one session; its id is the log runner id.
+export async function commandLogRecent(sessionId, { limit, afterSeq } = {}) {
+  return invoke("log_recent", { runner: sessionId, limit, afterSeq });
+}
+
+// Omit runner to search every preview and command session.
+export async function logSearch(query, { runner, limit } = {}) {
+  return invoke("log_search", { query, runner, limit });
+}
+
+export async function logExport(runner, path) {
+  return invoke("log_export", { runner, path });
+}
+
 export function onCommandStatus(cb) {
   return listen("kforge://command/status", (event) => cb(event.payload));
 }
 
-export async function commandRun(command, cwd) {
+// Starts a new session alongside any running ones and resolves to its info
+// ({ id, name, command, cwd, status, exitCode, ... }). Log and status events
+// carry the session id. Pass terminal: { cols, rows } to run in a
+// pseudo-terminal; its raw output then arrives through onCommandOutput.
+// Destructive commands need the confirmationToken from commandCheck.
+export async function commandRun(
+  command,
+  cwd,
+  { name, terminal, confirmationToken } = {},
+) {
   return invoke("command_run", {
     command,
     cwd,
+    name,
+    terminal,
+    confirmationToken,
+  });
+}
+
+// Resolves to { decision: "allow" | "confirm" | "deny", reasons,
+// confirmationToken, expiresAtMs, auditError }.
+export async function commandCheck(command, cwd) {
+  return invoke("command_check", { command, cwd });
+}
+
+// Policy is { deny: [patterns], allow: [patterns] }; "*" matches anything.
+// Both resolve to the policy plus { loadError, auditError }; while
+// loadError is set every command is blocked until a policy is saved.
+export async function commandPolicyGet() {
+  return invoke("command_policy_get");
+}
+
+export async function commandPolicySet(policy) {
+  return invoke("command_policy_set", { policy });
+}
+
+export async function commandAuditLog({ limit } = {}) {
+  return invoke("command_audit_log", { limit });
+}
+
+// Payload is { sessionId, data } with escape sequences intact, for a
+// terminal emulator.
+export function onCommandOutput(cb) {
+  return listen("kforge://command/output", (event) => cb(event.payload));
+}
+
+export async function commandWrite(sessionId, data) {
+  return invoke("command_write", { sessionId, data });
+}
+
+export async function commandResize(sessionId, { cols, rows }) {
+  return invoke("command_resize", { sessionId, size: { cols, rows } });
+}
+
+// Payload: { sessionId, status, success, exitCode, signal, durationMs,
+// stdoutBytes, stdoutLines, stderrBytes, stderrLines } once a session ends.
+export function onCommandComplete(cb) {
+  return listen("kforge://command/complete", (event) => cb(event.payload));
+}
+
+// Runs to completion and resolves to the completion payload plus
+// { stdout, stderr, truncated, timedOut }.
+export async function commandCapture(
+  command,
+  cwd,
+  { name, timeoutSecs, confirmationToken } = {},
+) {
+  return invoke("command_capture", {
+    command,
+    cwd,
+    name,
+    timeoutSecs,
+    confirmationToken,
   });
 }
 
-export async function commandStop() {
-  return invoke("command_stop");
+export async function commandSessions() {
+  return invoke("command_sessions");
+}
+
+// With no session id every running session is stopped.
+export async function commandStop(sessionId) {
+  return invoke("command_stop", { sessionId });
+}
+
+// Resolves to [{ id, source, name, dir, package, command, detail }] for the
+// project's package.json scripts (workspaces included), Makefile targets,
+// justfile recipes and Cargo aliases.
+export async function taskDiscover(projectPath) {
+  return invoke("task_discover", { projectPath });
+}
+
+// Starts a discovered task as a command session; resolves like commandRun.
+export async function taskRun(
+  projectPath,
+  taskId,
+  { terminal, confirmationToken } = {},
+) {
+  return invoke("task_run", {
+    projectPath,
+    taskId,
+    terminal,
+    confirmationToken,
+  });
 }
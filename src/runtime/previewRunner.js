@@ -35,10 +35,26 @@ export function onPreviewLog(cb) {
   return listen("kforge://preview/log", (event) => cb(event.payload));
 }
 
+// Backlog kept in Rust: runner is a preview id, or "preview" for messages
+// that belong to no preview.
+export async function previewLogRecent(runner, { limit, afterSeq } = {}) {
+  return invoke("log_recent", { runner, limit, afterSeq });
+}
+
 export function onPreviewStatus(cb) {
   return listen("kforge://preview/status", (event) => cb(event.payload));
 }
 
+// Payload is { previewId, request } for each request a static preview served.
+export function onPreviewRequest(cb) {
+  return listen("kforge://preview/request", (event) => cb(event.payload));
+}
+
+// query: { afterSeq, problemsOnly, path, limit }, all optional.
+export async function previewRequests(previewId, query = {}) {
+  return invoke("preview_requests", { previewId, query });
+}
+
 export async function previewDetectKind(projectPath) {
   return invoke("preview_detect_kind", { projectPath });
 }
@@ -124,18 +140,42 @@ export async function previewDetectTemplates(projectPath) {
   };
 }
 
-export async function previewGetStatus() {
-  return invoke("preview_get_status");
+export async function previewGetStatus(projectPath) {
+  return invoke("preview_get_status", { projectPath });
 }
 
-export async function previewInstall(projectPath) {
-  return invoke("preview_install", { projectPath });
+export async function previewList() {
+  return invoke("preview_list");
 }
 
-export async function previewStart(projectPath) {
-  return invoke("preview_start", { projectPath });
+export async function previewInstall(projectPath) {
+  return invoke("preview_install", { projectPath });
 }
 
-export async function previewStop() {
-  return invoke("preview_stop");
+// mode: "dev" (default) or "production" (build, then serve the output).
+// port, exposeOnLan, proxy, https and liveReload configure KForge's static
+// server; liveReload: false turns off reloading for static dev previews.
+// proxy: [{ path: "/api/*", target: "http://localhost:8787", stripPrefix }]
+// supervise: { maxRestarts, healthCheckSecs } restarts a crashed dev server.
+export async function previewStart(
+  projectPath,
+  { mode, port, exposeOnLan, proxy, https, liveReload, supervise } = {},
+) {
+  return invoke("preview_start", {
+    projectPath,
+    mode,
+    serve: { port, exposeOnLan, proxy, https, liveReload },
+    supervise,
+  });
+}
+
+// { certPath, hosts, expiresAt } of the self-signed certificate HTTPS
+// previews use; created on first call.
+export async function previewHttpsCertificate() {
+  return invoke("preview_https_certificate");
+}
+
+// With no arguments every running preview is stopped.
+export async function previewStop({ previewId, projectPath } = {}) {
+  return invoke("preview_stop", { previewId, projectPath });
 }